@@ -1,6 +1,6 @@
 from .s2gpp import s2gpp_local_array, s2gpp_local_file, s2gpp_distributed_main, s2gpp_distributed_sub
 from sklearn.base import BaseEstimator
-from typing import Optional
+from typing import Callable, Optional, Union
 from multiprocessing import cpu_count
 from enum import Enum
 import numpy as np
@@ -9,43 +9,101 @@ from pathlib import Path
 
 class Clustering(Enum):
     KDE = "kde"
+    RadiusKDE = "radius-kde"
     MeanShift = "meanshift"
+    DBSCAN = "dbscan"
+
+
+class ScoreNormalization(Enum):
+    Raw = "raw"
+    MinMax = "min-max"
+    ZScore = "z-score"
+    Robust = "robust"
+    Model = "model"
+
+
+class ContributionMetric(Enum):
+    Uniqueness = "uniqueness"
+    Distance = "distance"
+
+
+class ScoreAlignment(Enum):
+    NONE = "none"
+    NaN = "nan"
+    Edge = "edge"
+    MovingAverage = "moving-average"
 
 
 class Series2GraphPP(BaseEstimator):
     def __init__(self,
-                 pattern_length: int,
+                 pattern_length: Union[int, str],
                  latent: Optional[int] = None,
                  rate: int = 100,
+                 n_components: int = 3,
                  query_length: Optional[int] = None,
                  n_threads: int = -1,
                  clustering: Clustering = Clustering.KDE,
-                 # explainability: bool = False,
+                 kde_bandwidth: Union[str, float] = "scott",
+                 kde_resolution: int = 250,
+                 kde_peak_order: int = 1,
+                 score_normalization: ScoreNormalization = ScoreNormalization.MinMax,
+                 score_alignment: ScoreAlignment = ScoreAlignment.NONE,
+                 explainability: bool = False,
+                 contribution_metric: ContributionMetric = ContributionMetric.Uniqueness,
                  self_correction: bool = False,
-                 local_host="127.0.0.1:1992"
+                 local_host="127.0.0.1:1992",
+                 progress: Optional[Callable[[dict], None]] = None
                  ):
         self.pattern_length = pattern_length
-        self.latent = latent or int(self.pattern_length / 3)
+        if pattern_length == "auto":
+            # estimated together with the pattern length from the data
+            self.latent = 0
+            self.query_length = 0
+        else:
+            self.latent = latent or int(self.pattern_length / 3)
+            self.query_length = query_length or self.pattern_length
         self.rate = rate
-        self.query_length = query_length or self.pattern_length
+        self.n_components = n_components
         self.n_threads = n_threads if n_threads > 0 else min(cpu_count() - 1, 1)
         self.clustering = clustering
+        self.kde_bandwidth = kde_bandwidth
+        self.kde_resolution = kde_resolution
+        self.kde_peak_order = kde_peak_order
+        self.score_normalization = score_normalization
+        self.score_alignment = score_alignment
         self.self_correction = self_correction
+        self.explainability = explainability
+        self.contribution_metric = contribution_metric
+        self.anomaly_contribution_: Optional[np.ndarray] = None
         self.local_host = local_host
+        self.progress = progress
+
+    def _pattern_length(self) -> Optional[int]:
+        return None if self.pattern_length == "auto" else self.pattern_length
 
     def fit_predict(self, X: np.ndarray) -> np.ndarray:
         X = X.astype(np.float32)
 
-        return s2gpp_local_array(
+        score, self.anomaly_contribution_ = s2gpp_local_array(
             X,
-            self.pattern_length,
+            self._pattern_length(),
             self.latent,
             self.query_length,
             self.rate,
+            self.n_components,
             self.n_threads,
             self.clustering.value,
-            self.self_correction
+            str(self.kde_bandwidth),
+            self.kde_resolution,
+            self.kde_peak_order,
+            self.score_normalization.value,
+            self.score_alignment.value,
+            self.self_correction,
+            self.explainability,
+            self.contribution_metric.value,
+            self.progress
         )
+        return score
 
 
 class DistributedRole(Enum):
@@ -67,34 +125,52 @@ class DistributedSeries2GraphPP(Series2GraphPP):
         if self._role == DistributedRole.Main:
             s2gpp_distributed_main(
                 str(X),
-                self.pattern_length,
+                self._pattern_length(),
                 self.latent,
                 self.query_length,
                 self.rate,
+                self.n_components,
                 self.n_threads,
                 str(self.output_path),
                 self.column_start,
                 self.column_end,
                 self.clustering.value,
+                str(self.kde_bandwidth),
+                self.kde_resolution,
+                self.kde_peak_order,
+                self.score_normalization.value,
+                self.score_alignment.value,
                 self.self_correction,
+                self.explainability,
+                self.contribution_metric.value,
                 self.local_host,
-                self.n_cluster_nodes
+                self.n_cluster_nodes,
+                self.progress
             )
         else:  # self._role == DistributedRole.Sub:
             s2gpp_distributed_sub(
-                self.pattern_length,
+                self._pattern_length(),
                 self.latent,
                 self.query_length,
                 self.rate,
+                self.n_components,
                 self.n_threads,
                 str(self.output_path),
                 self.column_start,
                 self.column_end,
                 self.clustering.value,
+                str(self.kde_bandwidth),
+                self.kde_resolution,
+                self.kde_peak_order,
+                self.score_normalization.value,
+                self.score_alignment.value,
                 self.self_correction,
+                self.explainability,
+                self.contribution_metric.value,
                 self.local_host,
                 self.n_cluster_nodes,
-                self.mainhost
+                self.mainhost,
+                self.progress
             )
 
     @staticmethod
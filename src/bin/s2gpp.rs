@@ -2,7 +2,7 @@ use log::*;
 use structopt::StructOpt;
 
 use env_logger::Env;
-use s2gpp::{s2gpp, Parameters};
+use s2gpp::{s2gpp, Parameters, Progress};
 use std::io::Write;
 
 fn main() {
@@ -10,11 +10,12 @@ fn main() {
         .format(|buf, record| writeln!(buf, "{} [S2G++]: {}", record.level(), record.args()))
         .init();
 
-    let params: Parameters = Parameters::from_args();
-    if params.explainability && params.n_cluster_nodes > 1 {
-        panic!("The explainability feature is only available in a non-distributed setting.")
-    }
+    let mut params: Parameters = Parameters::from_args();
+    params.progress = Progress::bars();
     debug!("Parameters: {:?}", params);
 
-    s2gpp(params, None).expect("Series2Graph++ did not terminate correctly!");
+    if let Err(e) = s2gpp(params, None) {
+        error!("{}", e);
+        std::process::exit(1);
+    }
 }
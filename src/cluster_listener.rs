@@ -1,5 +1,7 @@
 use crate::parameters::{Parameters, Role};
-use actix::{Actor, ActorContext, Addr, AsyncContext, Context, Handler, Message, System};
+use actix::{
+    Actor, ActorContext, Addr, AsyncContext, Context, Handler, Message, Recipient, System,
+};
 use actix_broker::BrokerSubscribe;
 use actix_telepathy::{prelude::*, Node};
 use log::*;
@@ -7,7 +9,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
 
-use crate::training::{StartTrainingMessage, Training};
+use crate::training::{DetectionResponse, StartTrainingMessage, Training};
 use crate::utils::ClusterNodes;
 
 #[derive(RemoteMessage, Serialize, Deserialize)]
@@ -20,17 +22,23 @@ pub struct ClusterMemberListener {
     connected_nodes: HashSet<Node>,
     main_node: Option<Node>,
     training: Addr<Training>,
+    sink: Option<Recipient<DetectionResponse>>,
     sorted_nodes: HashMap<usize, Node>,
     sorted_addr_buffer: Vec<SocketAddr>,
 }
 
 impl ClusterMemberListener {
-    pub fn new(parameters: Parameters, training: Addr<Training>) -> Self {
+    pub fn new(
+        parameters: Parameters,
+        training: Addr<Training>,
+        sink: Option<Recipient<DetectionResponse>>,
+    ) -> Self {
         Self {
             parameters,
             connected_nodes: HashSet::new(),
             main_node: None,
             training,
+            sink,
             sorted_nodes: HashMap::new(),
             sorted_addr_buffer: vec![],
         }
@@ -61,7 +69,7 @@ impl ClusterMemberListener {
         debug!("#nodes {}", nodes.len_incl_own());
         self.training.do_send(StartTrainingMessage {
             nodes,
-            source: None,
+            source: self.sink.clone(),
             data: None,
         });
     }
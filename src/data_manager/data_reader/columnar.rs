@@ -0,0 +1,316 @@
+use crate::parameters::Parameters;
+use anyhow::{Error, Result};
+use arrow::array::AsArray;
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Float32Type, Schema};
+use arrow::ipc::reader::FileReader;
+use arrow::record_batch::RecordBatch;
+use ndarray::{concatenate, Array2, ArrayView2, Axis};
+use parquet::arrow::arrow_reader::{
+    ArrowReaderMetadata, ArrowReaderOptions, ParquetRecordBatchReaderBuilder,
+};
+use parquet::arrow::ProjectionMask;
+use std::fs::File;
+use std::ops::Range;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum InputFormat {
+    Csv,
+    Parquet,
+    ArrowIpc,
+}
+
+impl InputFormat {
+    /// Guesses the format from the file extension; unknown extensions are read as CSV.
+    pub fn from_path(file_path: &str) -> Self {
+        match Path::new(file_path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_lowercase())
+            .as_deref()
+        {
+            Some("parquet") | Some("pq") => InputFormat::Parquet,
+            Some("arrow") | Some("ipc") | Some("feather") => InputFormat::ArrowIpc,
+            _ => InputFormat::Csv,
+        }
+    }
+}
+
+pub(crate) fn column_position(names: &[String], column: &str) -> Result<usize> {
+    names
+        .iter()
+        .position(|name| name.eq(column))
+        .ok_or_else(|| Error::msg(format!("The input has no column named '{}'!", column)))
+}
+
+/// The indices of the used columns, either given by name or by the column index range. The
+/// timestamp and label columns are never used.
+pub(crate) fn selected_columns(names: &[String], parameters: &Parameters) -> Result<Vec<usize>> {
+    let excluded = parameters
+        .carried_columns()
+        .iter()
+        .map(|column| column_position(names, column))
+        .collect::<Result<Vec<usize>>>()?;
+
+    let columns = if parameters.columns.is_empty() {
+        (parameters.column_start..parameters.until_column(names.len())).collect()
+    } else {
+        parameters
+            .columns
+            .iter()
+            .map(|column| column_position(names, column))
+            .collect::<Result<Vec<usize>>>()?
+    };
+
+    Ok(columns
+        .into_iter()
+        .filter(|column| !excluded.contains(column))
+        .collect())
+}
+
+fn column_names(schema: &Schema) -> Vec<String> {
+    schema
+        .fields()
+        .iter()
+        .map(|field| field.name().to_string())
+        .collect()
+}
+
+/// Converts all columns of the batch to `f32`. Null values become `NaN`.
+fn batch_to_array(batch: &RecordBatch) -> Result<Array2<f32>> {
+    let mut array = Array2::zeros((batch.num_rows(), batch.num_columns()));
+    for (c, column) in batch.columns().iter().enumerate() {
+        let column = cast(column, &DataType::Float32)?;
+        for (r, value) in column.as_primitive::<Float32Type>().iter().enumerate() {
+            array[[r, c]] = value.unwrap_or(f32::NAN);
+        }
+    }
+    Ok(array)
+}
+
+fn read_parquet<S, V>(file_path: &str, select: S, mut visit: V) -> Result<()>
+where
+    S: FnOnce(&[String]) -> Result<Vec<usize>>,
+    V: FnMut(&RecordBatch) -> Result<()>,
+{
+    let file = File::open(file_path)?;
+    let metadata = ArrowReaderMetadata::load(&file, ArrowReaderOptions::default())?;
+    let columns = select(&column_names(metadata.schema()))?;
+
+    // the projection keeps the order of the file, so the selected order is restored per batch
+    let mut projected = columns.clone();
+    projected.sort_unstable();
+    projected.dedup();
+    let order: Vec<usize> = columns
+        .iter()
+        .map(|column| projected.binary_search(column).unwrap())
+        .collect();
+    let mask = ProjectionMask::roots(metadata.parquet_schema(), projected);
+
+    // one row group at a time, so that at most one row group is decoded into memory
+    for row_group in 0..metadata.metadata().num_row_groups() {
+        let reader =
+            ParquetRecordBatchReaderBuilder::new_with_metadata(file.try_clone()?, metadata.clone())
+                .with_projection(mask.clone())
+                .with_row_groups(vec![row_group])
+                .build()?;
+        for batch in reader {
+            visit(&batch?.project(&order)?)?;
+        }
+    }
+    Ok(())
+}
+
+fn read_arrow_ipc<S, V>(file_path: &str, select: S, mut visit: V) -> Result<()>
+where
+    S: FnOnce(&[String]) -> Result<Vec<usize>>,
+    V: FnMut(&RecordBatch) -> Result<()>,
+{
+    let reader = FileReader::try_new(File::open(file_path)?, None)?;
+    let columns = select(&column_names(&reader.schema()))?;
+
+    for batch in reader {
+        visit(&batch?.project(&columns)?)?;
+    }
+    Ok(())
+}
+
+/// Reads the columns chosen by `select` batch by batch and hands every batch to `visit`.
+fn visit_batches<S, V>(file_path: &str, format: &InputFormat, select: S, visit: V) -> Result<()>
+where
+    S: FnOnce(&[String]) -> Result<Vec<usize>>,
+    V: FnMut(&RecordBatch) -> Result<()>,
+{
+    match format {
+        InputFormat::Parquet => read_parquet(file_path, select, visit),
+        InputFormat::ArrowIpc => read_arrow_ipc(file_path, select, visit),
+        InputFormat::Csv => Err(Error::msg(format!(
+            "'{}' is not a columnar file!",
+            file_path
+        ))),
+    }
+}
+
+/// Reads the columns chosen by `select` from every record batch and converts them with `convert`.
+fn read_batches<S, C, T>(
+    file_path: &str,
+    format: &InputFormat,
+    select: S,
+    convert: C,
+) -> Result<Vec<T>>
+where
+    S: FnOnce(&[String]) -> Result<Vec<usize>>,
+    C: Fn(&RecordBatch) -> Result<T>,
+{
+    let mut batches = vec![];
+    visit_batches(file_path, format, select, |batch| {
+        batches.push(convert(batch)?);
+        Ok(())
+    })?;
+    Ok(batches)
+}
+
+/// The number of rows and columns of a Parquet or Arrow IPC file.
+pub(crate) fn columnar_shape(file_path: &str, format: &InputFormat) -> Result<(usize, usize)> {
+    match format {
+        InputFormat::Parquet => {
+            let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(file_path)?)?;
+            Ok((
+                builder.metadata().file_metadata().num_rows() as usize,
+                builder.schema().fields().len(),
+            ))
+        }
+        _ => {
+            let reader = FileReader::try_new(File::open(file_path)?, None)?;
+            let n_columns = reader.schema().fields().len();
+            let mut n_rows = 0;
+            for batch in reader {
+                n_rows += batch?.num_rows();
+            }
+            Ok((n_rows, n_columns))
+        }
+    }
+}
+
+/// The names of all columns of a Parquet or Arrow IPC file.
+pub(crate) fn columnar_column_names(file_path: &str, format: &InputFormat) -> Result<Vec<String>> {
+    match format {
+        InputFormat::Parquet => {
+            let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(file_path)?)?;
+            Ok(column_names(builder.schema()))
+        }
+        _ => {
+            let reader = FileReader::try_new(File::open(file_path)?, None)?;
+            Ok(column_names(&reader.schema()))
+        }
+    }
+}
+
+/// Reads a Parquet or Arrow IPC file into one array per record batch, keeping only the selected
+/// columns.
+pub(crate) fn read_columnar_batches(
+    file_path: &str,
+    format: &InputFormat,
+    parameters: &Parameters,
+) -> Result<Vec<Array2<f32>>> {
+    read_batches(
+        file_path,
+        format,
+        |names| selected_columns(names, parameters),
+        batch_to_array,
+    )
+}
+
+/// Hands the selected columns of every record batch to `visit` without keeping the batches, so
+/// that a file can be partitioned without holding all of it in memory.
+pub(crate) fn visit_columnar_batches<V>(
+    file_path: &str,
+    format: &InputFormat,
+    parameters: &Parameters,
+    mut visit: V,
+) -> Result<()>
+where
+    V: FnMut(Array2<f32>) -> Result<()>,
+{
+    visit_batches(
+        file_path,
+        format,
+        |names| selected_columns(names, parameters),
+        |batch| visit(batch_to_array(batch)?),
+    )
+}
+
+/// Reads the given columns as strings, e.g. timestamps or labels.
+pub(crate) fn read_columnar_strings(
+    file_path: &str,
+    format: &InputFormat,
+    columns: &[String],
+) -> Result<Vec<Vec<String>>> {
+    let batches = read_batches(
+        file_path,
+        format,
+        |names| {
+            columns
+                .iter()
+                .map(|column| column_position(names, column))
+                .collect()
+        },
+        |batch| {
+            batch
+                .columns()
+                .iter()
+                .map(|column| {
+                    Ok(cast(column, &DataType::Utf8)?
+                        .as_string::<i32>()
+                        .iter()
+                        .map(|value| value.unwrap_or_default().to_string())
+                        .collect::<Vec<String>>())
+                })
+                .collect::<Result<Vec<Vec<String>>>>()
+        },
+    )?;
+
+    let mut values = vec![vec![]; columns.len()];
+    for batch in batches {
+        for (column_values, batch_values) in values.iter_mut().zip(batch) {
+            column_values.extend(batch_values);
+        }
+    }
+    Ok(values)
+}
+
+pub(crate) fn read_columnar(
+    file_path: &str,
+    format: &InputFormat,
+    parameters: &Parameters,
+) -> Result<Array2<f32>> {
+    let batches = read_columnar_batches(file_path, format, parameters)?;
+    if batches.is_empty() {
+        return Err(Error::msg(format!("'{}' contains no rows!", file_path)));
+    }
+    let views: Vec<ArrayView2<f32>> = batches.iter().map(|batch| batch.view()).collect();
+    Ok(concatenate(Axis(0), &views)?)
+}
+
+/// The rows each of `n_partitions` cluster nodes receives. Like the CSV partitioning, every
+/// partition but the last overlaps the next one by `overlap` rows and the last one takes the
+/// remainder.
+pub(crate) fn partition_ranges(
+    n_rows: usize,
+    n_partitions: usize,
+    overlap: usize,
+) -> Vec<Range<usize>> {
+    let partition_len = n_rows / n_partitions;
+    (0..n_partitions)
+        .map(|i| {
+            let start = i * partition_len;
+            let end = if i + 1 == n_partitions {
+                n_rows
+            } else {
+                ((i + 1) * partition_len + overlap).min(n_rows)
+            };
+            start..end
+        })
+        .collect()
+}
@@ -3,9 +3,40 @@ use actix_telepathy::prelude::*;
 use ndarray::Array2;
 use serde::{Deserialize, Serialize};
 
+/// A row partition of the input, whose columns have already been selected. The values are sent as
+/// raw little-endian `f32` bytes, because serializing every value on its own dominates the
+/// loading time of large datasets.
 #[derive(RemoteMessage, Serialize, Deserialize)]
 pub struct DataPartitionMessage {
-    pub data: Vec<Vec<String>>,
+    pub n_columns: usize,
+    #[serde(with = "serde_bytes")]
+    pub values: Vec<u8>,
+}
+
+impl DataPartitionMessage {
+    pub fn from_rows(rows: &[Vec<f32>], n_columns: usize) -> Self {
+        Self {
+            n_columns,
+            values: rows
+                .iter()
+                .flatten()
+                .flat_map(|value| value.to_le_bytes())
+                .collect(),
+        }
+    }
+
+    pub fn into_array(self) -> Array2<f32> {
+        let values: Vec<f32> = self
+            .values
+            .chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect();
+        Array2::from_shape_vec(
+            (values.len() / self.n_columns.max(1), self.n_columns),
+            values,
+        )
+        .expect("Could not deserialize sent data")
+    }
 }
 
 #[derive(Message)]
@@ -1,18 +1,25 @@
+pub(crate) mod columnar;
 pub(crate) mod messages;
 
 pub use messages::DataReceivedMessage;
 
-use csv::{ReaderBuilder, Trim};
+use csv::{Reader, ReaderBuilder, Trim};
 use ndarray::prelude::*;
 
+use crate::data_manager::data_reader::columnar::{
+    column_position, columnar_column_names, columnar_shape, partition_ranges, read_columnar,
+    read_columnar_strings, selected_columns, visit_columnar_batches, InputFormat,
+};
 pub use crate::data_manager::data_reader::messages::DataPartitionMessage;
 use actix::{Actor, Addr};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::str::FromStr;
 
-use crate::data_manager::DataManager;
-use crate::utils::AnyClusterNodesIterator;
+use crate::data_manager::{DataManager, MissingValueFilter};
+use crate::parameters::Parameters;
+use crate::utils::{AnyClusterNodesIterator, Phase};
+use anyhow::{Error, Result};
 use std::ops::Not;
 
 use log::*;
@@ -23,82 +30,297 @@ pub struct DataReading {
 }
 
 pub trait DataReader {
-    fn read_csv(&mut self, file_path: &str, addr: Addr<Self>)
+    fn read_csv(&mut self, file_path: &str, addr: Addr<Self>) -> Result<()>
+    where
+        Self: Actor;
+    fn read_columnar(
+        &mut self,
+        file_path: &str,
+        format: &InputFormat,
+        addr: Addr<Self>,
+    ) -> Result<()>
     where
         Self: Actor;
 }
 
 impl DataReader for DataManager {
-    fn read_csv(&mut self, file_path: &str, addr: Addr<Self>) {
-        let file = File::open(file_path).unwrap();
+    fn read_csv(&mut self, file_path: &str, addr: Addr<Self>) -> Result<()> {
+        let file = File::open(file_path)?;
         let count_reader = BufReader::new(file);
         let n_lines = if self.data_reading.as_ref().unwrap().with_header {
-            count_reader.lines().count() - 1
+            count_reader.lines().count().saturating_sub(1)
         } else {
             count_reader.lines().count()
         };
+        self.parameters.progress.start(Phase::ReadingData, n_lines);
 
         let mut nodes = self.cluster_nodes.clone();
         nodes.change_ids("DataManager");
         let receivers = nodes.to_any(addr);
-        let file = File::open(&file_path).unwrap();
-        let mut reader = ReaderBuilder::new()
-            .has_headers(true)
-            .trim(Trim::All)
-            .from_reader(file);
+        let mut reader = csv_reader(file_path, &self.parameters)?;
+        let columns = selected_columns(
+            &csv_column_names(&mut reader, &self.parameters)?,
+            &self.parameters,
+        )?;
 
         let partition_len = num_integer::Integer::div_floor(&n_lines, &receivers.len());
         let last_overlap = n_lines - (partition_len * receivers.len());
         let mut receiver_iterator: AnyClusterNodesIterator<Self> = receivers.clone().into_iter();
         let mut buffer = vec![];
         let mut overlap_buffer = vec![];
+        // the missing values are handled before partitioning, so that fills see the whole series
+        let mut filter = MissingValueFilter::new(self.parameters.missing_values.clone());
+        let mut records = reader.records();
+        let mut finished = false;
 
-        for record in reader.records() {
-            match record {
-                Ok(r) => {
-                    let strings = r.iter().map(|x| x.to_string()).collect();
-                    if buffer.len() < partition_len {
-                        buffer.push(strings);
-                    } else if receivers.len() > 1
-                        && ((overlap_buffer.len() < self.data_reading.as_ref().unwrap().overlap
-                            && receiver_iterator.last_position().not())
-                            || (overlap_buffer.len() < last_overlap
-                                && receiver_iterator.last_position()))
-                    {
-                        overlap_buffer.push(strings);
-                    } else {
-                        let mut data = buffer.clone();
-                        data.extend(overlap_buffer.clone());
-                        receiver_iterator
-                            .next()
-                            .unwrap()
-                            .do_send(DataPartitionMessage { data });
-                        debug!(
-                            "Sent data to receiver {}",
-                            receiver_iterator.get_position() - 1
-                        );
-
-                        buffer.clear();
-                        buffer.extend(overlap_buffer.clone());
-                        buffer.push(strings);
-                        overlap_buffer.clear();
-                    }
+        while !finished {
+            let complete_rows = match records.next() {
+                Some(record) => {
+                    let r = record?;
+                    let values: Vec<f32> = columns
+                        .iter()
+                        .map(|column| {
+                            parse_value(&r[*column]).map_err(|_| {
+                                Error::msg(format!("'{}' is not a number!", &r[*column]))
+                            })
+                        })
+                        .collect::<Result<Vec<f32>>>()?;
+                    self.parameters.progress.advance(Phase::ReadingData, 1);
+                    filter.push(values)?
+                }
+                None => {
+                    finished = true;
+                    filter.finish()?
+                }
+            };
+            for values in complete_rows {
+                if buffer.len() < partition_len {
+                    buffer.push(values);
+                } else if receivers.len() > 1
+                    && ((overlap_buffer.len() < self.data_reading.as_ref().unwrap().overlap
+                        && receiver_iterator.last_position().not())
+                        || (overlap_buffer.len() < last_overlap
+                            && receiver_iterator.last_position()))
+                {
+                    overlap_buffer.push(values);
+                } else {
+                    let mut rows = buffer.clone();
+                    rows.extend(overlap_buffer.clone());
+                    receiver_iterator
+                        .next()
+                        .unwrap()
+                        .do_send(DataPartitionMessage::from_rows(&rows, columns.len()));
+                    debug!(
+                        "Sent data to receiver {}",
+                        receiver_iterator.get_position() - 1
+                    );
+
+                    buffer.clear();
+                    buffer.extend(overlap_buffer.clone());
+                    buffer.push(values);
+                    overlap_buffer.clear();
                 }
-                Err(e) => panic!("{}", e.to_string()),
             }
         }
 
-        let mut data = buffer.clone();
-        data.extend(overlap_buffer.clone());
+        let mut rows = buffer.clone();
+        rows.extend(overlap_buffer.clone());
         receiver_iterator
             .next()
             .unwrap()
-            .do_send(DataPartitionMessage { data });
+            .do_send(DataPartitionMessage::from_rows(&rows, columns.len()));
         debug!(
             "Sent data to receiver {}",
             receiver_iterator.get_position() - 1
         );
+        Ok(())
+    }
+
+    fn read_columnar(
+        &mut self,
+        file_path: &str,
+        format: &InputFormat,
+        addr: Addr<Self>,
+    ) -> Result<()> {
+        let (n_rows, _) = columnar_shape(file_path, format)?;
+        let n_columns =
+            selected_columns(&columnar_column_names(file_path, format)?, &self.parameters)?.len();
+        let progress = self.parameters.progress.clone();
+        progress.start(Phase::ReadingData, n_rows);
+
+        let mut nodes = self.cluster_nodes.clone();
+        nodes.change_ids("DataManager");
+        let receivers = nodes.to_any(addr);
+        let overlap = self.data_reading.as_ref().unwrap().overlap;
+        let mut receiver_iterator: AnyClusterNodesIterator<Self> = receivers.clone().into_iter();
+        let ranges = partition_ranges(n_rows, receivers.len(), overlap);
+        let mut next_range = 0;
+
+        // the missing values are handled before partitioning, so that fills see the whole series
+        let mut filter = MissingValueFilter::new(self.parameters.missing_values.clone());
+        // the rows from `first_row` on that are still needed by the unsent partitions
+        let mut rows: Vec<Vec<f32>> = vec![];
+        let mut first_row = 0;
+
+        visit_columnar_batches(file_path, format, &self.parameters, |batch| {
+            for row in batch.outer_iter() {
+                rows.extend(filter.push(row.to_vec())?);
+            }
+            progress.advance(Phase::ReadingData, batch.nrows());
+
+            // all partitions but the last one are sent as soon as their rows are read
+            while next_range + 1 < ranges.len() && first_row + rows.len() >= ranges[next_range].end
+            {
+                let range = &ranges[next_range];
+                send_partition(
+                    &mut receiver_iterator,
+                    &rows[range.start - first_row..range.end - first_row],
+                    n_columns,
+                );
+                next_range += 1;
+                rows.drain(..ranges[next_range].start - first_row);
+                first_row = ranges[next_range].start;
+            }
+            Ok(())
+        })?;
+
+        rows.extend(filter.finish()?);
+        send_partition(&mut receiver_iterator, &rows, n_columns);
+        Ok(())
+    }
+}
+
+fn send_partition(
+    receiver_iterator: &mut AnyClusterNodesIterator<DataManager>,
+    rows: &[Vec<f32>],
+    n_columns: usize,
+) {
+    receiver_iterator
+        .next()
+        .unwrap()
+        .do_send(DataPartitionMessage::from_rows(rows, n_columns));
+    debug!(
+        "Sent data to receiver {}",
+        receiver_iterator.get_position() - 1
+    );
+}
+
+/// Reads a whole CSV, Parquet or Arrow IPC file on the calling thread, keeping only the columns
+/// selected by the parameters.
+pub fn read_data(file_path: &str, parameters: &Parameters) -> Result<Array2<f32>> {
+    let format = InputFormat::from_path(file_path);
+    if format.ne(&InputFormat::Csv) {
+        return read_columnar(file_path, &format, parameters);
+    }
+
+    let mut reader = csv_reader(file_path, parameters)?;
+    let columns = selected_columns(&csv_column_names(&mut reader, parameters)?, parameters)?;
+
+    let mut flat_data = vec![];
+    for record in reader.records() {
+        let record = record?;
+        for column in columns.iter() {
+            flat_data.push(parse_value(&record[*column])?);
+        }
+    }
+
+    Ok(Array2::from_shape_vec(
+        (flat_data.len() / columns.len().max(1), columns.len()),
+        flat_data,
+    )?)
+}
+
+/// The number of rows and columns of the file, before any column is selected.
+pub fn data_shape(file_path: &str, parameters: &Parameters) -> Result<(usize, usize)> {
+    let format = InputFormat::from_path(file_path);
+    if format.ne(&InputFormat::Csv) {
+        return columnar_shape(file_path, &format);
+    }
+
+    let mut reader = csv_reader(file_path, parameters)?;
+    let n_columns = reader.headers()?.len();
+    let mut n_rows = 0;
+    for record in reader.records() {
+        record?;
+        n_rows += 1;
+    }
+    Ok((n_rows, n_columns))
+}
+
+/// Parses a CSV cell. Empty cells are missing values and become `NaN`.
+fn parse_value(value: &str) -> Result<f32> {
+    if value.is_empty() {
+        Ok(f32::NAN)
+    } else {
+        Ok(f32::from_str(value)?)
+    }
+}
+
+/// A CSV reader with the dialect (delimiter, quoting and header) given by the parameters.
+pub(crate) fn csv_reader(file_path: &str, parameters: &Parameters) -> Result<Reader<File>> {
+    Ok(ReaderBuilder::new()
+        .has_headers(!parameters.no_header)
+        .delimiter(parameters.delimiter)
+        .quote(parameters.quote)
+        .quoting(!parameters.no_quoting)
+        .trim(Trim::All)
+        .from_path(file_path)?)
+}
+
+/// The column names of the CSV header. Without a header, the columns are named by their index.
+pub(crate) fn csv_column_names(
+    reader: &mut Reader<File>,
+    parameters: &Parameters,
+) -> Result<Vec<String>> {
+    let header = reader.headers()?;
+    if parameters.no_header {
+        Ok((0..header.len()).map(|i| i.to_string()).collect())
+    } else {
+        Ok(header.iter().map(|name| name.to_string()).collect())
+    }
+}
+
+/// The names of the columns selected by the parameters, in the order of the input dimensions.
+pub(crate) fn read_column_names(file_path: &str, parameters: &Parameters) -> Result<Vec<String>> {
+    let format = InputFormat::from_path(file_path);
+    let names = if format.ne(&InputFormat::Csv) {
+        columnar_column_names(file_path, &format)?
+    } else {
+        csv_column_names(&mut csv_reader(file_path, parameters)?, parameters)?
+    };
+    Ok(selected_columns(&names, parameters)?
+        .into_iter()
+        .map(|column| names[column].clone())
+        .collect())
+}
+
+/// Reads the timestamp and label columns as strings, in the order of
+/// `Parameters::carried_columns`.
+pub(crate) fn read_carried_columns(
+    file_path: &str,
+    parameters: &Parameters,
+) -> Result<Vec<Vec<String>>> {
+    let carried_columns = parameters.carried_columns();
+    let format = InputFormat::from_path(file_path);
+    if format.ne(&InputFormat::Csv) {
+        return read_columnar_strings(file_path, &format, &carried_columns);
+    }
+
+    let mut reader = csv_reader(file_path, parameters)?;
+    let names = csv_column_names(&mut reader, parameters)?;
+    let columns = carried_columns
+        .iter()
+        .map(|column| column_position(&names, column))
+        .collect::<Result<Vec<usize>>>()?;
+
+    let mut values = vec![vec![]; columns.len()];
+    for record in reader.records() {
+        let record = record?;
+        for (column_values, column) in values.iter_mut().zip(columns.iter()) {
+            column_values.push(record[*column].to_string());
+        }
     }
+    Ok(values)
 }
 
 #[allow(dead_code)]
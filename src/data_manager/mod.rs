@@ -1,27 +1,28 @@
 use actix::{Actor, ActorContext, Addr, AsyncContext, Context, Handler, Recipient};
-use std::cmp::Ordering;
 
-use ndarray::{Array1, Array2, Array3, Dim};
+use ndarray::{Array2, Array3};
 
+use crate::data_manager::data_reader::columnar::InputFormat;
 use crate::data_manager::data_reader::{DataPartitionMessage, DataReader, DataReading};
 pub use crate::data_manager::messages::{DataLoadedAndProcessed, LoadDataMessage};
+pub use crate::data_manager::preprocessor::MissingValues;
+pub(crate) use crate::data_manager::preprocessor::{handle_missing_values, MissingValueFilter};
 use crate::data_manager::preprocessor::{Preprocessing, PreprocessingDoneMessage, Preprocessor};
 use crate::parameters::{Parameters, Role};
 use actix_telepathy::prelude::*;
 
 use crate::data_manager::data_reader::messages::LocalReadDataMessage;
-use crate::data_manager::phase_spacer::PhaseSpacer;
+pub(crate) use crate::data_manager::phase_spacer::PhaseSpacer;
 use crate::data_manager::reference_dataset_builder::ReferenceDatasetBuilder;
 pub use crate::data_manager::stats_collector::DatasetStats;
 use crate::data_manager::stats_collector::{
     MinMaxCalculation, MinMaxCalculator, MinMaxDoneMessage, MinMaxNodeMessage, StdCalculation,
     StdCalculator, StdDoneMessage, StdNodeMessage,
 };
-use crate::messages::PoisonPill;
-use crate::utils::itertools::FromToAble;
-use crate::utils::{ClusterNodes, ConsoleLogger};
+use crate::error::S2GppError;
+use crate::messages::{PoisonPill, TrainingFailed};
+use crate::utils::ClusterNodes;
 use log::*;
-use std::str::FromStr;
 
 pub mod data_reader;
 mod messages;
@@ -49,6 +50,7 @@ pub struct DataManager {
     std_calculation: Option<StdCalculation>,
     preprocessing: Option<Preprocessing>,
     receiver: Recipient<DataLoadedAndProcessed>,
+    failure: Recipient<TrainingFailed>,
     dataset_stats: DatasetStats,
     reference_dataset: Option<Array3<f32>>,
     phase_space: Option<Array3<f32>>,
@@ -60,6 +62,7 @@ impl DataManager {
         mut nodes: ClusterNodes,
         parameters: Parameters,
         receiver: Recipient<DataLoadedAndProcessed>,
+        failure: Recipient<TrainingFailed>,
     ) -> Self {
         nodes.change_ids("DataManager");
 
@@ -72,6 +75,7 @@ impl DataManager {
             std_calculation: None,
             preprocessing: None,
             receiver,
+            failure,
             dataset_stats: DatasetStats::default(),
             reference_dataset: None,
             phase_space: None,
@@ -86,6 +90,14 @@ impl DataManager {
         }
     }
 
+    fn fail(&mut self, error: S2GppError, ctx: &mut Context<Self>) {
+        self.failure.do_send(TrainingFailed {
+            error,
+            cluster_node: None,
+        });
+        ctx.stop();
+    }
+
     fn calculate_datastats(&mut self, addr: Addr<Self>) {
         self.minmax_calculation = Some(MinMaxCalculation {
             nodes: vec![],
@@ -94,7 +106,7 @@ impl DataManager {
         });
         self.std_calculation = Some(StdCalculation {
             nodes: vec![],
-            n: None,
+            counts: None,
             mean: None,
             m2: None,
         });
@@ -110,7 +122,7 @@ impl DataManager {
     }
 
     fn preprocess(&mut self, addr: Addr<Self>) {
-        ConsoleLogger::new(3, 12, "Preprocessing Data".to_string()).print();
+        self.parameters.progress.step(3, 12, "Preprocessing Data");
         match &self.data {
             Some(data) => {
                 self.preprocessing = Some(Preprocessing::new(
@@ -162,10 +174,10 @@ impl Handler<LoadDataMessage> for DataManager {
     type Result = ();
 
     fn handle(&mut self, msg: LoadDataMessage, ctx: &mut Self::Context) -> Self::Result {
-        ConsoleLogger::new(1, 12, "Reading Data".to_string()).print();
+        self.parameters.progress.step(1, 12, "Reading Data");
         self.cluster_nodes = msg.nodes;
         self.data_reading = Some(DataReading {
-            with_header: true,
+            with_header: !self.parameters.no_header,
             overlap: self.parameters.pattern_length - 1,
         });
 
@@ -174,7 +186,13 @@ impl Handler<LoadDataMessage> for DataManager {
             data_path: Some(data_path),
         } = role
         {
-            self.read_csv(&data_path, ctx.address())
+            let read = match InputFormat::from_path(&data_path) {
+                InputFormat::Csv => self.read_csv(&data_path, ctx.address()),
+                format => self.read_columnar(&data_path, &format, ctx.address()),
+            };
+            if let Err(e) = read {
+                self.fail(S2GppError::reading(&data_path, e), ctx);
+            }
         } else {
             self.resolve_buffer(ctx.address())
         }
@@ -193,33 +211,11 @@ impl Handler<DataPartitionMessage> for DataManager {
 
         debug!("all nodes are now connected");
 
-        ConsoleLogger::new(2, 12, "Calculating Data Stats".to_string()).print();
-        let n_rows = msg.data.len();
-        let until_column = match self.parameters.column_end.cmp(&0) {
-            Ordering::Equal => msg.data[0].len(),
-            Ordering::Greater => self.parameters.column_end as usize,
-            Ordering::Less => (msg.data[0].len() as isize + self.parameters.column_end) as usize,
-        };
-
-        let n_columns = until_column - self.parameters.column_start;
-
-        let flat_data: Array1<f32> = msg
-            .data
-            .into_iter()
-            .flat_map(|rec| {
-                rec.iter()
-                    .fromto(self.parameters.column_start, until_column)
-                    .map(|b| f32::from_str(b).unwrap())
-                    .collect::<Vec<f32>>()
-            })
-            .collect();
-
-        self.data = Some(
-            flat_data
-                .into_shape(Dim([n_rows, n_columns]))
-                .expect("Could not deserialize sent data"),
-        );
-
+        self.parameters
+            .progress
+            .step(2, 12, "Calculating Data Stats");
+        // the reader has already applied the missing value policy to the whole series
+        self.data = Some(msg.into_array());
         self.calculate_datastats(ctx.address());
     }
 }
@@ -228,8 +224,13 @@ impl Handler<LocalReadDataMessage> for DataManager {
     type Result = ();
 
     fn handle(&mut self, msg: LocalReadDataMessage, ctx: &mut Self::Context) -> Self::Result {
-        self.data = Some(msg.data);
-        self.calculate_datastats(ctx.address());
+        match handle_missing_values(msg.data, &self.parameters.missing_values) {
+            Ok(data) => {
+                self.data = Some(data);
+                self.calculate_datastats(ctx.address());
+            }
+            Err(e) => self.fail(S2GppError::from_data_error(e), ctx),
+        }
     }
 }
 
@@ -257,9 +258,9 @@ impl Handler<PreprocessingDoneMessage> for DataManager {
     type Result = ();
 
     fn handle(&mut self, _msg: PreprocessingDoneMessage, _ctx: &mut Self::Context) -> Self::Result {
-        ConsoleLogger::new(4, 12, "Building Reference Dataset".to_string()).print();
+        self.parameters.progress.step(4, 12, "Building Reference Dataset");
         self.build_reference_dataset();
-        ConsoleLogger::new(5, 12, "Building Phase Space".to_string()).print();
+        self.parameters.progress.step(5, 12, "Building Phase Space");
         self.build_phase_space();
         self.finalize();
     }
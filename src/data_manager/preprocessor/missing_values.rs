@@ -0,0 +1,212 @@
+use crate::error::S2GppError;
+use anyhow::Result;
+use itertools::Itertools;
+use ndarray::Array2;
+use std::str::FromStr;
+
+const MAX_REPORTED_CELLS: usize = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MissingValues {
+    Reject,
+    ForwardFill,
+    Interpolate,
+    Drop,
+}
+
+impl FromStr for MissingValues {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reject" => Ok(MissingValues::Reject),
+            "ffill" => Ok(MissingValues::ForwardFill),
+            "interpolate" => Ok(MissingValues::Interpolate),
+            "drop" => Ok(MissingValues::Drop),
+            _ => Err(format!(
+                "{} is not a valid missing value policy! Allowed values are: 'reject', 'ffill', 'interpolate' and 'drop'",
+                s
+            )),
+        }
+    }
+}
+
+/// Removes all `NaN` values from `data` according to the `policy`.
+pub(crate) fn handle_missing_values(
+    data: Array2<f32>,
+    policy: &MissingValues,
+) -> Result<Array2<f32>> {
+    if !data.iter().any(|x| x.is_nan()) {
+        return Ok(data);
+    }
+
+    let n_columns = data.ncols();
+    let mut filter = MissingValueFilter::new(policy.clone());
+    let mut rows = vec![];
+    for row in data.rows() {
+        rows.extend(filter.push(row.to_vec())?);
+    }
+    rows.extend(filter.finish()?);
+    Ok(Array2::from_shape_vec(
+        (rows.len(), n_columns),
+        rows.concat(),
+    )?)
+}
+
+/// Applies a missing value policy to rows that are read one after another, so that a series that
+/// is split into partitions afterwards is handled as a whole. Rows whose missing values depend on
+/// later rows are held back until these arrive.
+pub(crate) struct MissingValueFilter {
+    policy: MissingValues,
+    n_rows: usize,
+    n_kept: usize,
+    /// the last known value of each column before the held back rows
+    last: Vec<Option<f32>>,
+    pending: Vec<Vec<f32>>,
+    missing_cells: Vec<(usize, usize)>,
+    n_missing: usize,
+}
+
+impl MissingValueFilter {
+    pub fn new(policy: MissingValues) -> Self {
+        Self {
+            policy,
+            n_rows: 0,
+            n_kept: 0,
+            last: vec![],
+            pending: vec![],
+            missing_cells: vec![],
+            n_missing: 0,
+        }
+    }
+
+    /// Adds the next row and returns the rows that are complete now. With `reject`, no row is
+    /// returned after the first missing value.
+    pub fn push(&mut self, row: Vec<f32>) -> Result<Vec<Vec<f32>>> {
+        let r = self.n_rows;
+        self.n_rows += 1;
+        if self.last.is_empty() {
+            self.last = vec![None; row.len()];
+        }
+
+        match self.policy {
+            MissingValues::Reject => {
+                for (c, _) in row.iter().enumerate().filter(|(_, x)| x.is_nan()) {
+                    if self.missing_cells.len() < MAX_REPORTED_CELLS {
+                        self.missing_cells.push((r, c));
+                    }
+                    self.n_missing += 1;
+                }
+                if self.n_missing > 0 {
+                    return Ok(vec![]);
+                }
+                Ok(self.keep(vec![row]))
+            }
+            MissingValues::Drop => {
+                if row.iter().any(|x| x.is_nan()) {
+                    return Ok(vec![]);
+                }
+                Ok(self.keep(vec![row]))
+            }
+            MissingValues::ForwardFill | MissingValues::Interpolate => {
+                self.pending.push(row);
+                if !self.is_resolvable() {
+                    return Ok(vec![]);
+                }
+                let rows = self.resolve()?;
+                Ok(self.keep(rows))
+            }
+        }
+    }
+
+    /// Returns the rows that are still held back, or the error of the policy.
+    pub fn finish(&mut self) -> Result<Vec<Vec<f32>>> {
+        match self.policy {
+            MissingValues::Reject if self.n_missing > 0 => {
+                Err(missing_values_error(&self.missing_cells, self.n_missing).into())
+            }
+            MissingValues::Drop if self.n_kept == 0 && self.n_rows > 0 => Err(
+                S2GppError::InvalidData("Every row contains a missing value!".to_string()).into(),
+            ),
+            MissingValues::ForwardFill | MissingValues::Interpolate if !self.pending.is_empty() => {
+                let rows = self.resolve()?;
+                Ok(self.keep(rows))
+            }
+            _ => Ok(vec![]),
+        }
+    }
+
+    fn keep(&mut self, rows: Vec<Vec<f32>>) -> Vec<Vec<f32>> {
+        self.n_kept += rows.len();
+        if let Some(last_row) = rows.last() {
+            for (last, x) in self.last.iter_mut().zip(last_row.iter()) {
+                *last = Some(*x);
+            }
+        }
+        rows
+    }
+
+    /// Whether every missing value of the held back rows can be filled without later rows.
+    fn is_resolvable(&self) -> bool {
+        match self.policy {
+            MissingValues::ForwardFill => (0..self.last.len())
+                .all(|c| self.last[c].is_some() || self.pending.iter().any(|row| !row[c].is_nan())),
+            _ => matches!(self.pending.last(), Some(row) if !row.iter().any(|x| x.is_nan())),
+        }
+    }
+
+    /// Fills the missing values of the held back rows. `ffill` repeats the last preceding value,
+    /// `interpolate` interpolates linearly between the neighbouring values. Missing values without
+    /// a preceding or a following value take the closest value.
+    fn resolve(&mut self) -> Result<Vec<Vec<f32>>> {
+        let mut rows = std::mem::take(&mut self.pending);
+        for c in 0..self.last.len() {
+            let mut next = vec![None; rows.len()];
+            let mut following = None;
+            for i in (0..rows.len()).rev() {
+                next[i] = following;
+                if !rows[i][c].is_nan() {
+                    following = Some((i as isize, rows[i][c]));
+                }
+            }
+
+            // the last known value belongs to the row right before the held back rows
+            let mut previous = self.last[c].map(|x| (-1_isize, x));
+            for i in 0..rows.len() {
+                if !rows[i][c].is_nan() {
+                    previous = Some((i as isize, rows[i][c]));
+                    continue;
+                }
+                rows[i][c] = match (&self.policy, previous, next[i]) {
+                    (MissingValues::Interpolate, Some((p_i, p)), Some((n_i, n))) => {
+                        p + (n - p) * (i as isize - p_i) as f32 / (n_i - p_i) as f32
+                    }
+                    (_, Some((_, p)), _) => p,
+                    (_, None, Some((_, n))) => n,
+                    (_, None, None) => {
+                        return Err(S2GppError::InvalidData(format!(
+                            "Column {} contains only missing values!",
+                            c
+                        ))
+                        .into())
+                    }
+                };
+            }
+        }
+        Ok(rows)
+    }
+}
+
+fn missing_values_error(cells: &[(usize, usize)], n_missing: usize) -> S2GppError {
+    let mut listed = cells
+        .iter()
+        .map(|(r, c)| format!("row {} column {}", r, c))
+        .join(", ");
+    if n_missing > cells.len() {
+        listed.push_str(&format!(" and {} more", n_missing - cells.len()));
+    }
+    S2GppError::InvalidData(format!(
+        "The data contains missing values at {}! Choose another missing value policy to fill or drop them.",
+        listed
+    ))
+}
@@ -1,9 +1,16 @@
 mod helper;
 mod messages;
+mod missing_values;
+#[cfg(test)]
+mod tests;
 
 pub use crate::data_manager::preprocessor::messages::{
     PreprocessColumnMessage, PreprocessingDoneMessage, ProcessedColumnMessage,
 };
+pub(crate) use crate::data_manager::preprocessor::missing_values::{
+    handle_missing_values, MissingValueFilter,
+};
+pub use crate::data_manager::preprocessor::missing_values::MissingValues;
 use actix::{Addr, AsyncContext, Handler, SyncArbiter};
 use ndarray::ArcArray2;
 
@@ -0,0 +1,86 @@
+use crate::data_manager::preprocessor::{handle_missing_values, MissingValueFilter, MissingValues};
+use ndarray::{arr2, Array2};
+use std::str::FromStr;
+
+fn data_with_gaps() -> Array2<f32> {
+    arr2(&[
+        [f32::NAN, 1.0],
+        [2.0, f32::NAN],
+        [f32::NAN, f32::NAN],
+        [6.0, 4.0],
+        [f32::NAN, 5.0],
+    ])
+}
+
+#[test]
+fn rejects_missing_values_with_position() {
+    let error = handle_missing_values(data_with_gaps(), &MissingValues::Reject).unwrap_err();
+    assert!(error.to_string().contains("row 0 column 0, row 1 column 1"));
+
+    let complete = arr2(&[[1.0, 2.0], [3.0, 4.0]]);
+    assert_eq!(
+        handle_missing_values(complete.clone(), &MissingValues::Reject).unwrap(),
+        complete
+    );
+}
+
+#[test]
+fn forward_fills_missing_values() {
+    let data = handle_missing_values(data_with_gaps(), &MissingValues::ForwardFill).unwrap();
+    assert_eq!(
+        data,
+        arr2(&[[2.0, 1.0], [2.0, 1.0], [2.0, 1.0], [6.0, 4.0], [6.0, 5.0]])
+    );
+}
+
+#[test]
+fn interpolates_missing_values() {
+    let data = handle_missing_values(data_with_gaps(), &MissingValues::Interpolate).unwrap();
+    assert_eq!(
+        data,
+        arr2(&[[2.0, 1.0], [2.0, 2.0], [4.0, 3.0], [6.0, 4.0], [6.0, 5.0]])
+    );
+}
+
+#[test]
+fn drops_rows_with_missing_values() {
+    let data = handle_missing_values(data_with_gaps(), &MissingValues::Drop).unwrap();
+    assert_eq!(data, arr2(&[[6.0, 4.0]]));
+
+    let empty_column = arr2(&[[f32::NAN, 1.0], [f32::NAN, 2.0]]);
+    assert!(handle_missing_values(empty_column.clone(), &MissingValues::Drop).is_err());
+    assert!(handle_missing_values(empty_column, &MissingValues::ForwardFill).is_err());
+}
+
+#[test]
+fn filter_holds_back_rows_until_gaps_are_closed() {
+    let mut filter = MissingValueFilter::new(MissingValues::Interpolate);
+    assert_eq!(filter.push(vec![1.0, 1.0]).unwrap(), vec![vec![1.0, 1.0]]);
+    assert!(filter.push(vec![f32::NAN, 2.0]).unwrap().is_empty());
+    assert_eq!(
+        filter.push(vec![3.0, 3.0]).unwrap(),
+        vec![vec![2.0, 2.0], vec![3.0, 3.0]]
+    );
+    assert!(filter.push(vec![4.0, f32::NAN]).unwrap().is_empty());
+    assert_eq!(filter.finish().unwrap(), vec![vec![4.0, 3.0]]);
+}
+
+#[test]
+fn filter_stops_at_first_rejected_row() {
+    let mut filter = MissingValueFilter::new(MissingValues::Reject);
+    assert_eq!(filter.push(vec![1.0]).unwrap().len(), 1);
+    assert_eq!(filter.push(vec![2.0]).unwrap().len(), 1);
+    assert!(filter.push(vec![f32::NAN]).unwrap().is_empty());
+    assert!(filter.push(vec![4.0]).unwrap().is_empty());
+    let error = filter.finish().unwrap_err();
+    assert!(error.to_string().contains("row 2 column 0!"));
+}
+
+#[test]
+fn parses_missing_value_policy() {
+    assert_eq!(
+        MissingValues::from_str("ffill").unwrap(),
+        MissingValues::ForwardFill
+    );
+    assert!(MissingValues::from_str("zero").is_err());
+}
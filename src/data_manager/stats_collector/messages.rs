@@ -13,7 +13,7 @@ pub struct DatasetStatsMessage {
 #[derive(RemoteMessage, Serialize, Deserialize)]
 #[with_source(source)]
 pub struct StdNodeMessage {
-    pub n: usize,
+    pub counts: Array1<f32>,
     pub mean: Array1<f32>,
     pub m2: Array1<f32>,
     pub source: RemoteAddr,
@@ -1,11 +1,18 @@
 use crate::data_manager::stats_collector::messages::{MinMaxDoneMessage, MinMaxNodeMessage};
-use crate::utils::Stats;
 use actix::prelude::*;
 use actix_telepathy::prelude::*;
-use ndarray::{Array1, Axis};
+use ndarray::{Array1, ArrayView2, Axis};
 
 use crate::data_manager::DataManager;
 
+/// The minimum and maximum of every column. Missing values (`NaN`) are ignored.
+pub(crate) fn nan_min_max(data: ArrayView2<f32>) -> (Array1<f32>, Array1<f32>) {
+    // `f32::min` and `f32::max` return the other value if one of them is `NaN`
+    let min = data.fold_axis(Axis(0), f32::INFINITY, |min, x| min.min(*x));
+    let max = data.fold_axis(Axis(0), f32::NEG_INFINITY, |max, x| max.max(*x));
+    (min, max)
+}
+
 pub struct MinMaxCalculation {
     pub nodes: Vec<RemoteAddr>,
     pub min: Option<Array1<f32>>,
@@ -26,8 +33,7 @@ impl MinMaxCalculator for DataManager {
     }
 
     fn calculate_minmax(&mut self, addr: Addr<Self>) {
-        let min = self.data.as_ref().unwrap().to_shared().min_axis(Axis(0));
-        let max = self.data.as_ref().unwrap().to_shared().max_axis(Axis(0));
+        let (min, max) = nan_min_max(self.data.as_ref().unwrap().view());
 
         let main = match self.cluster_nodes.get_main_node() {
             None => AnyAddr::Local(addr),
@@ -1,14 +1,17 @@
 mod messages;
 mod minmax;
 mod std;
+#[cfg(test)]
+mod tests;
 
 use ndarray::Array1;
+use serde::{Deserialize, Serialize};
 
 pub use crate::data_manager::stats_collector::messages::*;
 pub use crate::data_manager::stats_collector::minmax::{MinMaxCalculation, MinMaxCalculator};
 pub use crate::data_manager::stats_collector::std::{StdCalculation, StdCalculator};
 
-#[derive(Default, Clone, Debug)]
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct DatasetStats {
     pub min_col: Option<Array1<f32>>,
     pub max_col: Option<Array1<f32>>,
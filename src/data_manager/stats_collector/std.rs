@@ -1,27 +1,58 @@
 use crate::data_manager::stats_collector::messages::{StdDoneMessage, StdNodeMessage};
 use actix::prelude::*;
 use actix_telepathy::prelude::*;
-use ndarray::{s, Array1, Axis};
+use ndarray::{s, Array1, ArrayView2, Axis};
 
 use crate::data_manager::DataManager;
 
 pub struct StdCalculation {
     pub nodes: Vec<RemoteAddr>,
-    pub n: Option<usize>,
+    pub counts: Option<Array1<f32>>,
     pub mean: Option<Array1<f32>>,
     pub m2: Option<Array1<f32>>,
 }
 
+/// The number of values, the mean and the sum of squared differences from the mean of every
+/// column. Missing values (`NaN`) are ignored.
+pub(crate) fn nan_moments(data: ArrayView2<f32>) -> (Array1<f32>, Array1<f32>, Array1<f32>) {
+    let mut counts = Array1::zeros(data.ncols());
+    let mut mean = Array1::zeros(data.ncols());
+    let mut m2 = Array1::zeros(data.ncols());
+    for (c, column) in data.axis_iter(Axis(1)).enumerate() {
+        for x in column.iter().filter(|x| !x.is_nan()) {
+            counts[c] += 1.0;
+            let delta = x - mean[c];
+            mean[c] += delta / counts[c];
+            m2[c] += delta * (x - mean[c]);
+        }
+    }
+    (counts, mean, m2)
+}
+
+/// Combines the moments of two partitions (Chan et al.).
+pub(crate) fn merge_moments(
+    (counts_a, mean_a, m2_a): (Array1<f32>, Array1<f32>, Array1<f32>),
+    (counts_b, mean_b, m2_b): (Array1<f32>, Array1<f32>, Array1<f32>),
+) -> (Array1<f32>, Array1<f32>, Array1<f32>) {
+    let counts = &counts_a + &counts_b;
+    // columns without any value on both partitions would divide by zero
+    let safe_counts = counts.mapv(|n| n.max(1.0));
+    let delta = &mean_b - &mean_a;
+    let mean = (&mean_a * &counts_a + &mean_b * &counts_b) / &safe_counts;
+    let m2 = m2_a + m2_b + &delta * &delta * &counts_a * &counts_b / &safe_counts;
+    (counts, mean, m2)
+}
+
 pub trait StdCalculator {
-    fn set_intermediate_std(&mut self, n: usize, mean: Array1<f32>, m2: Array1<f32>);
+    fn set_intermediate_std(&mut self, counts: Array1<f32>, mean: Array1<f32>, m2: Array1<f32>);
     fn calculate_std(&mut self, addr: Addr<Self>)
     where
         Self: actix::Actor;
 }
 
 impl StdCalculator for DataManager {
-    fn set_intermediate_std(&mut self, n: usize, mean: Array1<f32>, m2: Array1<f32>) {
-        self.std_calculation.as_mut().unwrap().n = Some(n);
+    fn set_intermediate_std(&mut self, counts: Array1<f32>, mean: Array1<f32>, m2: Array1<f32>) {
+        self.std_calculation.as_mut().unwrap().counts = Some(counts);
         self.std_calculation.as_mut().unwrap().mean = Some(mean);
         self.std_calculation.as_mut().unwrap().m2 = Some(m2);
     }
@@ -35,15 +66,7 @@ impl StdCalculator for DataManager {
         };
         let end_slice = self.data.as_ref().unwrap().nrows() - cutoff;
         let data = self.data.as_ref().unwrap().slice(s![0..end_slice, ..]);
-        let n = data.nrows();
-        let mean = data.mean_axis(Axis(0)).unwrap();
-        let delta = data.to_owned()
-            - mean
-                .broadcast((data.nrows(), mean.len()))
-                .unwrap()
-                .to_owned();
-        let delta_n = delta.clone() / (n as f32);
-        let m2 = (delta * delta_n * (n as f32)).sum_axis(Axis(0));
+        let (counts, mean, m2) = nan_moments(data);
 
         let main = match self.cluster_nodes.get_main_node() {
             None => AnyAddr::Local(addr),
@@ -54,7 +77,7 @@ impl StdCalculator for DataManager {
             }
         };
         main.do_send(StdNodeMessage {
-            n,
+            counts,
             mean,
             m2,
             source: RemoteAddr::new_from_id(self.parameters.local_host, "DataManager"),
@@ -69,59 +92,37 @@ impl Handler<StdNodeMessage> for DataManager {
         let std_calcuation = self.std_calculation.as_mut().unwrap();
         std_calcuation.nodes.push(msg.source);
 
-        if std_calcuation.nodes.len() < self.parameters.n_cluster_nodes {
-            match (&std_calcuation.n, &std_calcuation.mean, &std_calcuation.m2) {
-                (Some(n), Some(mean), Some(m2)) => {
-                    let global_n = n + msg.n;
-                    let delta: Array1<f32> = msg.mean.clone() - mean;
-                    std_calcuation.m2 = Some(
-                        msg.m2
-                            + m2
-                            + delta.clone() * delta * ((n + msg.n) as f32 / global_n as f32),
-                    );
-
-                    std_calcuation.mean =
-                        Some((mean * *n as f32 + msg.mean * msg.n as f32) / global_n as f32);
-                    std_calcuation.n = Some(global_n);
-                }
-                _ => {
-                    self.set_intermediate_std(msg.n, msg.mean, msg.m2);
-                }
+        let moments = match (
+            std_calcuation.counts.take(),
+            std_calcuation.mean.take(),
+            std_calcuation.m2.take(),
+        ) {
+            (Some(counts), Some(mean), Some(m2)) => {
+                merge_moments((counts, mean, m2), (msg.counts, msg.mean, msg.m2))
             }
-        } else {
-            let std: Array1<f32> =
-                match (&std_calcuation.n, &std_calcuation.mean, &std_calcuation.m2) {
-                    (Some(n), Some(mean), Some(m2)) => {
-                        let global_n = n + msg.n;
-                        let delta: Array1<f32> = msg.mean.clone() - mean;
-                        let m2 = msg.m2
-                            + m2
-                            + delta.clone() * delta * ((n + msg.n) as f32 / global_n as f32);
-                        std_calcuation.n = Some(global_n);
-                        (m2 / (global_n - 1) as f32)
-                            .iter()
-                            .map(|x| x.sqrt())
-                            .collect()
-                    }
-                    _ => {
-                        // for single node case or if local message is faster than remote message
-                        let std = (msg.m2.clone() / (msg.n - 1) as f32)
-                            .iter()
-                            .map(|x| x.sqrt())
-                            .collect();
-                        self.set_intermediate_std(msg.n, msg.mean, msg.m2);
-                        std
-                    }
-                };
+            // for single node case or if local message is faster than remote message
+            _ => (msg.counts, msg.mean, msg.m2),
+        };
+        let (counts, mean, m2) = moments;
+        self.set_intermediate_std(counts, mean, m2);
+
+        let std_calcuation = self.std_calculation.as_ref().unwrap();
+        if std_calcuation.nodes.len() == self.parameters.n_cluster_nodes {
+            let counts = std_calcuation.counts.as_ref().unwrap();
+            let std: Array1<f32> = (std_calcuation.m2.as_ref().unwrap() / &(counts - 1.0))
+                .iter()
+                .map(|x| x.sqrt())
+                .collect();
+            let n = counts.iter().fold(0.0_f32, |a, b| a.max(*b)) as usize;
 
-            for node in self.std_calculation.as_ref().unwrap().nodes.iter() {
+            for node in std_calcuation.nodes.iter() {
                 let receiving_node = match &node.node.network_interface {
                     Some(_) => AnyAddr::Remote(node.clone()),
                     None => AnyAddr::Local(ctx.address()),
                 };
                 receiving_node.do_send(StdDoneMessage {
                     std: std.clone(),
-                    n: *self.std_calculation.as_ref().unwrap().n.as_ref().unwrap(),
+                    n,
                 });
             }
         }
@@ -0,0 +1,40 @@
+use crate::data_manager::stats_collector::minmax::nan_min_max;
+use crate::data_manager::stats_collector::std::{merge_moments, nan_moments};
+use ndarray::{arr1, arr2, s};
+
+#[test]
+fn min_max_ignore_missing_values() {
+    let data = arr2(&[[1.0, f32::NAN], [f32::NAN, 4.0], [-3.0, 6.0]]);
+    let (min, max) = nan_min_max(data.view());
+    assert_eq!(min, arr1(&[-3.0, 4.0]));
+    assert_eq!(max, arr1(&[1.0, 6.0]));
+}
+
+#[test]
+fn moments_ignore_missing_values() {
+    let data = arr2(&[[1.0, 2.0], [f32::NAN, 4.0], [3.0, 6.0]]);
+    let (counts, mean, m2) = nan_moments(data.view());
+    assert_eq!(counts, arr1(&[2.0, 3.0]));
+    assert_eq!(mean, arr1(&[2.0, 4.0]));
+    assert_eq!(m2, arr1(&[2.0, 8.0]));
+}
+
+#[test]
+fn merged_moments_equal_moments_of_whole_data() {
+    let data = arr2(&[
+        [1.0, 2.0],
+        [5.0, f32::NAN],
+        [3.0, 7.0],
+        [8.0, 1.0],
+        [2.0, 3.0],
+    ]);
+    let merged = merge_moments(
+        nan_moments(data.slice(s![..2, ..])),
+        nan_moments(data.slice(s![2.., ..])),
+    );
+    let (counts, mean, m2) = nan_moments(data.view());
+
+    assert_eq!(merged.0, counts);
+    assert!((merged.1 - mean).iter().all(|x| x.abs() < 1e-5));
+    assert!((merged.2 - m2).iter().all(|x| x.abs() < 1e-4));
+}
@@ -7,10 +7,18 @@ use std::sync::{Arc, Mutex};
 use actix_broker::BrokerSubscribe;
 use ndarray_linalg::assert::close_l1;
 use std::collections::HashMap;
+use std::fs::{remove_file, File};
 use std::net::SocketAddr;
 
+use crate::data_manager::data_reader::columnar::{
+    partition_ranges, visit_columnar_batches, InputFormat,
+};
+use crate::data_manager::data_reader::{
+    read_carried_columns, read_column_names, read_data, DataPartitionMessage,
+};
 use crate::data_manager::messages::DataLoadedAndProcessed;
 use crate::data_manager::{DataManager, LoadDataMessage};
+use crate::messages::TrainingFailed;
 use crate::parameters::{Parameters, Role};
 use crate::utils::ClusterNodes;
 use log::*;
@@ -41,6 +49,7 @@ impl OwnListener {
             cluster_nodes.clone(),
             self.parameters.clone(),
             ctx.address().recipient(),
+            ctx.address().recipient(),
         )
         .start();
         dm.do_send(LoadDataMessage {
@@ -85,6 +94,14 @@ impl Handler<DataLoadedAndProcessed> for OwnListener {
     }
 }
 
+impl Handler<TrainingFailed> for OwnListener {
+    type Result = ();
+
+    fn handle(&mut self, msg: TrainingFailed, _ctx: &mut Self::Context) -> Self::Result {
+        panic!("Could not load the data: {}", msg.error);
+    }
+}
+
 struct TestParams {
     ip: SocketAddr,
     seeds: Vec<SocketAddr>,
@@ -356,3 +373,194 @@ async fn start_reading(
         0.0005,
     );
 }
+
+#[test]
+fn reads_selected_columns_from_csv() {
+    let parameters = Parameters {
+        column_start: 1,
+        column_end: -1,
+        ..Default::default()
+    };
+    let data = read_data("data/test.csv", &parameters).unwrap();
+
+    assert_eq!(data.shape(), &[100, 1]);
+    assert!((data[[0, 0]] - 0.808_167).abs() < 1e-5);
+}
+
+#[test]
+fn reads_csv_dialect_without_header() {
+    let path = "data/_test_dialect.csv";
+    std::fs::write(path, "t0;1.5;'2.5';0\nt1;3.0;4.0;1\n").unwrap();
+
+    let parameters = Parameters {
+        delimiter: b';',
+        quote: b'\'',
+        no_header: true,
+        timestamp_column: Some("0".to_string()),
+        label_column: Some("3".to_string()),
+        ..Default::default()
+    };
+    let data = read_data(path, &parameters);
+    let missing_label = read_data(
+        path,
+        &Parameters {
+            label_column: Some("is_anomaly".to_string()),
+            ..parameters.clone()
+        },
+    );
+    let carried = read_carried_columns(path, &parameters);
+    remove_file(path).expect("Could not delete test file!");
+
+    assert_eq!(data.unwrap(), arr2(&[[1.5, 2.5], [3.0, 4.0]]));
+    assert!(missing_label.is_err());
+    assert_eq!(carried.unwrap(), vec![vec!["t0", "t1"], vec!["0", "1"]]);
+}
+
+#[test]
+fn excludes_timestamp_and_label_columns() {
+    let path = "data/_test_timeeval.csv";
+    std::fs::write(
+        path,
+        "timestamp\tvalue-0\tvalue-1\tis_anomaly\n0\t1.5\t2.5\t0\n1\t3.0\t4.0\t1\n",
+    )
+    .unwrap();
+
+    let parameters = Parameters {
+        delimiter: b'\t',
+        timestamp_column: Some("timestamp".to_string()),
+        label_column: Some("is_anomaly".to_string()),
+        ..Default::default()
+    };
+    let data = read_data(path, &parameters);
+    let names = read_column_names(path, &parameters);
+    remove_file(path).expect("Could not delete test file!");
+
+    assert_eq!(data.unwrap(), arr2(&[[1.5, 2.5], [3.0, 4.0]]));
+    assert_eq!(names.unwrap(), vec!["value-0", "value-1"]);
+}
+
+fn test_batch() -> arrow::record_batch::RecordBatch {
+    use arrow::array::{ArrayRef, Float64Array, Int32Array};
+
+    let a: ArrayRef = Arc::new(Float64Array::from(vec![0.5, 1.5, 2.5]));
+    let b: ArrayRef = Arc::new(Int32Array::from(vec![Some(1), None, Some(3)]));
+    let c: ArrayRef = Arc::new(Float64Array::from(vec![10.0, 20.0, 30.0]));
+    arrow::record_batch::RecordBatch::try_from_iter(vec![("a", a), ("b", b), ("c", c)]).unwrap()
+}
+
+#[test]
+fn reads_columns_by_name_from_parquet() {
+    let path = "data/_test_columns.parquet";
+    let batch = test_batch();
+    let mut writer =
+        parquet::arrow::ArrowWriter::try_new(File::create(path).unwrap(), batch.schema(), None)
+            .unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+
+    let parameters = Parameters {
+        columns: vec!["c".to_string(), "a".to_string()],
+        ..Default::default()
+    };
+    let data = read_data(path, &parameters);
+    remove_file(path).expect("Could not delete test file!");
+
+    assert_eq!(
+        data.unwrap(),
+        arr2(&[[10.0, 0.5], [20.0, 1.5], [30.0, 2.5]])
+    );
+}
+
+#[test]
+fn reads_parquet_row_group_by_row_group() {
+    let path = "data/_test_row_groups.parquet";
+    let batch = test_batch();
+    let properties = parquet::file::properties::WriterProperties::builder()
+        .set_max_row_group_size(2)
+        .build();
+    let mut writer = parquet::arrow::ArrowWriter::try_new(
+        File::create(path).unwrap(),
+        batch.schema(),
+        Some(properties),
+    )
+    .unwrap();
+    writer.write(&batch).unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+
+    let parameters = Parameters {
+        columns: vec!["a".to_string()],
+        ..Default::default()
+    };
+    let mut batch_lengths = vec![];
+    let visited = visit_columnar_batches(path, &InputFormat::Parquet, &parameters, |batch| {
+        batch_lengths.push(batch.nrows());
+        Ok(())
+    });
+    let data = read_data(path, &parameters);
+    remove_file(path).expect("Could not delete test file!");
+
+    assert!(visited.is_ok());
+    assert_eq!(batch_lengths, vec![2, 2, 2]);
+    assert_eq!(
+        data.unwrap().column(0),
+        arr1(&[0.5, 1.5, 2.5, 0.5, 1.5, 2.5])
+    );
+}
+
+#[test]
+fn reads_column_range_from_arrow_ipc() {
+    let path = "data/_test_columns.arrow";
+    let batch = test_batch();
+    let mut writer =
+        arrow::ipc::writer::FileWriter::try_new(File::create(path).unwrap(), &batch.schema())
+            .unwrap();
+    writer.write(&batch).unwrap();
+    writer.write(&batch).unwrap();
+    writer.finish().unwrap();
+
+    let parameters = Parameters {
+        column_start: 1,
+        ..Default::default()
+    };
+    let data = read_data(path, &parameters);
+    let missing_column = read_data(
+        path,
+        &Parameters {
+            columns: vec!["d".to_string()],
+            ..Default::default()
+        },
+    );
+    remove_file(path).expect("Could not delete test file!");
+
+    let data = data.unwrap();
+    assert_eq!(data.shape(), &[6, 2]);
+    assert_eq!(data.row(3), arr1(&[1.0, 10.0]));
+    assert!(data[[1, 0]].is_nan());
+    assert!(missing_column.is_err());
+}
+
+#[test]
+fn detects_input_format_from_extension() {
+    assert_eq!(InputFormat::from_path("data/ts_0.csv"), InputFormat::Csv);
+    assert_eq!(InputFormat::from_path("x.PARQUET"), InputFormat::Parquet);
+    assert_eq!(InputFormat::from_path("x.feather"), InputFormat::ArrowIpc);
+}
+
+#[test]
+fn partitions_overlap_like_csv_reading() {
+    assert_eq!(partition_ranges(10, 1, 2), vec![0..10]);
+    assert_eq!(partition_ranges(10, 3, 2), vec![0..5, 3..8, 6..10]);
+}
+
+#[test]
+fn partition_message_keeps_values() {
+    let data = arr2(&[[1.5, -2.0, f32::MAX], [0.0, 3.25, f32::MIN_POSITIVE]]);
+    let rows = vec![
+        vec![1.5, -2.0, f32::MAX],
+        vec![0.0, 3.25, f32::MIN_POSITIVE],
+    ];
+    let message = DataPartitionMessage::from_rows(&rows, 3);
+    assert_eq!(message.values.len(), 6 * 4);
+    assert_eq!(message.into_array(), data);
+}
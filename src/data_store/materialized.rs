@@ -0,0 +1,50 @@
+use crate::data_store::intersection::Intersection;
+use crate::data_store::materialize::Materialize;
+use crate::data_store::node::IndependentNode;
+use crate::data_store::point::Point;
+use crate::data_store::DataStore;
+use serde::{Deserialize, Serialize};
+use std::ops::Deref;
+
+/// The points, intersections and nodes of a `DataStore` without references, so that they can be
+/// written to a checkpoint. Transitions and edges are left out, because no phase after a checkpoint
+/// reads them.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub(crate) struct MaterializedDataStore {
+    points: Vec<Point>,
+    intersections: Vec<Intersection>,
+    nodes: Vec<IndependentNode>,
+}
+
+impl MaterializedDataStore {
+    /// Rebuilds the data store together with its index.
+    pub fn into_data_store(self) -> DataStore {
+        let mut data_store = DataStore::default();
+        for point in self.points {
+            data_store.add_point(point);
+        }
+        data_store.add_intersections(self.intersections);
+        for node in self.nodes {
+            data_store.add_independent_node(node);
+        }
+        data_store
+    }
+}
+
+impl Materialize<MaterializedDataStore> for DataStore {
+    fn materialize(&self) -> MaterializedDataStore {
+        MaterializedDataStore {
+            points: self
+                .points
+                .iter()
+                .map(|point| point.deref_clone())
+                .collect(),
+            intersections: self
+                .intersections
+                .iter()
+                .map(|intersection| intersection.deref().clone())
+                .collect(),
+            nodes: self.nodes.iter().map(|node| node.deref().clone()).collect(),
+        }
+    }
+}
@@ -15,6 +15,7 @@ pub(crate) mod edge;
 mod index;
 pub(crate) mod intersection;
 pub(crate) mod materialize;
+pub(crate) mod materialized;
 pub(crate) mod node;
 pub(crate) mod node_questions;
 pub(crate) mod point;
@@ -71,6 +72,10 @@ impl DataStore {
         self.points.clone()
     }
 
+    pub fn count_points(&self) -> usize {
+        self.points.len()
+    }
+
     pub fn mirror_points(&mut self, n_segments: usize) {
         for point in self.points.iter_mut() {
             point.mirror(n_segments);
@@ -133,6 +138,14 @@ impl DataStore {
         self.index.get_intersections(segment)
     }
 
+    pub fn get_intersections(&self) -> Vec<IntersectionRef> {
+        self.intersections.to_vec()
+    }
+
+    pub fn count_intersections(&self) -> usize {
+        self.intersections.len()
+    }
+
     // --- Nodes
 
     #[allow(dead_code)]
@@ -153,6 +166,14 @@ impl DataStore {
         self.index.get_nodes(point_id)
     }
 
+    pub fn get_nodes(&self) -> Vec<NodeRef> {
+        self.nodes.to_vec()
+    }
+
+    pub fn count_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
     // --- Edges
 
     pub fn add_edge(&mut self, edge: Edge) {
@@ -184,6 +205,10 @@ impl DataStore {
         self.edges.to_vec()
     }
 
+    pub fn count_edges(&self) -> usize {
+        self.edges.len()
+    }
+
     pub fn slice_edges(&self, range: Range<usize>) -> Iter<'_, EdgeRef> {
         self.edges[range].iter()
     }
@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The failures Series2Graph++ reports instead of aborting. They are wrapped in `anyhow::Error`
+/// by the public functions and can be recovered with `error.downcast_ref::<S2GppError>()`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum S2GppError {
+    /// A file could not be opened, read or written.
+    Io { path: String, message: String },
+    /// The input data cannot be used, e.g. it contains non-numeric or missing values.
+    InvalidData(String),
+    /// The parameters do not fit together or to the data.
+    InvalidParameters(String),
+    /// No transition of the time series crosses the segment.
+    NoIntersection { segment_id: usize },
+    /// The time series is too short to build a single query.
+    TooFewEdges { n_edges: usize, query_length: usize },
+    /// The actor system stopped before it produced a result.
+    Aborted(String),
+}
+
+impl S2GppError {
+    /// Recovers the typed error of `error`. Any other error is caused by the input data.
+    pub(crate) fn from_data_error(error: anyhow::Error) -> Self {
+        error
+            .downcast::<S2GppError>()
+            .unwrap_or_else(|error| S2GppError::InvalidData(error.to_string()))
+    }
+
+    /// Classifies an error that occurred while reading `path`.
+    pub(crate) fn reading(path: &str, error: anyhow::Error) -> Self {
+        match error.downcast::<S2GppError>() {
+            Ok(error) => error,
+            Err(error) if error.is::<std::io::Error>() => S2GppError::Io {
+                path: path.to_string(),
+                message: error.to_string(),
+            },
+            Err(error) => S2GppError::InvalidData(format!("Could not read '{}': {}", path, error)),
+        }
+    }
+
+    /// Classifies an error that occurred while writing `path`.
+    pub(crate) fn writing(path: &str, error: anyhow::Error) -> Self {
+        error
+            .downcast::<S2GppError>()
+            .unwrap_or_else(|error| S2GppError::Io {
+                path: path.to_string(),
+                message: error.to_string(),
+            })
+    }
+}
+
+impl fmt::Display for S2GppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            S2GppError::Io { path, message } => write!(f, "'{}': {}", path, message),
+            S2GppError::InvalidData(message) => write!(f, "Invalid data: {}", message),
+            S2GppError::InvalidParameters(message) => write!(f, "Invalid parameters: {}", message),
+            S2GppError::NoIntersection { segment_id } => {
+                write!(f, "No intersection found in segment {}!", segment_id)
+            }
+            S2GppError::TooFewEdges {
+                n_edges,
+                query_length,
+            } => write!(
+                f,
+                "There are less edges ({}) than the given 'query_length' ({})!",
+                n_edges, query_length
+            ),
+            S2GppError::Aborted(message) => {
+                write!(f, "Series2Graph++ did not terminate correctly: {}", message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for S2GppError {}
@@ -0,0 +1,279 @@
+mod range_based;
+#[cfg(test)]
+mod tests;
+
+use crate::data_manager::data_reader::read_carried_columns;
+use crate::error::S2GppError;
+use crate::evaluation::range_based::{range_precision, range_recall, ranges};
+use crate::parameters::{Parameters, Role};
+use crate::training::Thresholding;
+use anyhow::{Error, Result};
+use csv::ReaderBuilder;
+use log::*;
+use ndarray::{Array1, ArrayView1};
+use serde::Serialize;
+
+/// How well an anomaly score matches the labels.
+#[derive(Debug, Clone, Serialize)]
+pub struct Evaluation {
+    pub roc_auc: f64,
+    /// area under the precision-recall curve as average precision
+    pub pr_auc: f64,
+    /// the best point-wise F1 score over all thresholds
+    pub best_f1: f64,
+    pub best_f1_threshold: f32,
+    /// the threshold of the range-based metrics
+    pub threshold: f32,
+    pub range_precision: f64,
+    pub range_recall: f64,
+    pub range_f1: f64,
+}
+
+impl Evaluation {
+    pub fn log(&self) {
+        info!("ROC-AUC: {:.4}", self.roc_auc);
+        info!("PR-AUC: {:.4}", self.pr_auc);
+        info!(
+            "Best F1: {:.4} (threshold {})",
+            self.best_f1, self.best_f1_threshold
+        );
+        info!(
+            "Range-based precision: {:.4}, recall: {:.4}, F1: {:.4} (threshold {})",
+            self.range_precision, self.range_recall, self.range_f1, self.threshold
+        );
+    }
+}
+
+/// Evaluates `score` against the point-wise `labels`, which must have the same length. Rows
+/// with a `NaN` score are left out of the point-wise metrics. The range-based metrics
+/// (Tatbul et al., "Precision and Recall for Time Series") use flat positional bias and
+/// reciprocal cardinality; `existence_weight` rewards detecting an anomaly range at all. Their
+/// threshold is chosen by `thresholding`, or is the best-F1 threshold without it.
+pub fn evaluate(
+    score: ArrayView1<f32>,
+    labels: ArrayView1<bool>,
+    thresholding: Option<&Thresholding>,
+    existence_weight: f64,
+) -> Result<Evaluation, S2GppError> {
+    if score.len() != labels.len() {
+        return Err(S2GppError::InvalidData(format!(
+            "The score has {} rows, but there are {} labels! Use a 'score-alignment' to score every row.",
+            score.len(),
+            labels.len()
+        )));
+    }
+
+    let mut sorted: Vec<(f32, bool)> = score
+        .iter()
+        .copied()
+        .zip(labels.iter().copied())
+        .filter(|(s, _)| !s.is_nan())
+        .collect();
+    sorted.sort_by(|(a, _), (b, _)| b.partial_cmp(a).unwrap());
+    let n_anomalous = sorted.iter().filter(|(_, label)| *label).count();
+    if n_anomalous == 0 || n_anomalous == sorted.len() {
+        return Err(S2GppError::InvalidData(
+            "The labels must mark normal and anomalous rows!".to_string(),
+        ));
+    }
+
+    let curve = confusion_curve(&sorted);
+    let (best_f1, best_f1_threshold) = best_f1(&curve, n_anomalous);
+    let threshold = thresholding.map_or(best_f1_threshold, |t| t.threshold(score));
+    let real = ranges(labels.iter().copied());
+    let predicted = ranges(score.iter().map(|s| *s > threshold));
+    let range_precision = range_precision(&real, &predicted);
+    let range_recall = range_recall(&real, &predicted, existence_weight);
+
+    Ok(Evaluation {
+        roc_auc: roc_auc(&curve, n_anomalous, sorted.len() - n_anomalous),
+        pr_auc: pr_auc(&curve, n_anomalous),
+        best_f1,
+        best_f1_threshold,
+        threshold,
+        range_precision,
+        range_recall,
+        range_f1: f1(range_precision, range_recall),
+    })
+}
+
+/// One point per distinct score of the descending `sorted` scores: the true and false positives
+/// of predicting every row with at least this score as anomalous, and the next lower score, above
+/// which exactly these rows lie.
+struct CurvePoint {
+    tp: usize,
+    fp: usize,
+    threshold: f32,
+}
+
+fn confusion_curve(sorted: &[(f32, bool)]) -> Vec<CurvePoint> {
+    let mut curve = vec![];
+    let (mut tp, mut fp) = (0, 0);
+    for (i, (score, label)) in sorted.iter().enumerate() {
+        if *label {
+            tp += 1;
+        } else {
+            fp += 1;
+        }
+        match sorted.get(i + 1) {
+            Some((next, _)) if next == score => continue,
+            Some((next, _)) => curve.push(CurvePoint {
+                tp,
+                fp,
+                threshold: *next,
+            }),
+            None => curve.push(CurvePoint {
+                tp,
+                fp,
+                threshold: f32::NEG_INFINITY,
+            }),
+        }
+    }
+    curve
+}
+
+fn roc_auc(curve: &[CurvePoint], n_anomalous: usize, n_normal: usize) -> f64 {
+    let mut area = 0.0;
+    let (mut last_tp, mut last_fp) = (0, 0);
+    for point in curve {
+        area += (point.fp - last_fp) as f64 * (point.tp + last_tp) as f64 / 2.0;
+        last_tp = point.tp;
+        last_fp = point.fp;
+    }
+    area / (n_anomalous as f64 * n_normal as f64)
+}
+
+fn pr_auc(curve: &[CurvePoint], n_anomalous: usize) -> f64 {
+    let mut last_tp = 0;
+    curve
+        .iter()
+        .map(|point| {
+            let precision = point.tp as f64 / (point.tp + point.fp) as f64;
+            let area = (point.tp - last_tp) as f64 / n_anomalous as f64 * precision;
+            last_tp = point.tp;
+            area
+        })
+        .sum()
+}
+
+fn best_f1(curve: &[CurvePoint], n_anomalous: usize) -> (f64, f32) {
+    curve
+        .iter()
+        .map(|point| {
+            let precision = point.tp as f64 / (point.tp + point.fp) as f64;
+            let recall = point.tp as f64 / n_anomalous as f64;
+            (f1(precision, recall), point.threshold)
+        })
+        .fold((0.0, f32::INFINITY), |best, current| {
+            if current.0 > best.0 {
+                current
+            } else {
+                best
+            }
+        })
+}
+
+fn f1(precision: f64, recall: f64) -> f64 {
+    if precision + recall == 0.0 {
+        0.0
+    } else {
+        2.0 * precision * recall / (precision + recall)
+    }
+}
+
+/// Evaluates the score file of the `evaluate` subcommand. The labels are read from the
+/// `label-column` of the data file, or of the score file if no data file is given.
+pub fn evaluate_files(params: &Parameters) -> Result<Evaluation> {
+    let (score_path, data_path, existence_weight) = match &params.role {
+        Role::Evaluate {
+            score_path,
+            data_path,
+            existence_weight,
+        } => (score_path, data_path, *existence_weight),
+        _ => {
+            return Err(S2GppError::InvalidParameters(
+                "Only the 'evaluate' subcommand can evaluate files!".to_string(),
+            )
+            .into())
+        }
+    };
+    let label_column = params.label_column.as_ref().ok_or_else(|| {
+        S2GppError::InvalidParameters("Evaluating needs a 'label-column'!".to_string())
+    })?;
+
+    let (score, score_labels) = read_score(score_path, label_column, params)
+        .map_err(|e| S2GppError::reading(score_path, e))?;
+    let labels = match data_path {
+        Some(data_path) => read_carried_columns(data_path, params)
+            .map_err(|e| S2GppError::reading(data_path, e))?
+            .pop(),
+        None => score_labels,
+    }
+    .ok_or_else(|| {
+        S2GppError::InvalidParameters(format!(
+            "The score file has no column '{}'! Please, pass the labelled data with '--data-path'.",
+            label_column
+        ))
+    })?;
+    let labels = labels
+        .iter()
+        .map(|label| parse_label(label))
+        .collect::<Result<Array1<bool>, S2GppError>>()?;
+
+    Ok(evaluate(
+        score.view(),
+        labels.view(),
+        params.thresholding.as_ref(),
+        existence_weight,
+    )?)
+}
+
+/// Reads a file written by `write_score`. It either has a single column without header or a
+/// header with a `score` column and possibly the `label_column`.
+fn read_score(
+    score_path: &str,
+    label_column: &str,
+    params: &Parameters,
+) -> Result<(Array1<f32>, Option<Vec<String>>)> {
+    let mut reader = ReaderBuilder::new()
+        .has_headers(false)
+        .delimiter(params.delimiter)
+        .from_path(score_path)?;
+    let mut records = reader.records();
+    let first = records
+        .next()
+        .ok_or_else(|| Error::msg("The score file is empty!"))??;
+    let header = first
+        .iter()
+        .position(|name| name == "score")
+        .map(|score| (score, first.iter().position(|name| name == label_column)));
+
+    let (score_idx, label_idx) = header.unwrap_or((0, None));
+    let mut scores = vec![];
+    let mut labels = vec![];
+    let body = if header.is_some() {
+        None
+    } else {
+        Some(Ok(first))
+    };
+    for record in body.into_iter().chain(records) {
+        let record = record?;
+        scores.push(record[score_idx].trim().parse::<f32>()?);
+        if let Some(label_idx) = label_idx {
+            labels.push(record[label_idx].to_string());
+        }
+    }
+    Ok((Array1::from(scores), label_idx.map(|_| labels)))
+}
+
+/// `true`/`false` or a number, which marks an anomaly if it is not zero.
+fn parse_label(label: &str) -> Result<bool, S2GppError> {
+    match label.trim() {
+        "true" | "True" => Ok(true),
+        "false" | "False" => Ok(false),
+        value => value
+            .parse::<f32>()
+            .map(|v| v != 0.0)
+            .map_err(|_| S2GppError::InvalidData(format!("'{}' is not a valid label!", label))),
+    }
+}
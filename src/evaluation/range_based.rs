@@ -0,0 +1,67 @@
+use std::ops::Range;
+
+/// The runs of consecutive `true` flags.
+pub(crate) fn ranges<I: Iterator<Item = bool>>(flags: I) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = vec![];
+    let mut open = false;
+    for (i, flag) in flags.enumerate() {
+        match ranges.last_mut() {
+            Some(range) if flag && open => range.end = i + 1,
+            _ if flag => ranges.push(i..i + 1),
+            _ => {}
+        }
+        open = flag;
+    }
+    ranges
+}
+
+/// The share of `range` that is covered by `others`, divided by the number of overlapping
+/// `others` if there are several (flat positional bias, reciprocal cardinality).
+fn overlap_reward(range: &Range<usize>, others: &[Range<usize>]) -> f64 {
+    let overlaps: Vec<usize> = others
+        .iter()
+        .map(|other| {
+            range
+                .end
+                .min(other.end)
+                .saturating_sub(range.start.max(other.start))
+        })
+        .filter(|overlap| *overlap > 0)
+        .collect();
+    if overlaps.is_empty() {
+        return 0.0;
+    }
+    let covered = overlaps.iter().sum::<usize>() as f64 / range.len() as f64;
+    covered / overlaps.len() as f64
+}
+
+/// Range-based recall of Tatbul et al.; `existence_weight` is their `alpha`.
+pub(crate) fn range_recall(
+    real: &[Range<usize>],
+    predicted: &[Range<usize>],
+    existence_weight: f64,
+) -> f64 {
+    if real.is_empty() {
+        return 0.0;
+    }
+    real.iter()
+        .map(|range| {
+            let overlap = overlap_reward(range, predicted);
+            let existence = if overlap > 0.0 { 1.0 } else { 0.0 };
+            existence_weight * existence + (1.0 - existence_weight) * overlap
+        })
+        .sum::<f64>()
+        / real.len() as f64
+}
+
+/// Range-based precision of Tatbul et al., which has no existence reward.
+pub(crate) fn range_precision(real: &[Range<usize>], predicted: &[Range<usize>]) -> f64 {
+    if predicted.is_empty() {
+        return 0.0;
+    }
+    predicted
+        .iter()
+        .map(|range| overlap_reward(range, real))
+        .sum::<f64>()
+        / predicted.len() as f64
+}
@@ -0,0 +1,85 @@
+use crate::evaluation::range_based::{range_precision, range_recall, ranges};
+use crate::evaluation::{evaluate, evaluate_files};
+use crate::parameters::{Parameters, Role};
+use crate::training::Thresholding;
+use ndarray::arr1;
+use std::fs::remove_file;
+
+#[test]
+fn perfect_score_is_rated_best() {
+    let score = arr1(&[0.1, 0.2, 0.9, 0.8, 0.1]);
+    let labels = arr1(&[false, false, true, true, false]);
+    let evaluation = evaluate(score.view(), labels.view(), None, 0.0).unwrap();
+
+    assert_eq!(evaluation.roc_auc, 1.0);
+    assert_eq!(evaluation.pr_auc, 1.0);
+    assert_eq!(evaluation.best_f1, 1.0);
+    assert_eq!(evaluation.best_f1_threshold, 0.2);
+    assert_eq!(evaluation.range_precision, 1.0);
+    assert_eq!(evaluation.range_recall, 1.0);
+}
+
+#[test]
+fn ties_and_missing_scores_are_handled() {
+    let score = arr1(&[0.5, 0.5, 0.5, 0.5, f32::NAN]);
+    let labels = arr1(&[true, false, false, false, true]);
+    let evaluation = evaluate(score.view(), labels.view(), None, 0.0).unwrap();
+
+    assert_eq!(evaluation.roc_auc, 0.5);
+    assert_eq!(evaluation.pr_auc, 0.25);
+}
+
+#[test]
+fn rejects_unusable_labels() {
+    let score = arr1(&[0.1, 0.2]);
+    assert!(evaluate(score.view(), arr1(&[false, false]).view(), None, 0.0).is_err());
+    assert!(evaluate(score.view(), arr1(&[false]).view(), None, 0.0).is_err());
+}
+
+#[test]
+fn range_based_metrics_reward_partial_detection() {
+    let real = ranges(
+        [false, false, true, true, true, true, false]
+            .iter()
+            .copied(),
+    );
+    let predicted = ranges(
+        [false, false, true, true, false, true, false]
+            .iter()
+            .copied(),
+    );
+    assert_eq!(real, vec![2..6]);
+    assert_eq!(predicted, vec![2..4, 5..6]);
+
+    assert_eq!(range_recall(&real, &predicted, 0.0), 0.375);
+    assert_eq!(range_recall(&real, &predicted, 1.0), 1.0);
+    assert_eq!(range_precision(&real, &predicted), 1.0);
+}
+
+#[test]
+fn evaluates_score_file_with_labels() {
+    let score_path = "data/_test_evaluate_scores.csv";
+    std::fs::write(
+        score_path,
+        "score,is_anomaly\n0.1,0\n0.2,0\n0.9,1\n0.8,1\n0.1,0\n",
+    )
+    .unwrap();
+    let params = Parameters {
+        role: Role::Evaluate {
+            score_path: score_path.to_string(),
+            data_path: None,
+            existence_weight: 0.0,
+        },
+        label_column: Some("is_anomaly".to_string()),
+        thresholding: Some(Thresholding::Fixed(0.85)),
+        ..Default::default()
+    };
+
+    let evaluation = evaluate_files(&params);
+    remove_file(score_path).expect("Could not delete test file!");
+    let evaluation = evaluation.unwrap();
+
+    assert_eq!(evaluation.roc_auc, 1.0);
+    assert_eq!(evaluation.threshold, 0.85);
+    assert_eq!(evaluation.range_recall, 0.5);
+}
@@ -2,12 +2,13 @@ mod sink;
 #[cfg(test)]
 mod tests;
 
+use crate::error::S2GppError;
 use crate::interface::sink::{MySink, SinkActor};
-use crate::training::DetectionResponse;
+use crate::training::{DetectionResponse, S2GppModel};
 use crate::{Parameters, StartTrainingMessage, Training};
 use actix::io::SinkWrite;
-use actix::{Actor, Handler};
-use anyhow::{Error, Result};
+use actix::{Actor, Handler, Recipient};
+use anyhow::Result;
 use ndarray::{Array1, Array2};
 use tokio::sync::mpsc;
 
@@ -16,35 +17,52 @@ pub trait SyncInterface<A> {
     fn fit(&mut self, data: Array2<A>) -> Result<SyncResult>;
 }
 
-pub type SyncResult = Array1<f32>;
+#[derive(Clone)]
+pub struct SyncResult {
+    pub anomaly_score: Array1<f32>,
+    /// the contribution of each dimension to each score, only available with `explainability`
+    pub anomaly_contribution: Option<Array2<f32>>,
+    /// the names of the input columns the contribution's dimensions belong to
+    pub contribution_columns: Vec<String>,
+    /// the learned graph, only available for local training
+    pub model: Option<S2GppModel>,
+}
+
+pub(crate) type DetectionResult = std::result::Result<SyncResult, S2GppError>;
 
-impl Handler<DetectionResponse> for SinkActor<SyncResult> {
+impl Handler<DetectionResponse> for SinkActor<DetectionResult> {
     type Result = ();
 
     fn handle(&mut self, msg: DetectionResponse, _ctx: &mut Self::Context) -> Self::Result {
-        let _ = self.sink.write(msg.anomaly_score);
+        let _ = self.sink.write(msg.result);
         self.sink.close()
     }
 }
 
-pub async fn actor_fit(actor: Training, data: Array2<f32>) -> Result<SyncResult> {
-    let (sender, mut receiver) = mpsc::unbounded_channel();
-
-    let sink_actor = SinkActor::create(move |ctx| {
+/// Starts an actor that forwards the result of the training to `sender` and stops the system.
+/// Has to be called inside a running system.
+pub(crate) fn result_sink(
+    sender: mpsc::UnboundedSender<DetectionResult>,
+) -> Recipient<DetectionResponse> {
+    SinkActor::create(move |ctx| {
         let sink = MySink::new(sender);
         SinkActor::new(SinkWrite::new(sink, ctx))
-    });
+    })
+    .recipient()
+}
+
+pub async fn actor_fit(actor: Training, data: Array2<f32>) -> Result<SyncResult> {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
 
     let addr = actor.start();
     addr.do_send(StartTrainingMessage {
         nodes: Default::default(),
-        source: Some(sink_actor.recipient()),
+        source: Some(result_sink(sender)),
         data: Some(data),
     });
 
-    if let Some(r) = receiver.recv().await {
-        Ok(r)
-    } else {
-        Err(Error::msg("Await resulted in None value!"))
+    match receiver.recv().await {
+        Some(result) => Ok(result?),
+        None => Err(S2GppError::Aborted("Await resulted in None value!".to_string()).into()),
     }
 }
@@ -1,18 +1,36 @@
 use actix::prelude::*;
 use anyhow::Result;
 
+pub use crate::data_manager::MissingValues;
+pub use crate::error::S2GppError;
+pub use crate::evaluation::{evaluate, evaluate_files, Evaluation};
+pub use crate::interface::SyncResult;
 pub use crate::parameters::{Parameters, Role};
+pub use crate::training::{
+    anomaly_ranges, AnomalyRange, Bandwidth, Clustering, ContributionMetric, GraphFormat,
+    OnlineScorer, RangesFormat, S2GppModel, ScoreAlignment, ScoreNormalization, Thresholding,
+};
+pub use crate::utils::{Phase, Progress, ProgressEvent};
 
 use crate::cluster_listener::ClusterMemberListener;
-use crate::interface::SyncInterface;
-use crate::training::{StartTrainingMessage, Training};
+use crate::data_manager::data_reader::{data_shape, read_data};
+use crate::data_manager::handle_missing_values;
+use crate::interface::{result_sink, SyncInterface};
+use crate::training::{
+    align_score, write_anomaly_ranges, write_score, DetectionResponse, StartTrainingMessage,
+    Training,
+};
 use crate::utils::ClusterNodes;
 use actix_telepathy::Cluster;
 use ndarray::{Array1, Array2};
+use std::path::Path;
+use tokio::sync::mpsc;
 
 mod cluster_listener;
 mod data_manager;
 mod data_store;
+mod error;
+mod evaluation;
 mod messages;
 mod parameters;
 #[cfg(test)]
@@ -25,23 +43,129 @@ mod interface;
 mod python_binding;
 
 pub fn s2gpp(params: Parameters, data: Option<Array2<f32>>) -> Result<Option<Array1<f32>>> {
-    if let Some(data) = data {
-        let mut training = Training::init(params);
-        let anomaly_score = training.fit(data)?;
+    Ok(detect(params, data)?.map(|result| result.anomaly_score))
+}
+
+/// Like `s2gpp`, but also returns the contribution of each dimension to each score (with
+/// `explainability`) and the learned graph (after a local training). Without `data`, the results
+/// are only written to the output files.
+pub fn detect(mut params: Parameters, data: Option<Array2<f32>>) -> Result<Option<SyncResult>> {
+    if let Role::Evaluate { .. } = &params.role {
+        evaluate_files(&params)?.log();
+        return Ok(None);
+    }
+    if let Some(model_in) = params.model_in.as_ref() {
+        let model = load_model(model_in)?;
+        let data = if let Some(data) = data {
+            data
+        } else if let Role::Main {
+            data_path: Some(data_path),
+        } = &params.role
+        {
+            read_data(data_path, &params)?
+        } else {
+            return Err(S2GppError::InvalidParameters(
+                "Scoring with a model requires a data path!".to_string(),
+            )
+            .into());
+        };
+        params.validate_missing_values()?;
+        let data = handle_missing_values(data, &params.missing_values)?;
+        if let Some(graph_output_path) = params.graph_output_path.as_ref() {
+            model
+                .export_graph(graph_output_path, &params.graph_format)
+                .map_err(|e| S2GppError::writing(graph_output_path, e))?;
+        }
+        let n_rows = data.nrows();
+        let anomaly_score = model.score_normalized(data, &params.score_normalization)?;
+        let anomaly_score = align_score(anomaly_score.view(), n_rows, &params.score_alignment);
+        if let Some(output_path) = params.score_output_path.as_ref() {
+            write_score(&anomaly_score, output_path, &params)?;
+            write_anomaly_ranges(&anomaly_score, output_path, &params)?;
+        }
 
-        Ok(Some(anomaly_score))
+        Ok(Some(SyncResult {
+            anomaly_score,
+            anomaly_contribution: None,
+            contribution_columns: vec![],
+            model: Some(model),
+        }))
+    } else if let Some(data) = data {
+        params.resolve_auto(data.view());
+        params.validate(data.nrows(), data.ncols())?;
+        let mut training = Training::init(params);
+        Ok(Some(training.fit(data)?))
     } else {
+        validate_input(&mut params)?;
+        let (sender, mut receiver) = mpsc::unbounded_channel();
         let system = System::new();
-        system
-            .block_on(s2gpp_async(params))
-            .expect("Series2Graph++ did not terminate correctly!");
-        system.run().unwrap();
+        system.block_on(async move { start_training(params, Some(result_sink(sender))) });
+        let exit = system.run();
 
+        if let Ok(Err(error)) = receiver.try_recv() {
+            return Err(error.into());
+        }
+        exit.map_err(|e| S2GppError::Aborted(e.to_string()))?;
         Ok(None)
     }
 }
 
+/// Validates the parameters against the input file before any actor starts. Sub nodes have no
+/// input, so only the settings are checked there. An `auto` pattern length is estimated from the
+/// file first, which reads it one additional time.
+fn validate_input(params: &mut Parameters) -> Result<()> {
+    match params.role.clone() {
+        Role::Main {
+            data_path: Some(data_path),
+        } => {
+            let data_path = data_path.as_str();
+            if params.is_auto() && params.n_cluster_nodes == 1 {
+                let data =
+                    read_data(data_path, params).map_err(|e| S2GppError::reading(data_path, e))?;
+                params.resolve_auto(data.view());
+            }
+            let (n_rows, n_columns) =
+                data_shape(data_path, params).map_err(|e| S2GppError::reading(data_path, e))?;
+            params.validate(n_rows, n_columns)?;
+        }
+        _ => params.validate_settings()?,
+    }
+    Ok(())
+}
+
+/// Learns the graph on `data` without scoring it against other data.
+pub fn fit(mut params: Parameters, data: Array2<f32>) -> Result<S2GppModel> {
+    params.resolve_auto(data.view());
+    params.validate(data.nrows(), data.ncols())?;
+    let mut training = Training::init(params);
+    training.fit(data)?.model.ok_or_else(|| {
+        S2GppError::InvalidParameters(
+            "The model can only be extracted from a local training!".to_string(),
+        )
+        .into()
+    })
+}
+
+/// Scores `data` against the graph of a previously fitted `model`.
+pub fn score(model: &S2GppModel, data: Array2<f32>) -> Result<Array1<f32>> {
+    model.score(data)
+}
+
+pub fn save_model<P: AsRef<Path>>(model: &S2GppModel, path: P) -> Result<()> {
+    model.save(path)
+}
+
+pub fn load_model<P: AsRef<Path>>(path: P) -> Result<S2GppModel> {
+    S2GppModel::load(path)
+}
+
 pub async fn s2gpp_async(params: Parameters) -> Result<()> {
+    start_training(params, None);
+    Ok(())
+}
+
+/// Starts the training inside the running system. Its result or error is sent to `sink`.
+fn start_training(params: Parameters, sink: Option<Recipient<DetectionResponse>>) {
     let host = params.local_host;
     let seed_nodes = match &params.role {
         Role::Sub { mainhost } => vec![*mainhost],
@@ -51,15 +175,13 @@ pub async fn s2gpp_async(params: Parameters) -> Result<()> {
     let training = Training::init(params.clone()).start();
     if params.n_cluster_nodes > 1 {
         let _cluster = Cluster::new(host, seed_nodes);
-        let _cluster_listener = ClusterMemberListener::new(params, training).start();
+        let _cluster_listener = ClusterMemberListener::new(params, training, sink).start();
     } else {
         let nodes = ClusterNodes::new();
         training.do_send(StartTrainingMessage {
             nodes,
-            source: None,
+            source: sink,
             data: None,
         });
     }
-
-    Ok(())
 }
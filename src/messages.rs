@@ -1,5 +1,17 @@
+use crate::error::S2GppError;
 use actix::prelude::*;
+use actix_telepathy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 #[derive(Message)]
 #[rtype(Result = "()")]
 pub struct PoisonPill;
+
+/// Stops the training, because one of its steps failed. A failure is forwarded to the other
+/// cluster nodes, so that none of them waits for the failed one.
+#[derive(RemoteMessage, Serialize, Deserialize, Clone)]
+pub struct TrainingFailed {
+    pub error: S2GppError,
+    /// the cluster node the failure was forwarded from; `None` if it happened on this node
+    pub cluster_node: Option<usize>,
+}
@@ -1,4 +1,14 @@
-use crate::training::Clustering;
+use crate::data_manager::MissingValues;
+use crate::error::S2GppError;
+use crate::training::{
+    Bandwidth, Clustering, ContributionMetric, GraphFormat, RangesFormat, ScoreAlignment,
+    ScoreNormalization, Thresholding,
+};
+use crate::utils::period::dominant_period;
+use crate::utils::Progress;
+use log::*;
+use ndarray::ArrayView2;
+use std::cmp::Ordering;
 use std::net::SocketAddr;
 use structopt::StructOpt;
 
@@ -16,6 +26,18 @@ pub enum Role {
         #[structopt(short = "h", long = "mainhost")]
         mainhost: SocketAddr,
     },
+
+    #[structopt(name = "evaluate")]
+    Evaluate {
+        #[structopt(long = "score-path")]
+        score_path: String,
+
+        #[structopt(short = "d", long = "data-path")]
+        data_path: Option<String>,
+
+        #[structopt(long = "existence-weight", default_value = "0")]
+        existence_weight: f64,
+    },
 }
 
 impl Default for Role {
@@ -32,7 +54,12 @@ pub struct Parameters {
     #[structopt(short = "l", long = "local-host", default_value = "127.0.0.1:8000")]
     pub local_host: SocketAddr,
 
-    #[structopt(short = "p", long = "pattern-length", default_value = "50")]
+    #[structopt(
+        short = "p",
+        long = "pattern-length",
+        default_value = "50",
+        parse(try_from_str = parse_pattern_length)
+    )]
     pub pattern_length: usize,
 
     #[structopt(long = "latent", default_value = "16")]
@@ -41,6 +68,9 @@ pub struct Parameters {
     #[structopt(long = "rate", default_value = "100")]
     pub rate: usize,
 
+    #[structopt(long = "components", default_value = "3")]
+    pub n_components: usize,
+
     #[structopt(short = "t", long = "threads", default_value = "8")]
     pub n_threads: usize,
 
@@ -53,34 +83,132 @@ pub struct Parameters {
     #[structopt(long = "score-output-path")]
     pub score_output_path: Option<String>,
 
+    #[structopt(long = "score-normalization", default_value = "min-max")]
+    pub score_normalization: ScoreNormalization,
+
+    #[structopt(long = "score-alignment", default_value = "none")]
+    pub score_alignment: ScoreAlignment,
+
+    #[structopt(long = "threshold")]
+    pub thresholding: Option<Thresholding>,
+
+    #[structopt(long = "ranges-format", default_value = "csv")]
+    pub ranges_format: RangesFormat,
+
     #[structopt(long = "column-start-idx", default_value = "0")]
     pub column_start: usize,
 
     #[structopt(long = "column-end-idx", default_value = "0")]
     pub column_end: isize,
 
+    #[structopt(long = "columns", use_delimiter = true)]
+    pub columns: Vec<String>,
+
+    #[structopt(long = "delimiter", default_value = ",", parse(try_from_str = parse_byte))]
+    pub delimiter: u8,
+
+    #[structopt(long = "quote", default_value = "\"", parse(try_from_str = parse_byte))]
+    pub quote: u8,
+
+    #[structopt(long = "no-quoting")]
+    pub no_quoting: bool,
+
+    #[structopt(long = "no-header")]
+    pub no_header: bool,
+
+    #[structopt(long = "timestamp-column")]
+    pub timestamp_column: Option<String>,
+
+    #[structopt(long = "label-column")]
+    pub label_column: Option<String>,
+
+    #[structopt(long = "missing-values", default_value = "reject")]
+    pub missing_values: MissingValues,
+
     #[structopt(long = "clustering", default_value = "kde")]
     pub clustering: Clustering,
 
+    #[structopt(long = "kde-bandwidth", default_value = "scott")]
+    pub kde_bandwidth: Bandwidth,
+
+    #[structopt(long = "kde-resolution", default_value = "250")]
+    pub kde_resolution: usize,
+
+    #[structopt(long = "kde-peak-order", default_value = "1")]
+    pub kde_peak_order: usize,
+
     #[structopt(long = "explainability")]
     pub explainability: bool,
 
-    #[structopt(
-        long = "anomaly-contribution-output-path",
-        short = "aco",
-        default_value = "anomaly-contribution.csv"
-    )]
-    pub anomaly_contribution_output_path: String,
+    #[structopt(long = "anomaly-contribution-output-path", short = "aco")]
+    pub anomaly_contribution_output_path: Option<String>,
+
+    #[structopt(long = "contribution-metric", default_value = "uniqueness")]
+    pub contribution_metric: ContributionMetric,
 
     #[structopt(long = "self-correction")]
     pub self_correction: bool,
+
+    #[structopt(long = "model-out")]
+    pub model_out: Option<String>,
+
+    #[structopt(long = "model-in")]
+    pub model_in: Option<String>,
+
+    #[structopt(long = "graph-output-path")]
+    pub graph_output_path: Option<String>,
+
+    #[structopt(long = "graph-format", default_value = "graphml")]
+    pub graph_format: GraphFormat,
+
+    #[structopt(long = "report-path")]
+    pub report_path: Option<String>,
+
+    #[structopt(long = "debug-dump-dir")]
+    pub debug_dump_dir: Option<String>,
+
+    #[structopt(long = "checkpoint-dir")]
+    pub checkpoint_dir: Option<String>,
+
+    #[structopt(long = "resume-from")]
+    pub resume_from: Option<String>,
+
+    #[structopt(skip)]
+    pub progress: Progress,
+}
+
+/// Marks a `pattern_length` that is estimated from the data, see `Parameters::resolve_auto`.
+pub(crate) const AUTO_PATTERN_LENGTH: usize = 0;
+const MIN_AUTO_PATTERN_LENGTH: usize = 10;
+
+/// Parses a positive pattern length or `auto`.
+fn parse_pattern_length(s: &str) -> Result<usize, String> {
+    match s {
+        "auto" => Ok(AUTO_PATTERN_LENGTH),
+        _ => match s.parse::<usize>() {
+            Ok(AUTO_PATTERN_LENGTH) | Err(_) => Err(format!(
+                "'{}' is neither a positive pattern length nor 'auto'!",
+                s
+            )),
+            Ok(pattern_length) => Ok(pattern_length),
+        },
+    }
+}
+
+/// Parses a single ASCII character; tabs can also be given as `\t` or `tab`.
+fn parse_byte(s: &str) -> Result<u8, String> {
+    match s {
+        "\\t" | "tab" => Ok(b'\t'),
+        _ if s.len() == 1 && s.is_ascii() => Ok(s.as_bytes()[0]),
+        _ => Err(format!("'{}' is not a single ASCII character!", s)),
+    }
 }
 
 impl Parameters {
     pub fn is_main_addr(&self, addr: SocketAddr) -> bool {
         match &self.role {
             Role::Sub { mainhost } => addr.eq(mainhost),
-            Role::Main { .. } => addr.eq(&self.local_host),
+            Role::Main { .. } | Role::Evaluate { .. } => addr.eq(&self.local_host),
         }
     }
 
@@ -98,6 +226,172 @@ impl Parameters {
         }
     }
 
+    /// The exclusive index of the last used column for data with `n_columns` columns.
+    pub fn until_column(&self, n_columns: usize) -> usize {
+        match self.column_end.cmp(&0) {
+            Ordering::Equal => n_columns,
+            Ordering::Greater => self.column_end as usize,
+            Ordering::Less => (n_columns as isize + self.column_end) as usize,
+        }
+    }
+
+    /// The timestamp and label columns (in this order, if given). They are not part of the
+    /// time series, but written next to the anomaly score.
+    pub fn carried_columns(&self) -> Vec<String> {
+        self.timestamp_column
+            .iter()
+            .chain(self.label_column.iter())
+            .cloned()
+            .collect()
+    }
+
+    pub fn is_auto(&self) -> bool {
+        self.pattern_length == AUTO_PATTERN_LENGTH
+    }
+
+    /// Replaces an `auto` pattern length by the dominant period of `data` and derives `latent` and
+    /// `query_length` from it with the ratios of the defaults. Without a clear period, the default
+    /// pattern length is used. The pattern length is capped so that the data fits the validation.
+    /// Distributed settings stay `auto` and are rejected by the validation.
+    pub fn resolve_auto(&mut self, data: ArrayView2<f32>) {
+        if !self.is_auto() || self.n_cluster_nodes > 1 {
+            return;
+        }
+        let default = Parameters::default();
+        let pattern_length = dominant_period(data).unwrap_or_else(|| {
+            warn!(
+                "No dominant period found, falling back to 'pattern-length' {}",
+                default.pattern_length
+            );
+            default.pattern_length
+        });
+        let max_pattern_length = (data.nrows() / 3).max(MIN_AUTO_PATTERN_LENGTH);
+
+        self.pattern_length = pattern_length.clamp(MIN_AUTO_PATTERN_LENGTH, max_pattern_length);
+        self.latent = self.pattern_length * default.latent / default.pattern_length;
+        self.query_length = self.pattern_length * default.query_length / default.pattern_length;
+        info!(
+            "Chose 'pattern-length' {}, 'latent' {} and 'query-length' {}",
+            self.pattern_length, self.latent, self.query_length
+        );
+    }
+
+    /// Checks the parameters that do not depend on the data.
+    pub fn validate_settings(&self) -> Result<(), S2GppError> {
+        let invalid = |message: String| Err(S2GppError::InvalidParameters(message));
+
+        if self.is_auto() {
+            return invalid(if self.n_cluster_nodes > 1 {
+                "'pattern-length' auto is only available in a non-distributed setting, because the sub nodes cannot see the data.".to_string()
+            } else {
+                "'pattern-length' auto needs the data to estimate the pattern length from."
+                    .to_string()
+            });
+        }
+
+        if self.latent == 0 || self.latent >= self.pattern_length {
+            return invalid(format!(
+                "'latent' ({}) must be at least 1 and smaller than 'pattern-length' ({}), because the phase space sums up 'latent' consecutive points per dimension.",
+                self.latent, self.pattern_length
+            ));
+        }
+        if self.n_components < 3 {
+            return invalid(format!(
+                "'components' ({}) must be at least 3, because the last component is rotated onto the reference curve and the segments need at least two more.",
+                self.n_components
+            ));
+        }
+        if self.pattern_length - self.latent < self.n_components {
+            return invalid(format!(
+                "'pattern-length' - 'latent' ({}) must be at least 'components' ({}), because the phase space is reduced to 'components' principal components. Increase 'pattern-length' or decrease 'latent' or 'components'.",
+                self.pattern_length - self.latent,
+                self.n_components
+            ));
+        }
+        if self.n_threads == 0 || self.n_cluster_nodes == 0 {
+            return invalid("'threads' and 'cluster-nodes' must be at least 1.".to_string());
+        }
+        if self.rate < self.n_cluster_nodes {
+            return invalid(format!(
+                "'rate' ({}) must be at least 'cluster-nodes' ({}), because every cluster node is responsible for at least one segment.",
+                self.rate, self.n_cluster_nodes
+            ));
+        }
+        if self.kde_resolution < 3 || self.kde_peak_order == 0 {
+            return invalid(format!(
+                "'kde-resolution' ({}) must be at least 3 and 'kde-peak-order' ({}) at least 1.",
+                self.kde_resolution, self.kde_peak_order
+            ));
+        }
+        if self.model_out.is_some() && self.n_cluster_nodes > 1 {
+            return invalid(
+                "'model-out' is only available in a non-distributed setting, because the graph is spread over the cluster nodes.".to_string(),
+            );
+        }
+        self.validate_missing_values()
+    }
+
+    /// The `drop` policy removes rows, so the scores cannot be related to the rows of the input
+    /// anymore, and the rows a cluster node receives would depend on the dropped ones.
+    pub(crate) fn validate_missing_values(&self) -> Result<(), S2GppError> {
+        if self.missing_values != MissingValues::Drop {
+            return Ok(());
+        }
+        if self.n_cluster_nodes > 1 {
+            return Err(S2GppError::InvalidParameters(
+                "'missing-values' drop is only available in a non-distributed setting.".to_string(),
+            ));
+        }
+        if !self.carried_columns().is_empty() || self.score_alignment != ScoreAlignment::None {
+            return Err(S2GppError::InvalidParameters(
+                "'missing-values' drop cannot be combined with 'timestamp-column', 'label-column' or a 'score-alignment', because the scores do not belong to the rows of the input anymore.".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks the parameters against a time series with `n_rows` rows and `n_columns` columns,
+    /// counted before the columns are selected.
+    pub fn validate(&self, n_rows: usize, n_columns: usize) -> Result<(), S2GppError> {
+        self.validate_settings()?;
+        let invalid = |message: String| Err(S2GppError::InvalidParameters(message));
+
+        if self.columns.is_empty() {
+            if self.column_end.unsigned_abs() > n_columns
+                || (self.column_end < 0 && self.column_end.unsigned_abs() == n_columns)
+            {
+                return invalid(format!(
+                    "'column-end-idx' ({}) is out of range for data with {} columns.",
+                    self.column_end, n_columns
+                ));
+            }
+            let until_column = self.until_column(n_columns);
+            if self.column_start >= until_column {
+                return invalid(format!(
+                    "'column-start-idx' ({}) must be smaller than the end of the column range ({}), otherwise no column is selected.",
+                    self.column_start, until_column
+                ));
+            }
+        }
+
+        let min_rows = self.pattern_length + self.query_length;
+        if n_rows < min_rows {
+            return invalid(format!(
+                "The time series has {} rows, but needs at least 'pattern-length' + 'query-length' = {} rows. Decrease them or use a longer time series.",
+                n_rows, min_rows
+            ));
+        }
+        if n_rows / self.n_cluster_nodes < self.pattern_length {
+            return invalid(format!(
+                "Each of the {} cluster nodes gets about {} rows, which is less than 'pattern-length' ({}). Use fewer cluster nodes.",
+                self.n_cluster_nodes,
+                n_rows / self.n_cluster_nodes,
+                self.pattern_length
+            ));
+        }
+        Ok(())
+    }
+
     pub fn first_segment_of_i_next_cluster_node(&self, segment_id: usize, i: usize) -> usize {
         let i_next_cluster_node_id =
             (self.segment_id_to_assignment(segment_id) + i) % self.n_cluster_nodes;
@@ -113,16 +407,42 @@ impl Default for Parameters {
             pattern_length: 50,
             latent: 16,
             rate: 100,
+            n_components: 3,
             n_threads: 1,
             n_cluster_nodes: 1,
             query_length: 75,
             score_output_path: None,
+            score_normalization: ScoreNormalization::MinMax,
+            score_alignment: ScoreAlignment::None,
+            thresholding: None,
+            ranges_format: RangesFormat::Csv,
             column_start: 0,
             column_end: 0,
+            columns: vec![],
+            delimiter: b',',
+            quote: b'"',
+            no_quoting: false,
+            no_header: false,
+            timestamp_column: None,
+            label_column: None,
+            missing_values: MissingValues::Reject,
             clustering: Clustering::MultiKDE,
+            kde_bandwidth: Bandwidth::Scott,
+            kde_resolution: 250,
+            kde_peak_order: 1,
             explainability: false,
-            anomaly_contribution_output_path: "anomaly-contribution.csv".to_string(),
+            anomaly_contribution_output_path: None,
+            contribution_metric: ContributionMetric::Uniqueness,
             self_correction: false,
+            model_out: None,
+            model_in: None,
+            graph_output_path: None,
+            graph_format: GraphFormat::GraphML,
+            report_path: None,
+            debug_dump_dir: None,
+            checkpoint_dir: None,
+            resume_from: None,
+            progress: Progress::default(),
         }
     }
 }
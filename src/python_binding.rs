@@ -1,160 +1,323 @@
+use crate::detect;
+use crate::parameters::AUTO_PATTERN_LENGTH;
 use crate::s2gpp as orig_s2gpp;
-use crate::training::Clustering;
-use crate::{Parameters, Role};
-use numpy::{IntoPyArray, PyArray1, PyReadonlyArray2};
+use crate::training::{
+    Bandwidth, Clustering, ContributionMetric, ScoreAlignment, ScoreNormalization,
+};
+use crate::{Parameters, Progress, ProgressEvent, Role, S2GppError};
+use numpy::{IntoPyArray, PyArray1, PyArray2, PyReadonlyArray2};
 use pyo3::exceptions;
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
 use std::panic;
 use std::str::FromStr;
 
+/// Maps the errors of Series2Graph++ to the closest Python exception.
+fn to_py_err(error: anyhow::Error) -> PyErr {
+    match error.downcast_ref::<S2GppError>() {
+        Some(S2GppError::Io { .. }) => exceptions::PyIOError::new_err(error.to_string()),
+        Some(S2GppError::InvalidData(_))
+        | Some(S2GppError::InvalidParameters(_))
+        | Some(S2GppError::TooFewEdges { .. }) => {
+            exceptions::PyValueError::new_err(error.to_string())
+        }
+        Some(S2GppError::NoIntersection { .. }) | Some(S2GppError::Aborted(_)) | None => {
+            exceptions::PyRuntimeError::new_err(error.to_string())
+        }
+    }
+}
+
+fn progress_event_dict<'py>(py: Python<'py>, event: &ProgressEvent) -> PyResult<&'py PyDict> {
+    let dict = PyDict::new(py);
+    match event {
+        ProgressEvent::Step {
+            step,
+            n_steps,
+            title,
+        } => {
+            dict.set_item("step", step)?;
+            dict.set_item("n_steps", n_steps)?;
+            dict.set_item("title", title)?;
+        }
+        ProgressEvent::Advanced { phase, done, total } => {
+            dict.set_item("phase", phase.to_string())?;
+            dict.set_item("done", done)?;
+            dict.set_item("total", total)?;
+        }
+    }
+    Ok(dict)
+}
+
+/// Calls the Python `callback` with a dict per progress event. Errors of the callback are printed,
+/// but do not stop the run.
+fn py_progress(callback: Option<PyObject>) -> Progress {
+    match callback {
+        Some(callback) => Progress::with_callback(move |event| {
+            Python::with_gil(|py| {
+                if let Err(e) = progress_event_dict(py, event)
+                    .and_then(|dict| callback.call1(py, (dict,)).map(|_| ()))
+                {
+                    e.print(py);
+                }
+            })
+        }),
+        None => Progress::default(),
+    }
+}
+
 #[pyfunction]
 fn s2gpp_local_array<'py>(
     py: Python<'py>,
     data: PyReadonlyArray2<'py, f32>,
-    pattern_length: usize,
+    pattern_length: Option<usize>,
     latent: usize,
     query_length: usize,
     rate: usize,
+    n_components: usize,
     n_threads: usize,
     clustering: String,
+    kde_bandwidth: String,
+    kde_resolution: usize,
+    kde_peak_order: usize,
+    score_normalization: String,
+    score_alignment: String,
     self_correction: bool,
-) -> PyResult<&'py PyArray1<f32>> {
+    explainability: bool,
+    contribution_metric: String,
+    progress: Option<PyObject>,
+) -> PyResult<(&'py PyArray1<f32>, Option<&'py PyArray2<f32>>)> {
     let mut params = Parameters::default();
-    params.pattern_length = pattern_length;
+    params.pattern_length = pattern_length.unwrap_or(AUTO_PATTERN_LENGTH);
     params.latent = latent;
     params.query_length = query_length;
     params.rate = rate;
+    params.n_components = n_components;
     params.n_threads = n_threads;
-    params.clustering = Clustering::from_str(&clustering).unwrap();
+    params.clustering =
+        Clustering::from_str(&clustering).map_err(exceptions::PyValueError::new_err)?;
+    params.kde_bandwidth =
+        Bandwidth::from_str(&kde_bandwidth).map_err(exceptions::PyValueError::new_err)?;
+    params.kde_resolution = kde_resolution;
+    params.kde_peak_order = kde_peak_order;
+    params.score_normalization = ScoreNormalization::from_str(&score_normalization)
+        .map_err(exceptions::PyValueError::new_err)?;
+    params.score_alignment =
+        ScoreAlignment::from_str(&score_alignment).map_err(exceptions::PyValueError::new_err)?;
     params.self_correction = self_correction;
+    params.explainability = explainability;
+    params.contribution_metric = ContributionMetric::from_str(&contribution_metric)
+        .map_err(exceptions::PyValueError::new_err)?;
+    params.progress = py_progress(progress);
 
     let data = data.as_array().to_owned();
-    let anomaly_scores =
-        orig_s2gpp(params, Some(data)).expect("Series2Graph++ did not terminate correctly!");
+    let result = py
+        .allow_threads(move || detect(params, Some(data)))
+        .map_err(to_py_err)?;
 
-    match anomaly_scores {
-        Some(res) => Ok(res.into_pyarray(py)),
-        None => Err(exceptions::PyTypeError::new_err("Error message")),
+    match result {
+        Some(res) => Ok((
+            res.anomaly_score.into_pyarray(py),
+            res.anomaly_contribution
+                .map(|contribution| contribution.into_pyarray(py)),
+        )),
+        None => Err(exceptions::PyRuntimeError::new_err(
+            "Series2Graph++ did not return an anomaly score!",
+        )),
     }
 }
 
 #[pyfunction]
 fn s2gpp_local_file<'py>(
-    _py: Python<'py>,
+    py: Python<'py>,
     data_path: String,
-    pattern_length: usize,
+    pattern_length: Option<usize>,
     latent: usize,
     query_length: usize,
     rate: usize,
+    n_components: usize,
     n_threads: usize,
     score_output_path: Option<String>,
     column_start: usize,
     column_end: isize,
     clustering: String,
+    kde_bandwidth: String,
+    kde_resolution: usize,
+    kde_peak_order: usize,
+    score_normalization: String,
+    score_alignment: String,
     self_correction: bool,
+    explainability: bool,
+    contribution_metric: String,
     local_host: String,
+    progress: Option<PyObject>,
 ) -> PyResult<()> {
-    let result = panic::catch_unwind(|| {
+    let result = panic::catch_unwind(|| -> PyResult<()> {
         let mut params = Parameters::default();
 
         params.role = Role::Main {
             data_path: Some(data_path),
         };
-        params.pattern_length = pattern_length;
+        params.pattern_length = pattern_length.unwrap_or(AUTO_PATTERN_LENGTH);
         params.latent = latent;
         params.query_length = query_length;
         params.rate = rate;
+        params.n_components = n_components;
         params.n_threads = n_threads;
         params.score_output_path = score_output_path;
         params.column_start = column_start;
         params.column_end = column_end;
-        params.clustering = Clustering::from_str(&clustering).unwrap();
+        params.clustering =
+            Clustering::from_str(&clustering).map_err(exceptions::PyValueError::new_err)?;
+        params.kde_bandwidth =
+            Bandwidth::from_str(&kde_bandwidth).map_err(exceptions::PyValueError::new_err)?;
+        params.kde_resolution = kde_resolution;
+        params.kde_peak_order = kde_peak_order;
+        params.score_normalization = ScoreNormalization::from_str(&score_normalization)
+            .map_err(exceptions::PyValueError::new_err)?;
+        params.score_alignment = ScoreAlignment::from_str(&score_alignment)
+            .map_err(exceptions::PyValueError::new_err)?;
         params.self_correction = self_correction;
-        params.local_host = local_host.parse().unwrap();
+        params.explainability = explainability;
+        params.contribution_metric = ContributionMetric::from_str(&contribution_metric)
+            .map_err(exceptions::PyValueError::new_err)?;
+        params.progress = py_progress(progress);
+        params.local_host = local_host.parse()?;
 
-        orig_s2gpp(params, None).expect("Series2Graph++ did not terminate correctly!");
+        py.allow_threads(move || orig_s2gpp(params, None))
+            .map_err(to_py_err)?;
+        Ok(())
     });
     match result {
-        Ok(_) => Ok(()),
-        Err(_) => Err(exceptions::PyTypeError::new_err("Error message")),
+        Ok(result) => result,
+        Err(_) => Err(exceptions::PyRuntimeError::new_err(
+            "Series2Graph++ did not terminate correctly!",
+        )),
     }
 }
 
 #[pyfunction]
 fn s2gpp_distributed_main<'py>(
-    _py: Python<'py>,
+    py: Python<'py>,
     data_path: String,
-    pattern_length: usize,
+    pattern_length: Option<usize>,
     latent: usize,
     query_length: usize,
     rate: usize,
+    n_components: usize,
     n_threads: usize,
     score_output_path: Option<String>,
     column_start: usize,
     column_end: isize,
     clustering: String,
+    kde_bandwidth: String,
+    kde_resolution: usize,
+    kde_peak_order: usize,
+    score_normalization: String,
+    score_alignment: String,
     self_correction: bool,
+    explainability: bool,
+    contribution_metric: String,
     local_host: String,
     n_cluster_nodes: usize,
+    progress: Option<PyObject>,
 ) -> PyResult<()> {
     let mut params = Parameters::default();
 
     params.role = Role::Main {
         data_path: Some(data_path),
     };
-    params.pattern_length = pattern_length;
+    params.pattern_length = pattern_length.unwrap_or(AUTO_PATTERN_LENGTH);
     params.latent = latent;
     params.query_length = query_length;
     params.rate = rate;
+    params.n_components = n_components;
     params.n_threads = n_threads;
     params.score_output_path = score_output_path;
     params.column_start = column_start;
     params.column_end = column_end;
-    params.clustering = Clustering::from_str(&clustering).unwrap();
+    params.clustering =
+        Clustering::from_str(&clustering).map_err(exceptions::PyValueError::new_err)?;
+    params.kde_bandwidth =
+        Bandwidth::from_str(&kde_bandwidth).map_err(exceptions::PyValueError::new_err)?;
+    params.kde_resolution = kde_resolution;
+    params.kde_peak_order = kde_peak_order;
+    params.score_normalization = ScoreNormalization::from_str(&score_normalization)
+        .map_err(exceptions::PyValueError::new_err)?;
+    params.score_alignment =
+        ScoreAlignment::from_str(&score_alignment).map_err(exceptions::PyValueError::new_err)?;
     params.self_correction = self_correction;
+    params.explainability = explainability;
+    params.contribution_metric = ContributionMetric::from_str(&contribution_metric)
+        .map_err(exceptions::PyValueError::new_err)?;
+    params.progress = py_progress(progress);
     params.local_host = local_host.parse()?;
     params.n_cluster_nodes = n_cluster_nodes;
 
-    orig_s2gpp(params, None).expect("Series2Graph++ did not terminate correctly!");
+    py.allow_threads(move || orig_s2gpp(params, None))
+        .map_err(to_py_err)?;
     Ok(())
 }
 
 #[pyfunction]
 fn s2gpp_distributed_sub<'py>(
-    _py: Python<'py>,
-    pattern_length: usize,
+    py: Python<'py>,
+    pattern_length: Option<usize>,
     latent: usize,
     query_length: usize,
     rate: usize,
+    n_components: usize,
     n_threads: usize,
     score_output_path: Option<String>,
     column_start: usize,
     column_end: isize,
     clustering: String,
+    kde_bandwidth: String,
+    kde_resolution: usize,
+    kde_peak_order: usize,
+    score_normalization: String,
+    score_alignment: String,
     self_correction: bool,
+    explainability: bool,
+    contribution_metric: String,
     local_host: String,
     n_cluster_nodes: usize,
     mainhost: String,
+    progress: Option<PyObject>,
 ) -> PyResult<()> {
     let mut params = Parameters::default();
 
     params.role = Role::Sub {
         mainhost: mainhost.parse()?,
     };
-    params.pattern_length = pattern_length;
+    params.pattern_length = pattern_length.unwrap_or(AUTO_PATTERN_LENGTH);
     params.latent = latent;
     params.query_length = query_length;
     params.rate = rate;
+    params.n_components = n_components;
     params.n_threads = n_threads;
     params.score_output_path = score_output_path;
     params.column_start = column_start;
     params.column_end = column_end;
-    params.clustering = Clustering::from_str(&clustering).unwrap();
+    params.clustering =
+        Clustering::from_str(&clustering).map_err(exceptions::PyValueError::new_err)?;
+    params.kde_bandwidth =
+        Bandwidth::from_str(&kde_bandwidth).map_err(exceptions::PyValueError::new_err)?;
+    params.kde_resolution = kde_resolution;
+    params.kde_peak_order = kde_peak_order;
+    params.score_normalization = ScoreNormalization::from_str(&score_normalization)
+        .map_err(exceptions::PyValueError::new_err)?;
+    params.score_alignment =
+        ScoreAlignment::from_str(&score_alignment).map_err(exceptions::PyValueError::new_err)?;
     params.self_correction = self_correction;
+    params.explainability = explainability;
+    params.contribution_metric = ContributionMetric::from_str(&contribution_metric)
+        .map_err(exceptions::PyValueError::new_err)?;
+    params.progress = py_progress(progress);
     params.local_host = local_host.parse()?;
     params.n_cluster_nodes = n_cluster_nodes;
 
-    orig_s2gpp(params, None).expect("Series2Graph++ did not terminate correctly!");
+    py.allow_threads(move || orig_s2gpp(params, None))
+        .map_err(to_py_err)?;
     Ok(())
 }
 
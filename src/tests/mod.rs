@@ -2,18 +2,21 @@ pub mod utils;
 
 use crate::cluster_listener::ClusterMemberListener;
 use crate::data_manager::data_reader::read_data_;
-use crate::parameters::{Parameters, Role};
-use crate::training::{Clustering, StartTrainingMessage, Training};
+use crate::data_manager::MissingValues;
+use crate::parameters::{Parameters, Role, AUTO_PATTERN_LENGTH};
+use crate::training::{Clustering, ScoreAlignment, StartTrainingMessage, Training};
 use crate::utils::ClusterNodes;
-use crate::{s2gpp, SyncInterface};
+use crate::{detect, s2gpp, S2GppError, SyncInterface};
 use actix::prelude::*;
 use actix_rt::System;
 use actix_telepathy::Cluster;
+use ndarray::{Array1, Axis};
 use ndarray_linalg::close_l1;
 use port_scanner::request_open_port;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use std::env::temp_dir;
 use std::fs::remove_file;
+use structopt::StructOpt;
 
 const ESTIMATED_SCORES_PATH: &str = "ts_0.csv.scores";
 const EXPECTED_SCORES_PATH: &str = "data/ts_0.csv.scores";
@@ -57,6 +60,40 @@ fn global_test_kde_clustering_provided_data() {
     assert!(result.is_some());
 }
 
+#[test]
+#[ignore] // takes some time
+fn aligned_score_has_input_length() {
+    let params: Parameters = Parameters {
+        score_alignment: ScoreAlignment::MovingAverage,
+        ..Default::default()
+    };
+
+    let data = read_data_("data/ts_0.csv");
+    let n_rows = data.nrows();
+
+    let score = s2gpp(params, Some(data)).unwrap().unwrap();
+
+    assert_eq!(score.len(), n_rows);
+}
+
+#[test]
+#[ignore] // takes some time
+fn more_components_score_every_point() {
+    let params: Parameters = Parameters {
+        n_components: 5,
+        score_alignment: ScoreAlignment::Edge,
+        ..Default::default()
+    };
+
+    let data = read_data_("data/ts_0.csv");
+    let n_rows = data.nrows();
+
+    let result = detect(params, Some(data)).unwrap().unwrap();
+
+    assert_eq!(result.anomaly_score.len(), n_rows);
+    assert_eq!(result.model.unwrap().components[0].nrows(), 5);
+}
+
 #[test]
 #[ignore] // takes some time
 fn global_comut_distributed_2() {
@@ -116,7 +153,7 @@ fn run_single_global_comut(params: Parameters) {
         let training = Training::init(params.clone()).start();
         if params.n_cluster_nodes > 1 {
             let _cluster = Cluster::new(host, seed_nodes);
-            let _cluster_listener = ClusterMemberListener::new(params, training).start();
+            let _cluster_listener = ClusterMemberListener::new(params, training, None).start();
         } else {
             let nodes = ClusterNodes::new();
             training.do_send(StartTrainingMessage {
@@ -138,3 +175,201 @@ fn check_for_comut_score() {
 
     close_l1(&estimated_scores, &expected_scores, 0.000001);
 }
+
+#[test]
+fn missing_input_file_is_reported() {
+    let params: Parameters = Parameters {
+        role: Role::Main {
+            data_path: Some("data/does_not_exist.csv".to_string()),
+        },
+        local_host: format!("127.0.0.1:{}", request_open_port().unwrap_or(1994))
+            .parse()
+            .unwrap(),
+        ..Default::default()
+    };
+
+    let error = s2gpp(params, None).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<S2GppError>(),
+        Some(S2GppError::Io { .. })
+    ));
+}
+
+#[test]
+fn unwritable_score_output_is_reported() {
+    let params: Parameters = Parameters {
+        score_output_path: Some("data/does_not_exist/scores.csv".to_string()),
+        ..Default::default()
+    };
+
+    let error = s2gpp(params, Some(read_data_("data/ts_0.csv"))).unwrap_err();
+    match error.downcast_ref::<S2GppError>() {
+        Some(S2GppError::Io { path, .. }) => assert_eq!(path, "data/does_not_exist/scores.csv"),
+        _ => panic!("Expected an IO error, but got: {}", error),
+    }
+}
+
+#[test]
+fn unwritable_model_output_is_reported() {
+    let params: Parameters = Parameters {
+        model_out: Some("data/does_not_exist/model.s2gpp".to_string()),
+        ..Default::default()
+    };
+
+    let error = s2gpp(params, Some(read_data_("data/ts_0.csv"))).unwrap_err();
+    match error.downcast_ref::<S2GppError>() {
+        Some(S2GppError::Io { path, .. }) => assert_eq!(path, "data/does_not_exist/model.s2gpp"),
+        _ => panic!("Expected an IO error, but got: {}", error),
+    }
+}
+
+#[test]
+fn unwritable_graph_output_is_reported() {
+    let params: Parameters = Parameters {
+        graph_output_path: Some("data/does_not_exist/graph.graphml".to_string()),
+        ..Default::default()
+    };
+
+    let error = s2gpp(params, Some(read_data_("data/ts_0.csv"))).unwrap_err();
+    match error.downcast_ref::<S2GppError>() {
+        Some(S2GppError::Io { path, .. }) => {
+            assert_eq!(path, "data/does_not_exist/graph.graphml")
+        }
+        _ => panic!("Expected an IO error, but got: {}", error),
+    }
+}
+
+#[test]
+fn unwritable_report_is_reported() {
+    let params: Parameters = Parameters {
+        report_path: Some("data/does_not_exist/report.json".to_string()),
+        ..Default::default()
+    };
+
+    let error = s2gpp(params, Some(read_data_("data/ts_0.csv"))).unwrap_err();
+    match error.downcast_ref::<S2GppError>() {
+        Some(S2GppError::Io { path, .. }) => assert_eq!(path, "data/does_not_exist/report.json"),
+        _ => panic!("Expected an IO error, but got: {}", error),
+    }
+}
+
+#[test]
+fn missing_values_are_reported() {
+    let mut data = read_data_("data/ts_0.csv");
+    data[[10, 1]] = f32::NAN;
+
+    let error = s2gpp(Parameters::default(), Some(data)).unwrap_err();
+    match error.downcast_ref::<S2GppError>() {
+        Some(S2GppError::InvalidData(message)) => assert!(message.contains("row 10 column 1")),
+        _ => panic!("Expected invalid data, but got: {}", error),
+    }
+}
+
+#[test]
+fn parameters_are_validated_against_data() {
+    let params = Parameters::default();
+    assert!(params.validate(10000, 2).is_ok());
+    assert!(params.validate(100, 2).is_err());
+    // TimeEval-style settings query with windows shorter than the pattern
+    let shorter_query = Parameters {
+        query_length: 45,
+        ..Default::default()
+    };
+    assert!(shorter_query.validate(10000, 2).is_ok());
+
+    let invalid = [
+        Parameters {
+            latent: 50,
+            ..Default::default()
+        },
+        Parameters {
+            rate: 2,
+            n_cluster_nodes: 3,
+            ..Default::default()
+        },
+        Parameters {
+            n_components: 2,
+            ..Default::default()
+        },
+        Parameters {
+            n_components: 40,
+            ..Default::default()
+        },
+        Parameters {
+            missing_values: MissingValues::Drop,
+            n_cluster_nodes: 2,
+            ..Default::default()
+        },
+        Parameters {
+            missing_values: MissingValues::Drop,
+            label_column: Some("is_anomaly".to_string()),
+            ..Default::default()
+        },
+        Parameters {
+            missing_values: MissingValues::Drop,
+            score_alignment: ScoreAlignment::Edge,
+            ..Default::default()
+        },
+        Parameters {
+            model_out: Some("model.s2gpp".to_string()),
+            n_cluster_nodes: 2,
+            ..Default::default()
+        },
+        Parameters {
+            column_end: 3,
+            ..Default::default()
+        },
+        Parameters {
+            column_end: -2,
+            ..Default::default()
+        },
+        Parameters {
+            column_start: 1,
+            column_end: 1,
+            ..Default::default()
+        },
+    ];
+    for params in invalid.iter() {
+        assert!(matches!(
+            params.validate(10000, 2),
+            Err(S2GppError::InvalidParameters(_))
+        ));
+    }
+}
+
+#[test]
+fn invalid_parameters_are_rejected_before_training() {
+    let params = Parameters {
+        latent: 60,
+        ..Default::default()
+    };
+    let error = s2gpp(params, Some(read_data_("data/ts_0.csv"))).unwrap_err();
+    assert!(error.to_string().contains("'latent' (60)"));
+}
+
+#[test]
+fn auto_pattern_length_is_estimated_from_data() {
+    let mut params = Parameters::from_iter(&["s2gpp", "--pattern-length", "auto", "main"]);
+    assert!(params.is_auto());
+
+    let data = Array1::range(0., 5000., 1.)
+        .mapv(|x: f32| (2. * std::f32::consts::PI * x / 40.).sin())
+        .insert_axis(Axis(1));
+    params.resolve_auto(data.view());
+    assert_eq!(
+        (params.pattern_length, params.latent, params.query_length),
+        (40, 12, 60)
+    );
+    assert!(params.validate(data.nrows(), data.ncols()).is_ok());
+}
+
+#[test]
+fn auto_pattern_length_is_rejected_when_distributed() {
+    let mut params = Parameters {
+        pattern_length: AUTO_PATTERN_LENGTH,
+        n_cluster_nodes: 2,
+        ..Default::default()
+    };
+    params.resolve_auto(read_data_("data/ts_0.csv").view());
+    assert!(params.validate_settings().is_err());
+}
@@ -2,26 +2,63 @@ mod messages;
 #[cfg(test)]
 mod tests;
 
+use crate::data_manager::data_reader::read_column_names;
 use crate::data_store::node::NodeRef;
-pub(crate) use crate::training::anomaly_contribution::messages::{
-    ClusterCenterMessage, QueryClusterContribution, QueryClusterContributionResponse,
-    QueryClustercontributionDone,
-};
+use crate::parameters::{Parameters, Role};
+pub(crate) use crate::training::anomaly_contribution::messages::NodeContributions;
+use crate::training::scoring::alignment::align_score;
+use crate::training::scoring::messages::ScoreInitDone;
+use crate::training::scoring::weights::ScoringWeights;
+use crate::training::Training;
 use crate::utils::float_approx::FloatApprox;
-use actix::{Actor, Context, Handler};
+use crate::utils::rotation_protocol::RotationProtocol;
+use actix::{AsyncContext, Context, Handler};
 use anyhow::{Error, Result};
-use ndarray::{concatenate, s, stack, Array1, Array2, ArrayView1, Axis, Dim};
+use csv::WriterBuilder;
+use log::*;
+use ndarray::{s, stack, Array1, Array2, ArrayView1, Axis};
 use ndarray_stats::SummaryStatisticsExt;
 use num_traits::ToPrimitive;
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
-use std::ops::{Div, Sub};
+use std::ops::{Deref, Div, Sub};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-#[derive(Default, Debug)]
+/// How the contribution of an input dimension to a node is measured. The cluster centers of the
+/// nodes already hold one coordinate per input dimension: the distance of the intersection to the
+/// origin in the rotated plane of this dimension.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContributionMetric {
+    /// how rarely the intersections of a segment share the node's coordinate
+    Uniqueness,
+    /// how far the node's coordinate lies from the mean coordinate of its segment
+    Distance,
+}
+
+impl FromStr for ContributionMetric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "uniqueness" => Ok(ContributionMetric::Uniqueness),
+            "distance" => Ok(ContributionMetric::Distance),
+            _ => Err(format!(
+                "{} is not a valid contribution metric! Allowed values are: 'uniqueness' and 'distance'",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Default, Clone)]
 pub(crate) struct AnomalyContribution {
     node_contribution: HashMap<NodeRef, Array1<f32>>,
-    query_response: Option<Vec<Array1<f32>>>,
-    n_dims: Option<usize>,
+    /// per-dimension contribution of each score
+    pub contribution: Option<Array2<f32>>,
+    /// names of the input columns of the contribution's dimensions
+    pub columns: Vec<String>,
+    node_contribution_rotation_protocol: RotationProtocol<NodeContributions>,
 }
 
 impl AnomalyContribution {
@@ -30,13 +67,16 @@ impl AnomalyContribution {
         nodes: Vec<NodeRef>,
         cluster_centers: Array2<f32>,
         label_counts: Vec<usize>,
+        metric: &ContributionMetric,
     ) {
-        let adapted_cluster_centers = cluster_centers; //self.combine_dimensions(cluster_centers).unwrap();
-        let dim_scores = self.calculate_dimension_uniqueness(adapted_cluster_centers, label_counts);
-        match &self.n_dims {
-            None => self.n_dims = Some(dim_scores.shape()[1]),
-            Some(_) => (),
-        }
+        let dim_scores = match metric {
+            ContributionMetric::Uniqueness => {
+                self.calculate_dimension_uniqueness(cluster_centers, label_counts)
+            }
+            ContributionMetric::Distance => self
+                .calculate_distances(cluster_centers, label_counts)
+                .expect("Could not calculate the distances of the cluster centers!"),
+        };
 
         for node in nodes {
             let dim_score = dim_scores.row(node.get_cluster());
@@ -78,7 +118,6 @@ impl AnomalyContribution {
         result
     }
 
-    #[allow(dead_code)]
     fn calculate_distances(
         &self,
         centers: Array2<f32>,
@@ -101,28 +140,37 @@ impl AnomalyContribution {
         Ok(distances)
     }
 
-    /// A multidimensional time series with $d$ dimensions results in a multidimensional projection
-    /// with $2d$ dimensions. The intersection coordinates have $2d-1$ dimensions, because the first
-    /// dimension is the distance of the intersection in $d_1$ and $d_2$ to the origin. Thus, we must
-    /// transform the remaining dimensions $2d - 2$ into a $d$-dimensional array. Thereby, we always
-    /// add the coordinates together.
-    #[allow(dead_code)]
-    fn combine_dimensions(&self, cluster_centers: Array2<f32>) -> Result<Array2<f32>> {
-        let cc_shape = cluster_centers.shape();
-        let first_dim = cluster_centers.column(0).insert_axis(Axis(1));
-        let remaining_dims = cluster_centers.slice(s![.., 1..cc_shape[1]]);
-        let shape = remaining_dims.shape();
-        let new_shape = Dim([shape[0] * shape[1].div(2), 2]);
-        let reshaped_remaining = remaining_dims.to_owned().into_shape(new_shape)?;
-        let remaining_dims = reshaped_remaining.sum_axis(Axis(1));
-        let remaining_dims = remaining_dims
-            .view()
-            .into_shape(Dim([shape[0], shape[1].div(2)]))?;
-        Ok(concatenate(Axis(1), &[first_dim, remaining_dims])?)
-    }
-
-    fn query_node_score(&mut self, nodes: Vec<NodeRef>) {
-        let mut contributions: Vec<ArrayView1<f32>> = nodes
+    /// The contributions of the known nodes without references, e.g. to send or checkpoint them.
+    pub(crate) fn node_contributions(&self) -> NodeContributions {
+        NodeContributions {
+            contributions: self
+                .node_contribution
+                .iter()
+                .map(|(node, contribution)| (node.deref().clone(), contribution.clone()))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn add_node_contributions(&mut self, node_contributions: NodeContributions) {
+        for (node, contribution) in node_contributions.contributions {
+            self.node_contribution.insert(node.into_ref(), contribution);
+        }
+    }
+
+    pub(crate) fn is_rotating(&self) -> bool {
+        self.node_contribution_rotation_protocol.is_running()
+    }
+
+    fn n_dims(&self) -> usize {
+        self.node_contribution
+            .values()
+            .next()
+            .map_or(0, |contribution| contribution.len())
+    }
+
+    /// The mean contribution of the `nodes` a query passes. Queries without known nodes get zeros.
+    fn query_contribution(&self, nodes: &[NodeRef]) -> Array1<f32> {
+        let contributions: Vec<ArrayView1<f32>> = nodes
             .iter()
             .map(|node| {
                 self.node_contribution
@@ -130,62 +178,187 @@ impl AnomalyContribution {
                     .expect("Node is not registered")
                     .view()
             })
+            .filter(|x| !x.sum().is_nan())
             .collect();
 
-        let contribution = if contributions.is_empty() {
-            let n_dims = *self.n_dims.as_ref().unwrap();
-            Array1::zeros([n_dims])
+        if contributions.is_empty() {
+            Array1::zeros([self.n_dims()])
         } else {
-            contributions = contributions
-                .into_iter()
-                .filter(|x| !x.sum().is_nan())
-                .collect();
             stack(Axis(1), contributions.as_slice())
                 .unwrap()
                 .mean_axis(Axis(1))
                 .unwrap()
-        };
-
-        match &mut self.query_response {
-            Some(query_response) => query_response.push(contribution),
-            None => {
-                self.query_response = Some(vec![contribution]);
-            }
-        };
+        }
     }
 }
 
-impl Actor for AnomalyContribution {
-    type Context = Context<Self>;
+/// Queries without contribution repeat the previous one. Afterwards, the median of each
+/// dimension is subtracted, so that the contributions show the deviation from normal behaviour.
+pub(crate) fn center_contributions(mut contributions: Array2<f32>) -> Array2<f32> {
+    for i in 1..contributions.nrows() {
+        if contributions.row(i).sum() == 0.0 {
+            let last = contributions.row(i - 1).to_owned();
+            contributions.row_mut(i).assign(&last);
+        }
+    }
+
+    for mut dim in contributions.axis_iter_mut(Axis(1)) {
+        let mut sorted: Vec<FloatApprox<f32>> = dim.iter().map(|x| FloatApprox(*x)).collect();
+        sorted.sort();
+        if let Some(median) = sorted.get(sorted.len().div(2)) {
+            let median = median.0;
+            dim.mapv_inplace(|x| x - median);
+        }
+    }
+    contributions
 }
 
-impl Handler<ClusterCenterMessage> for AnomalyContribution {
-    type Result = ();
+/// The names of the input columns, if the data was read from a file with a header, or else their
+/// indices.
+fn contribution_columns(parameters: &Parameters, n_dims: usize) -> Vec<String> {
+    let names = match &parameters.role {
+        Role::Main {
+            data_path: Some(data_path),
+        } => read_column_names(data_path, parameters)
+            .map_err(|e| warn!("Could not read the column names of the contribution: {}", e))
+            .ok(),
+        _ => None,
+    };
+    match names {
+        Some(names) if names.len() == n_dims => names,
+        _ => (0..n_dims).map(|i| i.to_string()).collect(),
+    }
+}
 
-    fn handle(&mut self, msg: ClusterCenterMessage, _ctx: &mut Self::Context) -> Self::Result {
-        self.record_contributions(msg.nodes, msg.cluster_centers, msg.label_counts)
+/// The given output path or, next to the score, e.g. `scores.contribution.csv` for `scores.csv`.
+pub(crate) fn contribution_path(parameters: &Parameters) -> Option<PathBuf> {
+    match (
+        &parameters.anomaly_contribution_output_path,
+        &parameters.score_output_path,
+    ) {
+        (Some(output_path), _) => Some(PathBuf::from(output_path)),
+        (None, Some(score_output_path)) => {
+            Some(Path::new(score_output_path).with_extension("contribution.csv"))
+        }
+        (None, None) => None,
     }
 }
 
-impl Handler<QueryClusterContribution> for AnomalyContribution {
-    type Result = ();
+pub(crate) trait AnomalyContributor {
+    fn start_node_contribution_rotation(&mut self, ctx: &mut Context<Training>);
+    fn query_contributions(&self) -> Array2<f32>;
+    fn finalize_contribution(&mut self, contributions: Array2<f32>);
+    fn output_contribution(&self, output_path: PathBuf) -> Result<()>;
+}
+
+impl AnomalyContributor for Training {
+    fn start_node_contribution_rotation(&mut self, ctx: &mut Context<Training>) {
+        self.anomaly_contribution
+            .node_contribution_rotation_protocol
+            .start(self.cluster_nodes.len());
+        self.anomaly_contribution
+            .node_contribution_rotation_protocol
+            .resolve_buffer(ctx.address().recipient());
+        self.cluster_nodes
+            .get_as(&self.cluster_nodes.get_next_idx().unwrap(), "Training")
+            .unwrap()
+            .do_send(self.anomaly_contribution.node_contributions());
+        self.anomaly_contribution
+            .node_contribution_rotation_protocol
+            .sent();
+    }
 
-    fn handle(&mut self, msg: QueryClusterContribution, _ctx: &mut Self::Context) -> Self::Result {
-        self.query_node_score(msg.nodes)
+    /// The contributions of the queries this cluster node has scored, in the order of the scores.
+    fn query_contributions(&self) -> Array2<f32> {
+        let edges = self.data_store.get_edges();
+        let query_length = self.parameters.query_length;
+        let end = self.scoring.edges_in_time.len() - (query_length - 1);
+        let contributions: Vec<Array1<f32>> = (0..end)
+            .map(|i| {
+                let from_edge_idx = self.scoring.edges_in_time[i];
+                let to_edge_idx = self.scoring.edges_in_time[i + query_length - 1] + 1;
+                let nodes: Vec<NodeRef> = edges[from_edge_idx..to_edge_idx.min(edges.len())]
+                    .iter()
+                    .map(|e| e.get_from_node())
+                    .collect();
+                self.anomaly_contribution.query_contribution(&nodes)
+            })
+            .collect();
+        let views: Vec<ArrayView1<f32>> = contributions.iter().map(|c| c.view()).collect();
+        if views.is_empty() {
+            Array2::zeros((0, self.anomaly_contribution.n_dims()))
+        } else {
+            stack(Axis(0), views.as_slice()).unwrap()
+        }
+    }
+
+    /// Centers the contributions of all queries and aligns them like the score.
+    fn finalize_contribution(&mut self, contributions: Array2<f32>) {
+        let contributions = center_contributions(contributions);
+        let n = self
+            .dataset_stats
+            .as_ref()
+            .and_then(|stats| stats.n)
+            .unwrap_or_else(|| contributions.nrows());
+        let aligned: Vec<Array1<f32>> = contributions
+            .axis_iter(Axis(1))
+            .map(|dim| align_score(dim, n, &self.parameters.score_alignment))
+            .collect();
+        let aligned: Vec<ArrayView1<f32>> = aligned.iter().map(|dim| dim.view()).collect();
+        self.anomaly_contribution.columns =
+            contribution_columns(&self.parameters, contributions.ncols());
+        self.anomaly_contribution.contribution = Some(if aligned.is_empty() {
+            contributions
+        } else {
+            stack(Axis(1), aligned.as_slice()).unwrap()
+        });
+    }
+
+    fn output_contribution(&self, output_path: PathBuf) -> Result<()> {
+        let contribution = self
+            .anomaly_contribution
+            .contribution
+            .as_ref()
+            .expect("Please, calculate the contribution before saving to file!");
+        let mut writer = WriterBuilder::new().from_path(output_path)?;
+        writer.write_record(&self.anomaly_contribution.columns)?;
+        for row in contribution.rows() {
+            writer.serialize(row.to_vec())?;
+        }
+        writer.flush()?;
+        Ok(())
     }
 }
 
-impl Handler<QueryClustercontributionDone> for AnomalyContribution {
+impl Handler<NodeContributions> for Training {
     type Result = ();
 
-    fn handle(
-        &mut self,
-        msg: QueryClustercontributionDone,
-        _ctx: &mut Self::Context,
-    ) -> Self::Result {
-        msg.receiver
-            .do_send(QueryClusterContributionResponse {
-                contributions: self.query_response.take().unwrap(),
-            })
+    fn handle(&mut self, msg: NodeContributions, ctx: &mut Self::Context) -> Self::Result {
+        if !self
+            .anomaly_contribution
+            .node_contribution_rotation_protocol
+            .received(&msg)
+        {
+            return;
+        }
+
+        self.anomaly_contribution
+            .add_node_contributions(msg.clone());
+
+        if self
+            .anomaly_contribution
+            .node_contribution_rotation_protocol
+            .is_running()
+        {
+            self.cluster_nodes
+                .get_as(&self.cluster_nodes.get_next_idx().unwrap(), "Training")
+                .unwrap()
+                .do_send(msg);
+            self.anomaly_contribution
+                .node_contribution_rotation_protocol
+                .sent();
+        } else if self.init_done() {
+            ctx.address().do_send(ScoreInitDone);
+        }
     }
 }
@@ -1,6 +1,10 @@
 use crate::data_store::node::IndependentNode;
-use crate::training::anomaly_contribution::AnomalyContribution;
+use crate::parameters::Parameters;
+use crate::training::anomaly_contribution::{
+    center_contributions, contribution_path, AnomalyContribution, ContributionMetric,
+};
 use ndarray::{arr1, arr2};
+use std::path::PathBuf;
 
 #[test]
 fn test() {
@@ -21,7 +25,12 @@ fn test() {
     ];
 
     let mut ac = AnomalyContribution::default();
-    ac.record_contributions(nodes.clone(), cluster_centers, vec![1, 1, 1, 1]);
+    ac.record_contributions(
+        nodes.clone(),
+        cluster_centers,
+        vec![1, 1, 1, 1],
+        &ContributionMetric::Uniqueness,
+    );
 
     for (node, exp) in nodes.iter().zip(expected) {
         let contribution = ac.node_contribution.remove(node).unwrap();
@@ -30,16 +39,57 @@ fn test() {
 }
 
 #[test]
-fn test_dim_combination() {
-    let cc = arr2(&[
-        [0.5, 2.0, 2.0, 4.0, 4.0],
-        [0.5, 2.0, 2.0, 4.0, 4.0],
-        [0.5, 2.0, 2.0, 4.0, 4.0],
-    ]);
-
-    let expected = arr2(&[[0.5, 4.0, 8.0], [0.5, 4.0, 8.0], [0.5, 4.0, 8.0]]);
-
-    let ac = AnomalyContribution::default();
-    let combined = ac.combine_dimensions(cc);
-    assert_eq!(combined.unwrap(), expected)
+fn test_distance_contribution() {
+    let nodes = vec![
+        IndependentNode::new(0, 0, 0).into_ref(),
+        IndependentNode::new(0, 1, 0).into_ref(),
+        IndependentNode::new(0, 2, 0).into_ref(),
+    ];
+
+    let cluster_centers = arr2(&[[1., 0.], [1., 2.], [4., 1.]]);
+
+    let expected = vec![
+        arr1(&[0.75_f32, 0.75]),
+        arr1(&[0.75, 1.25]),
+        arr1(&[2.25, 0.25]),
+    ];
+
+    let mut ac = AnomalyContribution::default();
+    ac.record_contributions(
+        nodes.clone(),
+        cluster_centers,
+        vec![2, 1, 1],
+        &ContributionMetric::Distance,
+    );
+
+    for (node, exp) in nodes.iter().zip(expected) {
+        let contribution = ac.node_contribution.remove(node).unwrap();
+        assert_eq!(contribution, exp)
+    }
+}
+
+#[test]
+fn test_center_contributions() {
+    let contributions = arr2(&[[1., 2.], [0., 0.], [3., 2.], [1., 4.]]);
+    let expected = arr2(&[[0., 0.], [0., 0.], [2., 0.], [0., 2.]]);
+
+    assert_eq!(center_contributions(contributions), expected)
+}
+
+#[test]
+fn test_contribution_path() {
+    let mut params = Parameters {
+        score_output_path: Some("out/scores.csv".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(
+        contribution_path(&params),
+        Some(PathBuf::from("out/scores.contribution.csv"))
+    );
+
+    params.anomaly_contribution_output_path = Some("contribution.csv".to_string());
+    assert_eq!(
+        contribution_path(&params),
+        Some(PathBuf::from("contribution.csv"))
+    );
 }
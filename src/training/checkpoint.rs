@@ -0,0 +1,330 @@
+use crate::data_manager::DatasetStats;
+use crate::data_store::materialize::Materialize;
+use crate::data_store::materialized::MaterializedDataStore;
+use crate::data_store::node::IndependentNode;
+use crate::data_store::node_questions::NodeQuestions;
+use crate::error::S2GppError;
+use crate::messages::PoisonPill;
+use crate::parameters::Parameters;
+use crate::training::anomaly_contribution::NodeContributions;
+use crate::training::intersection_calculation::IntersectionCalculationDone;
+use crate::training::node_estimation::NodeEstimationDone;
+use crate::training::rotation::RotationDoneMessage;
+use crate::training::Training;
+use actix::{AsyncContext, Context};
+use anyhow::{Error, Result};
+use log::*;
+use ndarray::{Array1, Array2, Array3};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{create_dir_all, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+const CHECKPOINT_MAGIC: &[u8; 6] = b"S2GCKP";
+/// Must be increased whenever the serialized layout of `Checkpoint` changes.
+const CHECKPOINT_FORMAT_VERSION: u32 = 1;
+
+/// The phase after which a checkpoint is written.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CheckpointPhase {
+    Rotation,
+    IntersectionCalculation,
+    NodeEstimation,
+}
+
+impl CheckpointPhase {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CheckpointPhase::Rotation => "rotation",
+            CheckpointPhase::IntersectionCalculation => "intersection-calculation",
+            CheckpointPhase::NodeEstimation => "node-estimation",
+        }
+    }
+}
+
+/// Everything a cluster node needs to continue the training after `phase`. The state of later
+/// phases is still empty in checkpoints of earlier phases.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Checkpoint {
+    phase: CheckpointPhase,
+    cluster_node: usize,
+    n_cluster_nodes: usize,
+    pattern_length: usize,
+    latent: usize,
+    rate: usize,
+    n_components: usize,
+    dataset_stats: Option<DatasetStats>,
+    num_rotated: Option<usize>,
+    /// principal components, means and rotation matrix of the `Rotation`
+    components: Vec<Array2<f32>>,
+    means: Vec<Array1<f32>>,
+    rotation_matrix: Option<Array3<f32>>,
+    data_store: MaterializedDataStore,
+    mirrored: bool,
+    node_questions: NodeQuestions,
+    next_foreign_node: HashMap<(usize, usize), (usize, IndependentNode)>,
+    cluster_centers: HashMap<usize, Array2<f32>>,
+    cluster_sizes: HashMap<usize, Vec<usize>>,
+    node_contributions: NodeContributions,
+}
+
+impl Checkpoint {
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(CHECKPOINT_MAGIC)?;
+        bincode::serialize_into(&mut writer, &CHECKPOINT_FORMAT_VERSION)?;
+        bincode::serialize_into(&mut writer, self)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let mut magic = [0_u8; 6];
+        if reader.read_exact(&mut magic).is_err() || magic.ne(CHECKPOINT_MAGIC) {
+            return Err(Error::msg(format!(
+                "'{}' is not an S2G++ checkpoint file!",
+                path.display()
+            )));
+        }
+        let version: u32 = bincode::deserialize_from(&mut reader)?;
+        if version != CHECKPOINT_FORMAT_VERSION {
+            return Err(Error::msg(format!(
+                "The checkpoint has format version {}, but only version {} is supported!",
+                version, CHECKPOINT_FORMAT_VERSION
+            )));
+        }
+        Ok(bincode::deserialize_from(reader)?)
+    }
+
+    /// Rejects a checkpoint of another cluster node or of a run whose points and segments differ.
+    pub fn validate(&self, parameters: &Parameters, cluster_node: usize) -> Result<(), S2GppError> {
+        if self.cluster_node != cluster_node || self.n_cluster_nodes != parameters.n_cluster_nodes {
+            return Err(S2GppError::InvalidParameters(format!(
+                "The checkpoint was written by cluster node {} of {}, but this is cluster node {} of {}.",
+                self.cluster_node, self.n_cluster_nodes, cluster_node, parameters.n_cluster_nodes
+            )));
+        }
+        let written = (
+            self.pattern_length,
+            self.latent,
+            self.rate,
+            self.n_components,
+        );
+        let given = (
+            parameters.pattern_length,
+            parameters.latent,
+            parameters.rate,
+            parameters.n_components,
+        );
+        if written != given {
+            return Err(S2GppError::InvalidParameters(format!(
+                "The checkpoint was written with 'pattern-length', 'latent', 'rate' and 'components' {:?}, but {:?} are given.",
+                written, given
+            )));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) trait Checkpointer {
+    fn checkpoint_path(&self, phase: CheckpointPhase) -> Option<PathBuf>;
+    fn write_checkpoint(&self, phase: CheckpointPhase);
+    fn take_checkpoint(&self, phase: CheckpointPhase) -> Checkpoint;
+    fn restore_checkpoint(&mut self, checkpoint: Checkpoint);
+    fn resume(&mut self, path: &str, ctx: &mut Context<Training>) -> Result<(), S2GppError>;
+}
+
+impl Checkpointer for Training {
+    fn checkpoint_path(&self, phase: CheckpointPhase) -> Option<PathBuf> {
+        let dir = self.parameters.checkpoint_dir.as_ref()?;
+        Some(PathBuf::from(dir).join(format!(
+            "{}.{}.checkpoint",
+            phase.name(),
+            self.cluster_nodes.get_own_idx()
+        )))
+    }
+
+    /// Does nothing without a `checkpoint-dir` or if the training was resumed from this phase.
+    /// A checkpoint that cannot be written does not stop the training.
+    fn write_checkpoint(&self, phase: CheckpointPhase) {
+        if self.resumed_from == Some(phase) {
+            return;
+        }
+        let path = match self.checkpoint_path(phase) {
+            Some(path) => path,
+            None => return,
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), create_dir_all)
+            .map_err(Error::from)
+            .and_then(|_| self.take_checkpoint(phase).save(&path));
+        match result {
+            Ok(()) => info!(
+                "Wrote the {} checkpoint to '{}'",
+                phase.name(),
+                path.display()
+            ),
+            Err(e) => error!(
+                "Could not write the {} checkpoint to '{}': {}",
+                phase.name(),
+                path.display(),
+                e
+            ),
+        }
+    }
+
+    fn take_checkpoint(&self, phase: CheckpointPhase) -> Checkpoint {
+        Checkpoint {
+            phase,
+            cluster_node: self.cluster_nodes.get_own_idx(),
+            n_cluster_nodes: self.parameters.n_cluster_nodes,
+            pattern_length: self.parameters.pattern_length,
+            latent: self.parameters.latent,
+            rate: self.parameters.rate,
+            n_components: self.parameters.n_components,
+            dataset_stats: self.dataset_stats.clone(),
+            num_rotated: self.num_rotated,
+            components: self.rotation.components.clone(),
+            means: self.rotation.means.clone(),
+            rotation_matrix: self.rotation.rotation_matrix.clone(),
+            data_store: self.data_store.materialize(),
+            mirrored: self.segmentation.mirrored,
+            node_questions: self.segmentation.node_questions.clone(),
+            next_foreign_node: self.node_estimation.next_foreign_node.clone(),
+            cluster_centers: self.node_estimation.cluster_centers.clone(),
+            cluster_sizes: self.node_estimation.cluster_sizes.clone(),
+            node_contributions: self.anomaly_contribution.node_contributions(),
+        }
+    }
+
+    fn restore_checkpoint(&mut self, checkpoint: Checkpoint) {
+        self.dataset_stats = checkpoint.dataset_stats;
+        self.num_rotated = checkpoint.num_rotated;
+        self.rotation.components = checkpoint.components;
+        self.rotation.means = checkpoint.means;
+        self.rotation.rotation_matrix = checkpoint.rotation_matrix;
+        self.data_store = checkpoint.data_store.into_data_store();
+        self.segmentation.mirrored = checkpoint.mirrored;
+        self.segmentation.node_questions = checkpoint.node_questions;
+        self.node_estimation.next_foreign_node = checkpoint.next_foreign_node;
+        self.node_estimation.cluster_centers = checkpoint.cluster_centers;
+        self.node_estimation.cluster_sizes = checkpoint.cluster_sizes;
+        self.anomaly_contribution
+            .add_node_contributions(checkpoint.node_contributions);
+    }
+
+    /// Restores the checkpoint at `path` and continues with the phase after it, as if its phase
+    /// had just finished. The data is not read.
+    fn resume(&mut self, path: &str, ctx: &mut Context<Training>) -> Result<(), S2GppError> {
+        let checkpoint = Checkpoint::load(path).map_err(|e| S2GppError::reading(path, e))?;
+        checkpoint.validate(&self.parameters, self.cluster_nodes.get_own_idx())?;
+        let phase = checkpoint.phase;
+        info!("Resuming after the {} from '{}'", phase.name(), path);
+
+        self.restore_checkpoint(checkpoint);
+        self.resumed_from = Some(phase);
+        if let Some(data_manager) = self.data_manager.take() {
+            data_manager.do_send(PoisonPill);
+        }
+        match phase {
+            CheckpointPhase::Rotation => ctx.address().do_send(RotationDoneMessage),
+            CheckpointPhase::IntersectionCalculation => {
+                ctx.address().do_send(IntersectionCalculationDone)
+            }
+            CheckpointPhase::NodeEstimation => ctx.address().do_send(NodeEstimationDone),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data_manager::DatasetStats;
+    use crate::data_store::intersection::Intersection;
+    use crate::data_store::node::IndependentNode;
+    use crate::data_store::point::Point;
+    use crate::data_store::transition::{Transition, TransitionRef};
+    use crate::error::S2GppError;
+    use crate::training::checkpoint::{Checkpoint, CheckpointPhase, Checkpointer};
+    use crate::{Parameters, SyncInterface, Training};
+    use ndarray::arr1;
+    use std::fs::remove_file;
+
+    #[test]
+    fn restores_the_state_of_a_saved_checkpoint() {
+        let path = "data/_test_checkpoint.checkpoint";
+        let mut training = Training::init(Parameters::default());
+        training.dataset_stats = Some(DatasetStats::new(
+            arr1(&[1.0]),
+            arr1(&[0.0]),
+            arr1(&[2.0]),
+            10,
+        ));
+        training.num_rotated = Some(2);
+        training
+            .data_store
+            .add_point(Point::new(0, arr1(&[1., 0.]), 0));
+        training
+            .data_store
+            .add_point(Point::new(1, arr1(&[0., 1.]), 25));
+        let points = training.data_store.get_points();
+        let transition = TransitionRef::new(Transition::new(points[0].clone(), points[1].clone()));
+        training
+            .data_store
+            .add_intersection(Intersection::new(transition, arr1(&[0.5]), 3));
+        training
+            .data_store
+            .add_independent_node(IndependentNode::new(3, 0, 0));
+        training.segmentation.mirrored = true;
+
+        let checkpoint = training.take_checkpoint(CheckpointPhase::IntersectionCalculation);
+        checkpoint.save(path).unwrap();
+        let loaded = Checkpoint::load(path);
+        remove_file(path).expect("Could not delete test file!");
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.phase, CheckpointPhase::IntersectionCalculation);
+
+        let mut resumed = Training::init(Parameters::default());
+        resumed.restore_checkpoint(loaded);
+        assert_eq!(resumed.num_rotated, Some(2));
+        assert_eq!(resumed.dataset_stats.unwrap().n, Some(10));
+        assert!(resumed.segmentation.mirrored);
+        assert_eq!(resumed.data_store.count_points(), 2);
+        assert_eq!(
+            resumed
+                .data_store
+                .get_intersections_from_segment(3)
+                .map(|intersections| intersections.len()),
+            Some(1)
+        );
+        assert_eq!(
+            resumed
+                .data_store
+                .get_nodes_by_point_id(0)
+                .map(|nodes| nodes.len()),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn rejects_checkpoint_of_other_parameters() {
+        let training = Training::init(Parameters::default());
+        let checkpoint = training.take_checkpoint(CheckpointPhase::Rotation);
+        assert!(checkpoint.validate(&Parameters::default(), 0).is_ok());
+
+        let parameters = Parameters {
+            rate: 50,
+            ..Default::default()
+        };
+        assert!(matches!(
+            checkpoint.validate(&parameters, 0),
+            Err(S2GppError::InvalidParameters(_))
+        ));
+        assert!(checkpoint.validate(&Parameters::default(), 1).is_err());
+    }
+}
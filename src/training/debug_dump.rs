@@ -0,0 +1,228 @@
+use crate::data_store::node::NodeRef;
+use crate::data_store::transition::TransitionMixin;
+use crate::training::Training;
+use anyhow::Result;
+use csv::WriterBuilder;
+use log::*;
+use ndarray::{ArrayView1, ArrayView3, Axis};
+use std::fs::create_dir_all;
+use std::path::{Path, PathBuf};
+
+fn with_coordinates(mut row: Vec<String>, coordinates: ArrayView1<f32>) -> Vec<String> {
+    row.extend(coordinates.iter().map(|x| x.to_string()));
+    row
+}
+
+fn node_columns(node: &NodeRef) -> Vec<String> {
+    vec![
+        node.get_segment_id().to_string(),
+        node.get_cluster().to_string(),
+        node.get_from_id().to_string(),
+    ]
+}
+
+/// Writes the intermediate results of the pipeline to the `debug-dump-dir` as headerless CSV
+/// files named `<name>.local.0` or, in a cluster, `<name>.dist.<cluster node>`, like
+/// `scripts/compare-intermediate-outputs.sh` expects them.
+pub(crate) trait DebugDumper {
+    fn dump<I: IntoIterator<Item = Vec<String>>>(&self, name: &str, rows: I);
+    fn dump_phase_space(&self, phase_space: ArrayView3<f32>);
+    fn dump_rotation(&self);
+    fn dump_transitions(&self);
+    fn dump_intersections(&self);
+    fn dump_nodes(&self);
+    fn dump_edges(&self, name: &str);
+}
+
+impl Training {
+    fn debug_dump_path(&self, name: &str) -> Option<PathBuf> {
+        let dir = self.parameters.debug_dump_dir.as_ref()?;
+        let mode = if self.parameters.n_cluster_nodes > 1 {
+            "dist"
+        } else {
+            "local"
+        };
+        Some(PathBuf::from(dir).join(format!(
+            "{}.{}.{}",
+            name,
+            mode,
+            self.cluster_nodes.get_own_idx()
+        )))
+    }
+}
+
+fn write_rows<I: IntoIterator<Item = Vec<String>>>(path: &Path, rows: I) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        create_dir_all(dir)?;
+    }
+    let mut writer = WriterBuilder::new().has_headers(false).from_path(path)?;
+    for row in rows {
+        writer.write_record(&row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+impl DebugDumper for Training {
+    fn dump<I: IntoIterator<Item = Vec<String>>>(&self, name: &str, rows: I) {
+        if let Some(path) = self.debug_dump_path(name) {
+            match write_rows(&path, rows) {
+                Ok(()) => debug!("Dumped {} to '{}'", name, path.display()),
+                Err(e) => error!("Could not dump {} to '{}': {}", name, path.display(), e),
+            }
+        }
+    }
+
+    /// One row per point of the phase space with its window flattened row by row.
+    fn dump_phase_space(&self, phase_space: ArrayView3<f32>) {
+        if self.parameters.debug_dump_dir.is_none() {
+            return;
+        }
+        self.dump(
+            "phase-space",
+            phase_space
+                .axis_iter(Axis(0))
+                .map(|point| point.iter().map(|x| x.to_string()).collect::<Vec<String>>()),
+        );
+    }
+
+    /// The 3-D trajectory reduced by the PCA, with its three components of every input dimension
+    /// per row, and the rotated points with their id and segment.
+    fn dump_rotation(&self) {
+        if self.parameters.debug_dump_dir.is_none() {
+            return;
+        }
+        if let Some(reduced) = self.rotation.reduced.as_ref() {
+            self.dump(
+                "reduced",
+                reduced
+                    .axis_iter(Axis(0))
+                    .map(|point| point.iter().map(|x| x.to_string()).collect::<Vec<String>>()),
+            );
+        }
+        self.dump(
+            "points",
+            self.data_store.get_points().iter().map(|point| {
+                with_coordinates(
+                    vec![point.get_id().to_string(), point.get_segment().to_string()],
+                    point.clone_coordinates().view(),
+                )
+            }),
+        );
+    }
+
+    /// One row per transition: the id and segment of its start and end point.
+    fn dump_transitions(&self) {
+        if self.parameters.debug_dump_dir.is_none() {
+            return;
+        }
+        self.dump(
+            "transitions",
+            self.data_store.get_transitions().iter().map(|transition| {
+                let (from, to) = transition.get_points();
+                vec![
+                    from.get_id().to_string(),
+                    from.get_segment().to_string(),
+                    to.get_id().to_string(),
+                    to.get_segment().to_string(),
+                ]
+            }),
+        );
+    }
+
+    /// One row per intersection: its segment, the id of the transition's start point and its
+    /// coordinates.
+    fn dump_intersections(&self) {
+        if self.parameters.debug_dump_dir.is_none() {
+            return;
+        }
+        self.dump(
+            "intersections",
+            self.data_store
+                .get_intersections()
+                .iter()
+                .map(|intersection| {
+                    with_coordinates(
+                        vec![
+                            intersection.get_segment_id().to_string(),
+                            intersection.get_from_id().to_string(),
+                        ],
+                        intersection.get_coordinates(),
+                    )
+                }),
+        );
+    }
+
+    /// One row per node (segment, cluster and the id of its intersection's point) and one row
+    /// per cluster center (segment, cluster and coordinates).
+    fn dump_nodes(&self) {
+        if self.parameters.debug_dump_dir.is_none() {
+            return;
+        }
+        self.dump(
+            "nodes",
+            self.data_store.get_nodes().iter().map(node_columns),
+        );
+
+        let mut segments: Vec<&usize> = self.node_estimation.cluster_centers.keys().collect();
+        segments.sort();
+        self.dump(
+            "cluster-centers",
+            segments.into_iter().flat_map(|segment| {
+                self.node_estimation.cluster_centers[segment]
+                    .axis_iter(Axis(0))
+                    .enumerate()
+                    .map(move |(cluster, center)| {
+                        with_coordinates(vec![segment.to_string(), cluster.to_string()], center)
+                    })
+            }),
+        );
+    }
+
+    /// One row per edge with the columns of its start and end node.
+    fn dump_edges(&self, name: &str) {
+        if self.parameters.debug_dump_dir.is_none() {
+            return;
+        }
+        self.dump(
+            name,
+            self.data_store.get_edges().iter().map(|edge| {
+                let mut row = node_columns(&edge.get_from_node());
+                row.extend(node_columns(&edge.get_to_node()));
+                row
+            }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::training::debug_dump::DebugDumper;
+    use crate::{Parameters, SyncInterface, Training};
+    use std::fs::{read_to_string, remove_dir_all};
+    use std::path::PathBuf;
+
+    #[test]
+    fn dumps_rows_as_expected_by_the_comparison_script() {
+        let dir = "data/_test_debug_dump";
+        let training = Training::init(Parameters {
+            debug_dump_dir: Some(dir.to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            training.debug_dump_path("edges"),
+            Some(PathBuf::from(dir).join("edges.local.0"))
+        );
+
+        training.dump(
+            "edges",
+            vec![
+                vec!["0".to_string(), "1.5".to_string()],
+                vec!["1".to_string(), "2".to_string()],
+            ],
+        );
+        let dumped = read_to_string(PathBuf::from(dir).join("edges.local.0"));
+        remove_dir_all(dir).expect("Could not delete test directory!");
+        assert_eq!(dumped.unwrap(), "0,1.5\n1,2\n");
+    }
+}
@@ -10,6 +10,22 @@ pub use crate::training::edge_estimation::messages::EdgeEstimationDone;
 use crate::training::Training;
 use actix::prelude::*;
 
+/// Connects the nodes created by one point's transition with each other and with `previous_node`.
+/// Returns the ordered edges and the node the next transition continues from.
+pub(crate) fn order_edges(
+    intersection_nodes: &[NodeRef],
+    mut previous_node: Option<NodeRef>,
+) -> (Vec<Edge>, Option<NodeRef>) {
+    let mut edges = EdgesOrderer::new(previous_node.clone());
+    for current_node in intersection_nodes {
+        edges.add_node(&previous_node, current_node);
+        previous_node = Some(current_node.clone());
+    }
+
+    let last_node = edges.last_node.clone().or(previous_node);
+    (edges.into_vec(), last_node)
+}
+
 pub trait EdgeEstimator {
     fn estimate_edges(&mut self, ctx: &mut Context<Training>);
     fn connect_nodes(&mut self);
@@ -34,15 +50,10 @@ impl EdgeEstimator for Training {
 
         for point_id in 0..len_dataset {
             if let Some(intersection_nodes) = self.data_store.get_nodes_by_point_id(point_id) {
-                let mut edges = EdgesOrderer::new(previous_node.clone());
-                for current_node in intersection_nodes {
-                    edges.add_node(&previous_node, current_node);
-                    previous_node = Some(current_node.clone());
-                }
-
-                previous_node = edges.last_node.clone().or(previous_node);
+                let (edges, last_node) = order_edges(intersection_nodes, previous_node);
+                previous_node = last_node;
 
-                self.data_store.add_edges(edges.into_vec());
+                self.data_store.add_edges(edges);
 
                 if let Some(current_node) = &previous_node {
                     if let Some((_point_id, next_node)) = self
@@ -1,36 +1,58 @@
-use crate::messages::PoisonPill;
+use crate::data_store::transition::TransitionMixin;
+use crate::error::S2GppError;
+use crate::messages::{PoisonPill, TrainingFailed};
 use crate::training::intersection_calculation::messages::{
     IntersectionResultMessage, IntersectionTaskMessage,
 };
-use crate::utils::line_plane_intersection;
-use actix::{Actor, ActorContext, Handler, SyncContext};
-use ndarray::{arr1, Axis};
+use crate::utils::{line_plane_intersection, IntersectionError, Phase, Progress};
+use actix::{Actor, ActorContext, Handler, Recipient, SyncContext};
+use ndarray::{arr1, Array1, Array2, Axis};
 use ndarray_linalg::Norm;
 use num_integer::div_floor;
 
 use super::messages::{IntersectionResult, IntersectionTask};
 
-pub struct IntersectionCalculationHelper {}
+pub struct IntersectionCalculationHelper {
+    pub progress: Progress,
+    /// number of input dimensions
+    pub n_dims: usize,
+    pub failure: Recipient<TrainingFailed>,
+}
+
+/// Intersects the line with the hyperplane and reduces the intersection to its distance from the
+/// origin per input dimension, over all rotated components of this dimension.
+pub(crate) fn intersection_distance(
+    line_points: Array2<f32>,
+    plane_points: Array2<f32>,
+    n_dims: usize,
+) -> Result<Array1<f32>, IntersectionError> {
+    let intersection = line_plane_intersection(line_points, plane_points)?;
+    let shape = intersection.shape();
+    let reshaped = intersection
+        .to_shape([div_floor(shape[0], n_dims), n_dims])
+        .unwrap();
+    Ok(arr1(
+        &reshaped
+            .axis_iter(Axis(1))
+            .map(|coords| coords.norm())
+            .collect::<Vec<f32>>(),
+    ))
+}
 
 impl IntersectionCalculationHelper {
-    fn work(&self, task: IntersectionTask) -> IntersectionResult {
-        match line_plane_intersection(task.line_points.clone(), task.plane_points.clone()) {
-            Ok(intersection) => {
-                let shape = intersection.shape();
-                let reshaped = intersection.to_shape([2, div_floor(shape[0], 2)]).unwrap();
-                let distance = arr1(
-                    &reshaped
-                        .axis_iter(Axis(1))
-                        .map(|coords| coords.norm())
-                        .collect::<Vec<f32>>(),
-                );
-                IntersectionResult {
-                    transition: task.transition,
-                    segment_id: task.segment_id,
-                    intersection: distance,
-                }
-            }
-            Err(e) => panic!("intersection error {:?}", e),
+    fn work(&self, task: IntersectionTask) -> Result<IntersectionResult, S2GppError> {
+        self.progress.advance(Phase::Intersections, 1);
+        match intersection_distance(task.line_points, task.plane_points, self.n_dims) {
+            Ok(distance) => Ok(IntersectionResult {
+                transition: task.transition,
+                segment_id: task.segment_id,
+                intersection: distance,
+            }),
+            Err(_) => Err(S2GppError::InvalidData(format!(
+                "The transition from point {} does not intersect the plane of segment {}!",
+                task.transition.get_from_id(),
+                task.segment_id
+            ))),
         }
     }
 }
@@ -43,9 +65,15 @@ impl Handler<IntersectionTaskMessage> for IntersectionCalculationHelper {
     type Result = ();
 
     fn handle(&mut self, msg: IntersectionTaskMessage, _ctx: &mut Self::Context) -> Self::Result {
-        let results = msg.tasks.into_iter().map(|task| self.work(task)).collect();
-        msg.source
-            .do_send(IntersectionResultMessage { results });
+        let results: Result<Vec<IntersectionResult>, S2GppError> =
+            msg.tasks.into_iter().map(|task| self.work(task)).collect();
+        match results {
+            Ok(results) => msg.source.do_send(IntersectionResultMessage { results }),
+            Err(error) => self.failure.do_send(TrainingFailed {
+                error,
+                cluster_node: None,
+            }),
+        }
     }
 }
 
@@ -3,22 +3,24 @@ mod messages;
 #[cfg(test)]
 mod tests;
 
-use actix::{Addr, AsyncContext, Handler, Recipient, SyncArbiter};
+use actix::{Addr, AsyncContext, Context, Handler, Recipient, SyncArbiter};
 
 use ndarray::{arr1, concatenate, stack, Array1, Array2, Axis};
 use std::collections::HashMap;
 
 use std::f32::consts::PI;
 
+pub(crate) use crate::training::intersection_calculation::helper::intersection_distance;
 use crate::training::intersection_calculation::helper::IntersectionCalculationHelper;
 pub(crate) use crate::training::intersection_calculation::messages::{
     IntersectionCalculationDone, IntersectionResultMessage, IntersectionRotationMessage,
     IntersectionTaskMessage,
 };
 
+use crate::error::S2GppError;
 use crate::messages::PoisonPill;
 use crate::training::Training;
-use crate::utils::{HelperProtocol, PolarCoords};
+use crate::utils::{HelperProtocol, Phase, PolarCoords};
 
 use crate::data_store::intersection::Intersection;
 use crate::data_store::transition::{TransitionMixin, TransitionRef};
@@ -42,8 +44,99 @@ pub(crate) struct IntersectionCalculation {
     pub direct_protocol: DirectProtocol<IntersectionRotationMessage>,
 }
 
+/// Largest absolute coordinate of all transition end points.
+pub(crate) fn max_abs_coordinate(transitions: &[TransitionRef]) -> f32 {
+    transitions
+        .iter()
+        .map(|x| {
+            x.get_from_point()
+                .get_max_coordinate()
+                .max(x.get_from_point().get_min_coordinate().abs())
+                .max(
+                    x.get_to_point()
+                        .get_max_coordinate()
+                        .max(x.get_to_point().get_min_coordinate().abs())
+                        .abs(),
+                )
+        })
+        .fold(0_f32, |a, b| a.max(b))
+}
+
+/// End points of the planes that separate the `rate` segments around the origin.
+pub(crate) fn planes_end_points(max_value: f32, dims: usize, rate: usize) -> Vec<Array1<f32>> {
+    let radius = arr1(&[max_value, max_value]).norm();
+    (0..rate)
+        .map(|segment_id| {
+            let polar = arr1(&[radius, (2.0 * PI * segment_id as f32) / rate as f32]);
+            let other_dims = arr1(
+                (2..dims)
+                    .map(|_| max_value)
+                    .collect::<Vec<f32>>()
+                    .as_slice(),
+            );
+            concatenate(Axis(0), &[polar.to_cartesian().view(), other_dims.view()]).unwrap()
+        })
+        .collect()
+}
+
+pub(crate) fn line_points(transition: &TransitionRef) -> Array2<f32> {
+    stack(
+        Axis(0),
+        &[
+            transition.get_from_point().clone_coordinates().view(),
+            transition.get_to_point().clone_coordinates().view(),
+        ],
+    )
+    .unwrap()
+}
+
+/// Segments whose planes are crossed by the transition, taking the shorter way around the origin.
+pub(crate) fn intersected_segments(transition: &TransitionRef, rate: usize) -> Vec<SegmentID> {
+    let mut segment_diff = transition.segment_diff();
+    let half_rate = num_integer::Integer::div_floor(&rate, &2);
+
+    if segment_diff > half_rate {
+        if transition.get_to_segment() > half_rate {
+            segment_diff = (transition.get_from_segment() as isize
+                - (-(rate as isize) + transition.get_to_segment() as isize))
+                .unsigned_abs();
+        } else if transition.get_from_segment() > half_rate {
+            segment_diff = (transition.get_to_segment() as isize
+                - (-(rate as isize) + transition.get_from_segment() as isize))
+                .unsigned_abs();
+        }
+    }
+    segment_diff = segment_diff.min(half_rate);
+
+    (1..(segment_diff) + 1)
+        .map(|segment_lag| (transition.get_from_segment() + segment_lag).mod_floor(&rate))
+        .collect()
+}
+
+/// Points spanning the plane of `segment_id`, starting at the origin.
+pub(crate) fn plane_points(
+    planes_end_points: &[Array1<f32>],
+    segment_id: SegmentID,
+) -> Array2<f32> {
+    let dims = planes_end_points[segment_id].len();
+    let origin = arr1(vec![0_f32; dims].as_slice());
+    let mut arrays = vec![origin.view()];
+    let corner_points: Vec<Array1<f32>> = (2..dims)
+        .map(|d| {
+            let mut corner_point = planes_end_points[segment_id].clone();
+            corner_point[d] = 0.;
+            corner_point
+        })
+        .collect();
+
+    arrays.extend(corner_points.iter().map(|x| x.view()));
+    arrays.push(planes_end_points[segment_id].view());
+
+    stack(Axis(0), arrays.as_slice()).unwrap()
+}
+
 pub(crate) trait IntersectionCalculator {
-    fn calculate_intersections(&mut self, rec: Recipient<IntersectionResultMessage>);
+    fn calculate_intersections(&mut self, ctx: &mut Context<Training>);
     fn parallel_intersection_tasks(&mut self, rec: Recipient<IntersectionResultMessage>);
     fn rotate_foreign_assignments(&mut self, rec: Recipient<IntersectionCalculationDone>);
     fn assign_received_intersection(
@@ -56,120 +149,57 @@ pub(crate) trait IntersectionCalculator {
 }
 
 impl IntersectionCalculator for Training {
-    fn calculate_intersections(&mut self, rec: Recipient<IntersectionResultMessage>) {
+    fn calculate_intersections(&mut self, ctx: &mut Context<Training>) {
         if self.data_store.count_transitions() == 0 {
             self.start_distribution_protocol();
             return;
         }
 
-        let max_value = self
-            .data_store
-            .get_transitions()
-            .iter()
-            .map(|x| {
-                x.get_from_point()
-                    .get_max_coordinate()
-                    .max(x.get_from_point().get_min_coordinate().abs())
-                    .max(
-                        x.get_to_point()
-                            .get_max_coordinate()
-                            .max(x.get_to_point().get_min_coordinate().abs())
-                            .abs(),
-                    )
-            })
-            .fold(0_f32, |a, b| a.max(b));
-        let radius = arr1(&[max_value, max_value]).norm();
-
-        let dims = self
-            .data_store
-            .get_transitions()
-            .first()
-            .expect("Could not generate Segments")
-            .get_from_point()
-            .get_dims();
-
-        let origin = arr1(vec![0_f32; dims].as_slice());
-        let planes_end_points: Vec<Array1<f32>> = (0..self.parameters.rate)
-            .into_iter()
-            .map(|segment_id| {
-                let polar = arr1(&[
-                    radius,
-                    (2.0 * PI * segment_id as f32) / self.parameters.rate as f32,
-                ]);
-                let other_dims = arr1(
-                    (2..dims)
-                        .into_iter()
-                        .map(|_| max_value)
-                        .collect::<Vec<f32>>()
-                        .as_slice(),
+        let transitions = self.data_store.get_transitions();
+        let dims = match transitions.first() {
+            Some(transition) => transition.get_from_point().get_dims(),
+            None => {
+                self.fail(
+                    S2GppError::InvalidData(
+                        "Could not generate segments from the data!".to_string(),
+                    ),
+                    ctx,
                 );
-                concatenate(Axis(0), &[polar.to_cartesian().view(), other_dims.view()]).unwrap()
-            })
-            .collect();
-
-        for transition in self.data_store.get_transitions() {
-            let line_points = stack(
-                Axis(0),
-                &[
-                    transition.get_from_point().clone_coordinates().view(),
-                    transition.get_to_point().clone_coordinates().view(),
-                ],
-            )
-            .unwrap();
-
-            let mut segment_ids = vec![];
-
-            let mut segment_diff = transition.segment_diff();
-            let half_rate = num_integer::Integer::div_floor(&self.parameters.rate, &2);
-
-            if segment_diff > half_rate {
-                if transition.get_to_segment() > half_rate {
-                    segment_diff = (transition.get_from_segment() as isize
-                        - (-(self.parameters.rate as isize) + transition.get_to_segment() as isize))
-                        .abs() as usize;
-                } else if transition.get_from_segment() > half_rate {
-                    segment_diff = (transition.get_to_segment() as isize
-                        - (-(self.parameters.rate as isize)
-                            + transition.get_from_segment() as isize))
-                        .abs() as usize;
-                }
+                return;
             }
-            segment_diff = segment_diff.min(half_rate);
-
-            for segment_lag in 1..(segment_diff) + 1 {
-                let segment_id =
-                    (transition.get_from_segment() + segment_lag).mod_floor(&self.parameters.rate);
-                segment_ids.push(segment_id);
-
-                let mut arrays = vec![origin.view()];
-                let corner_points: Vec<Array1<f32>> = (2..dims)
-                    .into_iter()
-                    .map(|d| {
-                        let mut corner_point = planes_end_points[segment_id].clone();
-                        corner_point[d] = 0.;
-                        corner_point
-                    })
-                    .collect();
-
-                arrays.extend(corner_points.iter().map(|x| x.view()));
-                arrays.push(planes_end_points[segment_id].view());
-
-                let plane_points = stack(Axis(0), arrays.as_slice()).unwrap();
+        };
+        let planes_end_points =
+            planes_end_points(max_abs_coordinate(&transitions), dims, self.parameters.rate);
+
+        for transition in transitions {
+            let line_points = line_points(&transition);
+            for segment_id in intersected_segments(&transition, self.parameters.rate) {
                 self.intersection_calculation.pairs.push((
                     transition.clone(),
                     segment_id,
                     line_points.clone(),
-                    plane_points,
+                    plane_points(&planes_end_points, segment_id),
                 ));
             }
         }
         self.intersection_calculation.helper_protocol.n_total = 1; //self.parameters.n_threads;
-
+        self.parameters.progress.start(
+            Phase::Intersections,
+            self.intersection_calculation.pairs.len(),
+        );
+
+        let progress = self.parameters.progress.clone();
+        let n_dims = self.rotation.components.len();
+        let failure = ctx.address().recipient();
         self.intersection_calculation.helpers = Some(SyncArbiter::start(1, move || {
-            IntersectionCalculationHelper {}
+            IntersectionCalculationHelper {
+                progress: progress.clone(),
+                n_dims,
+                failure: failure.clone(),
+            }
         }));
 
-        self.parallel_intersection_tasks(rec);
+        self.parallel_intersection_tasks(ctx.address().recipient());
     }
 
     fn start_distribution_protocol(&mut self) {
@@ -1,6 +1,8 @@
+use crate::error::S2GppError;
+use crate::interface::SyncResult;
 use crate::utils::ClusterNodes;
 use actix::prelude::*;
-use ndarray::{Array1, Array2};
+use ndarray::Array2;
 
 #[derive(Message)]
 #[rtype(Result = "()")]
@@ -13,5 +15,5 @@ pub struct StartTrainingMessage {
 #[derive(Message)]
 #[rtype(Result = "()")]
 pub struct DetectionResponse {
-    pub anomaly_score: Array1<f32>,
+    pub result: Result<SyncResult, S2GppError>,
 }
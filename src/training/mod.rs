@@ -7,27 +7,42 @@ use std::ops::Sub;
 
 use crate::data_manager::{DataLoadedAndProcessed, DataManager, DatasetStats, LoadDataMessage};
 use crate::data_store::DataStore;
-use crate::messages::PoisonPill;
+use crate::error::S2GppError;
+use crate::messages::{PoisonPill, TrainingFailed};
 use crate::parameters::Parameters;
-use crate::training::anomaly_contribution::AnomalyContribution;
+pub use crate::training::anomaly_contribution::ContributionMetric;
+use crate::training::anomaly_contribution::{AnomalyContribution, NodeContributions};
+use crate::training::checkpoint::{CheckpointPhase, Checkpointer};
+use crate::training::debug_dump::DebugDumper;
 use crate::training::edge_estimation::{EdgeEstimationDone, EdgeEstimator};
 use crate::training::intersection_calculation::{
     IntersectionCalculation, IntersectionCalculationDone, IntersectionCalculator,
     IntersectionRotationMessage, SegmentID,
 };
 pub use crate::training::messages::{DetectionResponse, StartTrainingMessage};
-pub use crate::training::node_estimation::Clustering;
+use crate::training::model::ModelExtractor;
+pub use crate::training::model::{GraphFormat, OnlineScorer, S2GppModel};
 use crate::training::node_estimation::{
     AskForForeignNodes, ForeignNodesAnswer, NodeEstimation, NodeEstimationDone, NodeEstimator,
 };
+pub use crate::training::node_estimation::{Bandwidth, Clustering};
+use crate::training::report::RunReport;
 use crate::training::rotation::{
     PCAComponents, PCADecompositionMessage, PCAMeansMessage, Rotation, RotationDoneMessage,
     RotationMatrixMessage, Rotator,
 };
+pub(crate) use crate::training::scoring::alignment::align_score;
+pub use crate::training::scoring::alignment::ScoreAlignment;
 use crate::training::scoring::messages::{
     EdgeWeights, NodeDegrees, OverlapRotation, ScoringDone, SubScores,
 };
+pub use crate::training::scoring::normalization::ScoreNormalization;
+pub(crate) use crate::training::scoring::threshold::write_anomaly_ranges;
+pub use crate::training::scoring::threshold::{
+    anomaly_ranges, AnomalyRange, RangesFormat, Thresholding,
+};
 use crate::training::scoring::weights::ScoringWeights;
+pub(crate) use crate::training::scoring::write_score;
 use crate::training::scoring::{Scorer, Scoring};
 use crate::training::segmentation::messages::{
     SegmentMessage, SendFirstPointMessage, TransitionCountMessage,
@@ -36,8 +51,10 @@ use crate::training::segmentation::{Segmentation, SegmentedMessage, Segmenter};
 use crate::training::transposition::{
     Transposer, Transposition, TranspositionDone, TranspositionRotationMessage,
 };
-use crate::utils::{ClusterNodes, ConsoleLogger};
+use crate::utils::{ClusterNodes, Phase};
 mod anomaly_contribution;
+mod checkpoint;
+mod debug_dump;
 use crate::data_manager::data_reader::messages::LocalReadDataMessage;
 use crate::interface::{actor_fit, SyncInterface, SyncResult};
 use num_integer::Integer;
@@ -45,7 +62,9 @@ use num_integer::Integer;
 mod edge_estimation;
 mod intersection_calculation;
 mod messages;
+mod model;
 mod node_estimation;
+mod report;
 mod rotation;
 mod scoring;
 mod segmentation;
@@ -56,6 +75,7 @@ mod transposition;
     ForeignNodesAnswer,
     AskForForeignNodes,
     NodeDegrees,
+    NodeContributions,
     SubScores,
     EdgeWeights,
     OverlapRotation,
@@ -67,7 +87,8 @@ mod transposition;
     PCADecompositionMessage,
     PCAComponents,
     RotationMatrixMessage,
-    TransitionCountMessage
+    TransitionCountMessage,
+    TrainingFailed
 )]
 pub struct Training {
     own_addr: Option<Addr<Self>>,
@@ -83,7 +104,9 @@ pub struct Training {
     scoring: Scoring,
     data_store: DataStore,
     num_rotated: Option<usize>,
-    anomaly_contribution: Option<Addr<AnomalyContribution>>,
+    anomaly_contribution: AnomalyContribution,
+    report: RunReport,
+    resumed_from: Option<CheckpointPhase>,
     sink: Option<Recipient<DetectionResponse>>,
 }
 
@@ -97,6 +120,31 @@ impl Training {
             .sub(&(from_node_id as isize))
             .mod_floor(&(self.parameters.n_cluster_nodes as isize)) as usize
     }
+
+    /// Reports the error to the sink and to the other cluster nodes and stops the training.
+    fn fail(&mut self, error: S2GppError, ctx: &mut Context<Self>) {
+        error!("{}", error);
+        let own_idx = self.cluster_nodes.get_own_idx();
+        for node in self.cluster_nodes.to_any_as(ctx.address(), "Training") {
+            node.do_send(TrainingFailed {
+                error: error.clone(),
+                cluster_node: Some(own_idx),
+            });
+        }
+        self.stop_with(error, ctx);
+    }
+
+    /// Reports the error to the sink and stops the training without notifying other nodes.
+    fn stop_with(&mut self, error: S2GppError, ctx: &mut Context<Self>) {
+        if let Some(data_manager) = self.data_manager.take() {
+            data_manager.do_send(PoisonPill);
+        }
+        match self.sink.as_ref() {
+            Some(sink) => sink.do_send(DetectionResponse { result: Err(error) }),
+            None => System::current().stop_with_code(1),
+        }
+        ctx.stop();
+    }
 }
 
 impl SyncInterface<f32> for Training {
@@ -115,7 +163,9 @@ impl SyncInterface<f32> for Training {
             scoring: Scoring::default(),
             data_store: DataStore::default(),
             num_rotated: None,
-            anomaly_contribution: None,
+            anomaly_contribution: AnomalyContribution::default(),
+            report: RunReport::default(),
+            resumed_from: None,
             sink: None,
         }
     }
@@ -138,22 +188,28 @@ impl Actor for Training {
                 self.cluster_nodes.clone(),
                 self.parameters.clone(),
                 ctx.address().recipient(),
+                ctx.address().recipient(),
             )
             .start(),
         );
-
-        if self.parameters.explainability {
-            self.anomaly_contribution = Some(AnomalyContribution::start_default());
-        }
     }
 }
 
 impl Handler<StartTrainingMessage> for Training {
     type Result = ();
 
-    fn handle(&mut self, msg: StartTrainingMessage, _ctx: &mut Self::Context) -> Self::Result {
+    fn handle(&mut self, msg: StartTrainingMessage, ctx: &mut Self::Context) -> Self::Result {
         self.cluster_nodes = msg.nodes;
         self.sink = msg.source;
+        if self.parameters.report_path.is_some() {
+            self.report.start();
+        }
+        if let Some(resume_from) = self.parameters.resume_from.clone() {
+            if let Err(error) = self.resume(&resume_from, ctx) {
+                self.fail(error, ctx);
+            }
+            return;
+        }
         if let Some(data) = msg.data {
             self.data_manager
                 .as_ref()
@@ -174,7 +230,10 @@ impl Handler<DataLoadedAndProcessed> for Training {
     type Result = ();
 
     fn handle(&mut self, msg: DataLoadedAndProcessed, _ctx: &mut Self::Context) -> Self::Result {
-        ConsoleLogger::new(6, 12, "Rotating Data".to_string()).print();
+        self.parameters.progress.step(6, 12, "Rotating Data");
+        self.report
+            .finish_phase("data-loading", &[("points", msg.phase_space.shape()[0])]);
+        self.dump_phase_space(msg.phase_space.view());
         self.dataset_stats = Some(msg.dataset_stats);
         self.rotate(msg.phase_space, msg.data_ref);
     }
@@ -184,9 +243,14 @@ impl Handler<RotationDoneMessage> for Training {
     type Result = ();
 
     fn handle(&mut self, _msg: RotationDoneMessage, ctx: &mut Self::Context) -> Self::Result {
-        ConsoleLogger::new(7, 12, "Segmenting Data".to_string()).print();
-        self.data_manager.as_ref().unwrap().do_send(PoisonPill);
-        self.data_manager = None;
+        self.parameters.progress.step(7, 12, "Segmenting Data");
+        self.report
+            .finish_phase("rotation", &[("points", self.data_store.count_points())]);
+        self.dump_rotation();
+        self.write_checkpoint(CheckpointPhase::Rotation);
+        if let Some(data_manager) = self.data_manager.take() {
+            data_manager.do_send(PoisonPill);
+        }
 
         self.segment(ctx);
     }
@@ -196,9 +260,19 @@ impl Handler<SegmentedMessage> for Training {
     type Result = ();
 
     fn handle(&mut self, _msg: SegmentedMessage, ctx: &mut Self::Context) -> Self::Result {
-        ConsoleLogger::new(8, 12, "Calculating Intersections".to_string()).print();
+        self.parameters
+            .progress
+            .step(8, 12, "Calculating Intersections");
+        self.report.finish_phase(
+            "segmentation",
+            &[
+                ("points", self.data_store.count_points()),
+                ("transitions", self.data_store.count_transitions()),
+            ],
+        );
+        self.dump_transitions();
 
-        self.calculate_intersections(ctx.address().recipient());
+        self.calculate_intersections(ctx);
     }
 }
 
@@ -210,12 +284,22 @@ impl Handler<IntersectionCalculationDone> for Training {
         _msg: IntersectionCalculationDone,
         ctx: &mut Self::Context,
     ) -> Self::Result {
-        ConsoleLogger::new(9, 12, "Estimating Nodes".to_string()).print();
+        self.parameters.progress.step(9, 12, "Estimating Nodes");
+        self.report.finish_phase(
+            "intersection-calculation",
+            &[("intersections", self.data_store.count_intersections())],
+        );
+        self.dump_intersections();
+        self.write_checkpoint(CheckpointPhase::IntersectionCalculation);
 
         self.node_estimation.current_segment_id = num_integer::Integer::div_floor(
             &self.parameters.rate,
             &self.cluster_nodes.len_incl_own(),
         ) * self.cluster_nodes.get_own_idx();
+        self.parameters.progress.start(
+            Phase::NodeEstimation,
+            self.parameters.rate - self.node_estimation.current_segment_id,
+        );
         self.estimate_nodes(ctx.address().recipient());
     }
 }
@@ -224,7 +308,13 @@ impl Handler<NodeEstimationDone> for Training {
     type Result = ();
 
     fn handle(&mut self, _msg: NodeEstimationDone, ctx: &mut Self::Context) -> Self::Result {
-        ConsoleLogger::new(10, 12, "Estimating Edges".to_string()).print();
+        self.parameters.progress.step(10, 12, "Estimating Edges");
+        self.report.finish_phase(
+            "node-estimation",
+            &[("nodes", self.data_store.count_nodes())],
+        );
+        self.dump_nodes();
+        self.write_checkpoint(CheckpointPhase::NodeEstimation);
 
         self.estimate_edges(ctx);
     }
@@ -234,7 +324,14 @@ impl Handler<EdgeEstimationDone> for Training {
     type Result = ();
 
     fn handle(&mut self, _msg: EdgeEstimationDone, ctx: &mut Self::Context) -> Self::Result {
-        ConsoleLogger::new(11, 12, "Transpose Distributed Data".to_string()).print();
+        self.parameters
+            .progress
+            .step(11, 12, "Transpose Distributed Data");
+        self.report.finish_phase(
+            "edge-estimation",
+            &[("edges", self.data_store.count_edges())],
+        );
+        self.dump_edges("edges");
 
         self.scoring.node_degrees = self.calculate_node_degrees();
 
@@ -250,7 +347,10 @@ impl Handler<TranspositionDone> for Training {
     type Result = ();
 
     fn handle(&mut self, _msg: TranspositionDone, ctx: &mut Self::Context) -> Self::Result {
-        ConsoleLogger::new(12, 12, "Scoring".to_string()).print();
+        self.parameters.progress.step(12, 12, "Scoring");
+        self.report
+            .finish_phase("transposition", &[("edges", self.data_store.count_edges())]);
+        self.dump_edges("edges-transposed");
 
         self.init_scoring(ctx);
     }
@@ -264,16 +364,80 @@ impl Handler<ScoringDone> for Training {
             "score {}",
             self.scoring.score.as_ref().unwrap_or(&arr1(&[]))
         );
-        if let Some(sink) = self.sink.as_ref() {
-            let anomaly_score = self
-                .scoring
-                .score
-                .take()
-                .expect("The score should have been created by now");
-            sink.do_send(DetectionResponse { anomaly_score });
+        self.report.finish_phase(
+            "scoring",
+            &[(
+                "scores",
+                self.scoring.score.as_ref().map_or(0, |score| score.len()),
+            )],
+        );
+        if let Some(report_path) = self.parameters.report_path.clone() {
+            if let Err(e) = self.report.write(
+                &report_path,
+                self.cluster_nodes.get_own_idx(),
+                self.parameters.n_cluster_nodes,
+            ) {
+                self.fail(S2GppError::writing(&report_path, e), ctx);
+                return;
+            }
+        }
+        let model = self.extract_model();
+        if let Some(model_out) = self.parameters.model_out.clone() {
+            let saved = match model.as_ref() {
+                Some(model) => model
+                    .save(&model_out)
+                    .map_err(|e| S2GppError::writing(&model_out, e)),
+                // rejected by `Parameters::validate_settings` for distributed runs
+                None => Err(S2GppError::Aborted(
+                    "The model could not be extracted from the training!".to_string(),
+                )),
+            };
+            if let Err(error) = saved {
+                self.fail(error, ctx);
+                return;
+            }
+        }
+        if let Some(graph_output_path) = self.parameters.graph_output_path.clone() {
+            match model.as_ref() {
+                Some(model) => {
+                    if let Err(e) =
+                        model.export_graph(&graph_output_path, &self.parameters.graph_format)
+                    {
+                        self.fail(S2GppError::writing(&graph_output_path, e), ctx);
+                        return;
+                    }
+                }
+                None => warn!("The graph can only be exported after a local training!"),
+            }
+        }
+
+        // only the main node has the complete score
+        if let (Some(sink), Some(anomaly_score)) = (self.sink.as_ref(), self.scoring.score.take()) {
+            sink.do_send(DetectionResponse {
+                result: Ok(SyncResult {
+                    anomaly_score,
+                    anomaly_contribution: self.anomaly_contribution.contribution.take(),
+                    contribution_columns: self.anomaly_contribution.columns.clone(),
+                    model,
+                }),
+            });
         }
 
         ctx.stop();
         System::current().stop();
     }
 }
+
+impl Handler<TrainingFailed> for Training {
+    type Result = ();
+
+    fn handle(&mut self, msg: TrainingFailed, ctx: &mut Self::Context) -> Self::Result {
+        match msg.cluster_node {
+            Some(cluster_node) => {
+                error!("Cluster node {} failed: {}", cluster_node, msg.error);
+                self.stop_with(msg.error, ctx);
+            }
+            None => self.fail(msg.error, ctx),
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use crate::data_store::node::IndependentNode;
+use crate::training::model::S2GppModel;
+use crate::utils::json;
+use anyhow::Result;
+use itertools::Itertools;
+use std::io::Write;
+use std::str::FromStr;
+
+#[derive(Debug, Clone)]
+pub enum GraphFormat {
+    GraphML,
+    Dot,
+    Json,
+}
+
+impl FromStr for GraphFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "graphml" => Ok(GraphFormat::GraphML),
+            "dot" => Ok(GraphFormat::Dot),
+            "json" => Ok(GraphFormat::Json),
+            _ => Err(format!(
+                "{} is not a valid graph format! Allowed values are: 'graphml', 'dot' and 'json'",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct GraphNode {
+    pub id: String,
+    pub segment: usize,
+    pub cluster: usize,
+    pub center: Vec<f32>,
+    pub degree: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct GraphEdge {
+    pub source: String,
+    pub target: String,
+    pub weight: usize,
+}
+
+/// The nodes and edges of a model, sorted by segment and cluster.
+#[derive(Debug)]
+pub(crate) struct ExportedGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+fn node_id(segment: usize, cluster: usize) -> String {
+    format!("{}_{}", segment, cluster)
+}
+
+impl ExportedGraph {
+    pub fn from_model(model: &S2GppModel) -> Self {
+        let nodes = model
+            .cluster_centers
+            .iter()
+            .sorted_by_key(|(segment, _)| **segment)
+            .flat_map(|(segment, centers)| {
+                centers
+                    .rows()
+                    .into_iter()
+                    .enumerate()
+                    .map(move |(cluster, center)| (*segment, cluster, center.to_vec()))
+            })
+            .map(|(segment, cluster, center)| GraphNode {
+                id: node_id(segment, cluster),
+                segment,
+                cluster,
+                center,
+                degree: *model
+                    .node_degrees
+                    .get(&IndependentNode::new(segment, cluster, 0))
+                    .unwrap_or(&0),
+            })
+            .collect();
+
+        let edges = model
+            .edge_weight
+            .iter()
+            .map(|(edge, weight)| {
+                let (from_node, to_node) = (edge.get_from_node(), edge.get_to_node());
+                (
+                    (from_node.get_segment_id(), from_node.get_cluster()),
+                    (to_node.get_segment_id(), to_node.get_cluster()),
+                    *weight,
+                )
+            })
+            .sorted()
+            .map(|(from_node, to_node, weight)| GraphEdge {
+                source: node_id(from_node.0, from_node.1),
+                target: node_id(to_node.0, to_node.1),
+                weight,
+            })
+            .collect();
+
+        Self { nodes, edges }
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W, format: &GraphFormat) -> Result<()> {
+        match format {
+            GraphFormat::GraphML => self.write_graphml(writer),
+            GraphFormat::Dot => self.write_dot(writer),
+            GraphFormat::Json => self.write_json(writer),
+        }
+    }
+
+    fn write_graphml<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            writer,
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+        )?;
+        for (id, domain, type_) in [
+            ("segment", "node", "int"),
+            ("cluster", "node", "int"),
+            ("center", "node", "string"),
+            ("degree", "node", "int"),
+            ("weight", "edge", "int"),
+        ] {
+            writeln!(
+                writer,
+                r#"  <key id="{0}" for="{1}" attr.name="{0}" attr.type="{2}"/>"#,
+                id, domain, type_
+            )?;
+        }
+        writeln!(writer, r#"  <graph id="s2gpp" edgedefault="directed">"#)?;
+        for node in self.nodes.iter() {
+            writeln!(writer, r#"    <node id="{}">"#, node.id)?;
+            writeln!(
+                writer,
+                r#"      <data key="segment">{}</data>"#,
+                node.segment
+            )?;
+            writeln!(
+                writer,
+                r#"      <data key="cluster">{}</data>"#,
+                node.cluster
+            )?;
+            writeln!(
+                writer,
+                r#"      <data key="center">{}</data>"#,
+                node.center.iter().join(" ")
+            )?;
+            writeln!(writer, r#"      <data key="degree">{}</data>"#, node.degree)?;
+            writeln!(writer, "    </node>")?;
+        }
+        for edge in self.edges.iter() {
+            writeln!(
+                writer,
+                r#"    <edge source="{}" target="{}">"#,
+                edge.source, edge.target
+            )?;
+            writeln!(writer, r#"      <data key="weight">{}</data>"#, edge.weight)?;
+            writeln!(writer, "    </edge>")?;
+        }
+        writeln!(writer, "  </graph>")?;
+        writeln!(writer, "</graphml>")?;
+        Ok(())
+    }
+
+    fn write_dot<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writeln!(writer, "digraph s2gpp {{")?;
+        for node in self.nodes.iter() {
+            writeln!(
+                writer,
+                r#"  "{}" [segment={}, cluster={}, center="{}", degree={}];"#,
+                node.id,
+                node.segment,
+                node.cluster,
+                node.center.iter().join(" "),
+                node.degree
+            )?;
+        }
+        for edge in self.edges.iter() {
+            writeln!(
+                writer,
+                r#"  "{}" -> "{}" [weight={}];"#,
+                edge.source, edge.target, edge.weight
+            )?;
+        }
+        writeln!(writer, "}}")?;
+        Ok(())
+    }
+
+    fn write_json<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let nodes = self.nodes.iter().map(|node| {
+            json::object([
+                ("id", json::string(&node.id)),
+                ("segment", node.segment.to_string()),
+                ("cluster", node.cluster.to_string()),
+                (
+                    "center",
+                    json::array(node.center.iter().map(|x| json::number(*x))),
+                ),
+                ("degree", node.degree.to_string()),
+            ])
+        });
+        let edges = self.edges.iter().map(|edge| {
+            json::object([
+                ("source", json::string(&edge.source)),
+                ("target", json::string(&edge.target)),
+                ("weight", edge.weight.to_string()),
+            ])
+        });
+        writeln!(writer, "{{")?;
+        writeln!(writer, r#"  "nodes": {},"#, json::array_lines(nodes, "  "))?;
+        writeln!(writer, r#"  "edges": {}"#, json::array_lines(edges, "  "))?;
+        writeln!(writer, "}}")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,280 @@
+mod export;
+mod online;
+mod scorer;
+#[cfg(test)]
+mod tests;
+
+use crate::data_manager::DatasetStats;
+use crate::data_store::edge::MaterializedEdge;
+use crate::data_store::node::IndependentNode;
+use crate::parameters::Parameters;
+use crate::training::model::export::ExportedGraph;
+pub use crate::training::model::export::GraphFormat;
+pub use crate::training::model::online::OnlineScorer;
+use crate::training::model::scorer::ModelScorer;
+use crate::training::scoring::normalization::{normalize, ScoreNormalization};
+use crate::training::scoring::weights::add_edges_to_weights;
+use crate::training::Training;
+use anyhow::{Error, Result};
+use ndarray::{Array1, Array2, Array3, ArrayView1, Axis};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::ops::Deref;
+use std::path::Path;
+
+const MODEL_MAGIC: &[u8; 6] = b"S2GPP\0";
+/// Must be increased whenever the serialized layout of `S2GppModel` changes.
+pub const MODEL_FORMAT_VERSION: u32 = 4;
+
+/// The graph learned by Series2Graph++ together with everything that is needed to embed and score
+/// unseen time series with it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct S2GppModel {
+    pub(crate) pattern_length: usize,
+    pub(crate) latent: usize,
+    pub(crate) rate: usize,
+    pub(crate) query_length: usize,
+    /// whether the self-correction mirrored the points during training
+    pub(crate) mirrored: bool,
+    /// principal components per input dimension
+    pub(crate) components: Vec<Array2<f32>>,
+    /// means per input dimension
+    pub(crate) means: Vec<Array1<f32>>,
+    pub(crate) rotation_matrix: Array3<f32>,
+    /// cluster centers per segment; the row index is the node's cluster label
+    pub(crate) cluster_centers: HashMap<usize, Array2<f32>>,
+    /// number of intersections that have been merged into each cluster center
+    pub(crate) cluster_sizes: HashMap<usize, Vec<usize>>,
+    pub(crate) edge_weight: HashMap<MaterializedEdge, usize>,
+    pub(crate) node_degrees: HashMap<IndependentNode, usize>,
+    /// percentiles of the raw training scores, see `ScoreNormalization::Model`
+    pub(crate) score_quantiles: Vec<f32>,
+    /// statistics of the training data, e.g. its length `n` the training score was aligned to
+    pub(crate) dataset_stats: DatasetStats,
+}
+
+impl S2GppModel {
+    /// Scores `data` against the learned graph and scales the scores to [0, 1]. The data must
+    /// have as many columns as the training data.
+    pub fn score(&self, data: Array2<f32>) -> Result<Array1<f32>> {
+        self.score_normalized(data, &ScoreNormalization::MinMax)
+    }
+
+    /// Like `score`, but with the given `normalization`. `ScoreNormalization::Model` ranks the
+    /// scores against the scores of the training data.
+    pub fn score_normalized(
+        &self,
+        data: Array2<f32>,
+        normalization: &ScoreNormalization,
+    ) -> Result<Array1<f32>> {
+        let mut scores = ModelScorer::new(self).score(data)?;
+        normalize(&mut scores, normalization, &self.score_quantiles)?;
+        Ok(scores)
+    }
+
+    /// Adds `data` as further normal behaviour to the graph, e.g. to adapt to drift. The
+    /// intersections of `data` are merged into the closest nodes, whose cluster centers move
+    /// towards them, and the new edges are added to the edge weights and node degrees. With a
+    /// `max_distance`, intersections farther away from their closest node open a new node, so
+    /// that unseen behaviour is not absorbed by the existing nodes.
+    pub fn update(&mut self, data: Array2<f32>, max_distance: Option<f32>) -> Result<()> {
+        let mut scorer = ModelScorer::new(self);
+        scorer.build_graph(data.clone())?;
+        let mut data_store = scorer.into_data_store();
+
+        let mut opened_nodes = false;
+        for segment_id in 0..self.rate {
+            if let Some(intersections) = data_store.get_intersections_from_segment(segment_id) {
+                for intersection in intersections {
+                    opened_nodes |= self.merge_intersection(
+                        segment_id,
+                        intersection.get_coordinates(),
+                        max_distance,
+                    )?;
+                }
+            }
+        }
+        // the edges were built from the nodes before the update and miss the new nodes
+        if opened_nodes {
+            let mut scorer = ModelScorer::new(self);
+            scorer.build_graph(data)?;
+            data_store = scorer.into_data_store();
+        }
+        add_edges_to_weights(
+            &data_store.get_edges(),
+            &mut self.edge_weight,
+            &mut self.node_degrees,
+        );
+        Ok(())
+    }
+
+    /// Writes the model to `path` in a versioned binary format.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MODEL_MAGIC)?;
+        bincode::serialize_into(&mut writer, &MODEL_FORMAT_VERSION)?;
+        bincode::serialize_into(&mut writer, self)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes the nodes (with their cluster centers and degrees) and the weighted edges of the
+    /// graph to `path`.
+    pub fn export_graph<P: AsRef<Path>>(&self, path: P, format: &GraphFormat) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        ExportedGraph::from_model(self).write(&mut writer, format)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Reads a model that has been written by `save`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let mut magic = [0_u8; 6];
+        if reader.read_exact(&mut magic).is_err() || magic.ne(MODEL_MAGIC) {
+            return Err(Error::msg(format!(
+                "'{}' is not a Series2Graph++ model file!",
+                path.display()
+            )));
+        }
+
+        let version: u32 = bincode::deserialize_from(&mut reader)?;
+        if version != MODEL_FORMAT_VERSION {
+            return Err(Error::msg(format!(
+                "The model file has format version {}, but only version {} is supported!",
+                version, MODEL_FORMAT_VERSION
+            )));
+        }
+
+        Ok(bincode::deserialize_from(reader)?)
+    }
+
+    pub fn n_dims(&self) -> usize {
+        self.components.len()
+    }
+
+    /// The number of principal components each input dimension is reduced to.
+    pub(crate) fn n_components(&self) -> usize {
+        self.rotation_matrix.shape()[0]
+    }
+
+    /// The number of coordinates of a rotated point, i.e. all components but the rotation axis
+    /// of every input dimension.
+    pub(crate) fn point_dims(&self) -> usize {
+        self.n_dims() * (self.n_components() - 1)
+    }
+
+    pub(crate) fn parameters(&self) -> Parameters {
+        Parameters {
+            pattern_length: self.pattern_length,
+            latent: self.latent,
+            rate: self.rate,
+            query_length: self.query_length,
+            ..Default::default()
+        }
+    }
+
+    /// The label of the cluster center in `segment_id` closest to `coordinates`.
+    pub(crate) fn closest_cluster(&self, segment_id: usize, coordinates: ArrayView1<f32>) -> usize {
+        self.closest_center(segment_id, coordinates)
+            .map_or(0, |(label, _)| label)
+    }
+
+    /// The label of the cluster center in `segment_id` closest to `coordinates` and the Euclidean
+    /// distance to it.
+    fn closest_center(
+        &self,
+        segment_id: usize,
+        coordinates: ArrayView1<f32>,
+    ) -> Option<(usize, f32)> {
+        self.cluster_centers
+            .get(&segment_id)?
+            .axis_iter(Axis(0))
+            .map(|center| (&center - &coordinates).mapv(|x| x.powi(2)).sum())
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+            .map(|(label, distance)| (label, distance.sqrt()))
+    }
+
+    /// Moves the closest cluster center of the segment towards `coordinates` by a running mean.
+    /// The intersection becomes a new center if the segment has none yet or if the closest one
+    /// is farther away than `max_distance`. Centers without a known size count as one
+    /// intersection. Returns whether a new center, i.e. a new node, was opened.
+    pub(crate) fn merge_intersection(
+        &mut self,
+        segment_id: usize,
+        coordinates: ArrayView1<f32>,
+        max_distance: Option<f32>,
+    ) -> Result<bool> {
+        let closest = self.closest_center(segment_id, coordinates);
+        let is_close = |distance: f32| match max_distance {
+            Some(max_distance) => distance <= max_distance,
+            None => true,
+        };
+        match (self.cluster_centers.get_mut(&segment_id), closest) {
+            (Some(centers), Some((label, distance))) if is_close(distance) => {
+                let sizes = self.cluster_sizes.entry(segment_id).or_default();
+                sizes.resize(centers.nrows(), 1);
+                sizes[label] += 1;
+                let mut center = centers.row_mut(label);
+                let shift = (&coordinates - &center) / sizes[label] as f32;
+                center += &shift;
+                Ok(false)
+            }
+            (Some(centers), _) => {
+                centers.push_row(coordinates)?;
+                let sizes = self.cluster_sizes.entry(segment_id).or_default();
+                sizes.resize(centers.nrows() - 1, 1);
+                sizes.push(1);
+                Ok(true)
+            }
+            (None, _) => {
+                self.cluster_centers
+                    .insert(segment_id, coordinates.to_owned().insert_axis(Axis(0)));
+                self.cluster_sizes.insert(segment_id, vec![1]);
+                Ok(true)
+            }
+        }
+    }
+}
+
+pub(crate) trait ModelExtractor {
+    fn extract_model(&self) -> Option<S2GppModel>;
+}
+
+impl ModelExtractor for Training {
+    /// Only available for local training, because the graph is spread over the cluster nodes
+    /// otherwise.
+    fn extract_model(&self) -> Option<S2GppModel> {
+        if self.cluster_nodes.len() > 0 {
+            return None;
+        }
+
+        Some(S2GppModel {
+            pattern_length: self.parameters.pattern_length,
+            latent: self.parameters.latent,
+            rate: self.parameters.rate,
+            query_length: self.parameters.query_length,
+            mirrored: self.segmentation.mirrored,
+            components: self.rotation.components.clone(),
+            means: self.rotation.means.clone(),
+            rotation_matrix: self.rotation.rotation_matrix.clone()?,
+            cluster_centers: self.node_estimation.cluster_centers.clone(),
+            cluster_sizes: self.node_estimation.cluster_sizes.clone(),
+            edge_weight: self.scoring.edge_weight.clone(),
+            node_degrees: self
+                .scoring
+                .node_degrees
+                .iter()
+                .map(|(node, degree)| (node.deref().clone(), *degree))
+                .collect(),
+            score_quantiles: self.scoring.score_quantiles.clone(),
+            dataset_stats: self.dataset_stats.clone().unwrap_or_default(),
+        })
+    }
+}
@@ -0,0 +1,195 @@
+use crate::data_store::edge::EdgeRef;
+use crate::data_store::node::{IndependentNode, NodeRef};
+use crate::data_store::point::{Point, PointRef};
+use crate::data_store::transition::{Transition, TransitionMixin, TransitionRef};
+use crate::training::edge_estimation::order_edges;
+use crate::training::intersection_calculation::{
+    intersected_segments, intersection_distance, line_points, plane_points, planes_end_points,
+};
+use crate::training::model::S2GppModel;
+use crate::training::rotation::{project, rotate_reduced};
+use crate::training::scoring::helper::{score_p_degree, UnknownEdges};
+use anyhow::{Error, Result};
+use ndarray::{stack, Array1, Array2, ArrayView1, ArrayView2, Axis};
+use std::collections::{HashMap, VecDeque};
+
+/// Scores a stream of observations against a learned graph, one observation at a time.
+///
+/// Only the last `pattern_length - 1` observations and the edges of the open queries are kept.
+/// The scores are the raw query scores, i.e. they are not min-max normalized like the ones of
+/// `S2GppModel::score`, because the score range of a stream is unknown.
+pub struct OnlineScorer {
+    model: S2GppModel,
+    node_degrees: HashMap<NodeRef, usize>,
+    planes_end_points: Vec<Array1<f32>>,
+    observations: VecDeque<Array1<f32>>,
+    n_points: usize,
+    last_point: Option<PointRef>,
+    previous_node: Option<NodeRef>,
+    edges: VecDeque<EdgeRef>,
+    next_query: usize,
+}
+
+impl OnlineScorer {
+    pub fn new(model: S2GppModel) -> Self {
+        let node_degrees = model
+            .node_degrees
+            .iter()
+            .map(|(node, degree)| (node.clone().into_ref(), *degree))
+            .collect();
+        // the planes go through the origin, so their extent does not change the intersections
+        let planes_end_points = planes_end_points(1.0, model.point_dims(), model.rate);
+
+        Self {
+            model,
+            node_degrees,
+            planes_end_points,
+            observations: VecDeque::new(),
+            n_points: 0,
+            last_point: None,
+            previous_node: None,
+            edges: VecDeque::new(),
+            next_query: 0,
+        }
+    }
+
+    pub fn model(&self) -> &S2GppModel {
+        &self.model
+    }
+
+    /// Adds the next observation (one value per dimension) and returns the scores of all queries
+    /// that are complete now. The n-th score overall belongs to the query starting at the n-th
+    /// observation.
+    pub fn push(&mut self, observation: ArrayView1<f32>) -> Result<Vec<f32>> {
+        if observation.len() != self.model.n_dims() {
+            return Err(Error::msg(format!(
+                "The model was trained on {} dimensions, but the observation has {} values!",
+                self.model.n_dims(),
+                observation.len()
+            )));
+        }
+
+        self.observations.push_back(observation.to_owned());
+        if self.observations.len() < self.model.pattern_length - 1 {
+            return Ok(vec![]);
+        }
+
+        let coordinates = self.embed();
+        self.observations.pop_front();
+        self.add_point(coordinates)?;
+
+        self.score_complete_queries()
+    }
+
+    /// Embeds the buffered observations into one rotated phase space point.
+    fn embed(&self) -> Array1<f32> {
+        let latent = self.model.latent;
+        let width = self.model.pattern_length - latent;
+        let reduced: Vec<Array2<f32>> = (0..self.model.n_dims())
+            .map(|dim| {
+                let phase = Array2::from_shape_fn((1, width), |(_, j)| {
+                    (j..j + latent).map(|t| self.observations[t][dim]).sum()
+                });
+                project(
+                    phase.view(),
+                    &self.model.means[dim],
+                    &self.model.components[dim],
+                )
+            })
+            .collect();
+        let reduced = stack(
+            Axis(2),
+            reduced
+                .iter()
+                .map(|x| x.view())
+                .collect::<Vec<ArrayView2<f32>>>()
+                .as_slice(),
+        )
+        .expect("All dimensions are reduced to the same shape!");
+
+        rotate_reduced(&self.model.rotation_matrix, &reduced).remove(0)
+    }
+
+    fn add_point(&mut self, coordinates: Array1<f32>) -> Result<()> {
+        let rate = self.model.rate;
+        let mut point = Point::new_calculate_segment(self.n_points, coordinates, rate);
+        if self.model.mirrored {
+            point.mirror(rate);
+        }
+        let point = point.into_ref();
+        self.n_points += 1;
+
+        if let Some(last_point) = self.last_point.replace(point.clone()) {
+            let transition = Transition::new(last_point, point);
+            if transition.crosses_segments() && transition.has_valid_direction(rate as isize) {
+                self.connect(TransitionRef::new(transition))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Assigns the intersections of the transition to the closest nodes and connects them.
+    fn connect(&mut self, transition: TransitionRef) -> Result<()> {
+        let line_points = line_points(&transition);
+        let mut nodes = vec![];
+        for segment_id in intersected_segments(&transition, self.model.rate) {
+            let intersection = intersection_distance(
+                line_points.clone(),
+                plane_points(&self.planes_end_points, segment_id),
+                self.model.n_dims(),
+            )
+            .map_err(|e| Error::msg(format!("intersection error {:?}", e)))?;
+            nodes.push(
+                IndependentNode::new(
+                    segment_id,
+                    self.model.closest_cluster(segment_id, intersection.view()),
+                    transition.get_from_id(),
+                )
+                .into_ref(),
+            );
+        }
+        nodes.sort_by_key(|node| node.get_segment_id());
+
+        let (edges, last_node) = order_edges(&nodes, self.previous_node.take());
+        self.previous_node = last_node;
+        self.edges
+            .extend(edges.into_iter().map(|edge| edge.into_ref()));
+        Ok(())
+    }
+
+    /// Like the batch scoring, a query also includes the first edge after its end. Therefore, a
+    /// query is scored as soon as such an edge exists.
+    fn score_complete_queries(&mut self) -> Result<Vec<f32>> {
+        let mut scores = vec![];
+        loop {
+            let query_start = self.next_query;
+            let query_end = query_start + self.model.query_length - 1;
+            while let Some(edge) = self.edges.front() {
+                if edge.get_to_id() > query_start {
+                    break;
+                }
+                self.edges.pop_front();
+            }
+
+            let n_edges = self
+                .edges
+                .iter()
+                .take_while(|edge| edge.get_to_id() <= query_end)
+                .count();
+            if n_edges == self.edges.len() {
+                break;
+            }
+
+            let (score, _) = score_p_degree(
+                &self.edges.range(..n_edges + 1).cloned().collect::<Vec<_>>(),
+                &self.model.edge_weight,
+                &self.node_degrees,
+                UnknownEdges::Ignore,
+            )
+            .ok_or_else(|| Error::msg("Could not score the query!"))?;
+            scores.push(-score);
+            self.next_query += 1;
+        }
+        Ok(scores)
+    }
+}
@@ -0,0 +1,214 @@
+use crate::data_manager::PhaseSpacer;
+use crate::data_store::intersection::Intersection;
+use crate::data_store::node::{IndependentNode, NodeRef};
+use crate::data_store::point::PointRef;
+use crate::data_store::transition::{Transition, TransitionMixin};
+use crate::data_store::DataStore;
+use crate::training::edge_estimation::order_edges;
+use crate::training::intersection_calculation::{
+    intersected_segments, intersection_distance, line_points, max_abs_coordinate, plane_points,
+    planes_end_points,
+};
+use crate::training::model::S2GppModel;
+use crate::training::rotation::{project, rotate_reduced};
+use crate::training::scoring::helper::{score_p_degree, score_queries, UnknownEdges};
+use crate::training::scoring::weights::edges_in_time;
+use anyhow::{Error, Result};
+use ndarray::{stack, Array1, Array2, ArrayView2, Axis};
+use std::collections::HashMap;
+
+/// Walks unseen data through the same steps as the training, but assigns the intersections to the
+/// nodes of the learned graph instead of clustering them.
+pub(crate) struct ModelScorer<'a> {
+    model: &'a S2GppModel,
+    data_store: DataStore,
+}
+
+impl<'a> ModelScorer<'a> {
+    pub fn new(model: &'a S2GppModel) -> Self {
+        Self {
+            model,
+            data_store: DataStore::default(),
+        }
+    }
+
+    pub fn score(mut self, data: Array2<f32>) -> Result<Array1<f32>> {
+        let n_points = self.build_graph(data)?;
+        self.score_edges(n_points)
+    }
+
+    /// Builds the graph of `data` from the nodes of the model. Returns the number of points.
+    pub fn build_graph(&mut self, data: Array2<f32>) -> Result<usize> {
+        if data.ncols() != self.model.n_dims() {
+            return Err(Error::msg(format!(
+                "The model was trained on {} dimensions, but the data has {} columns!",
+                self.model.n_dims(),
+                data.ncols()
+            )));
+        }
+        if data.nrows() < self.model.pattern_length + self.model.query_length {
+            return Err(Error::msg(format!(
+                "The data needs at least 'pattern_length' + 'query_length' = {} rows!",
+                self.model.pattern_length + self.model.query_length
+            )));
+        }
+
+        let points = self.rotate(data)?;
+        let n_points = points.len();
+        self.segment(points);
+        self.calculate_intersections()?;
+        self.assign_nodes();
+        self.connect_nodes(n_points);
+        Ok(n_points)
+    }
+
+    pub fn into_data_store(self) -> DataStore {
+        self.data_store
+    }
+
+    fn rotate(&self, data: Array2<f32>) -> Result<Vec<Array1<f32>>> {
+        let phase_space = PhaseSpacer::new(data.into_shared(), self.model.parameters()).build();
+        let reduced: Vec<Array2<f32>> = (0..self.model.n_dims())
+            .map(|dim| {
+                project(
+                    phase_space.index_axis(Axis(2), dim),
+                    &self.model.means[dim],
+                    &self.model.components[dim],
+                )
+            })
+            .collect();
+        let reduced = stack(
+            Axis(2),
+            reduced
+                .iter()
+                .map(|x| x.view())
+                .collect::<Vec<ArrayView2<f32>>>()
+                .as_slice(),
+        )?;
+
+        Ok(rotate_reduced(&self.model.rotation_matrix, &reduced))
+    }
+
+    fn segment(&mut self, points: Vec<Array1<f32>>) {
+        let rate = self.model.rate;
+        self.data_store.add_points_with_offset(points, 0, rate);
+        if self.model.mirrored {
+            self.data_store.mirror_points(rate);
+        }
+
+        let mut last_point: Option<PointRef> = None;
+        for point in self.data_store.get_points() {
+            if let Some(last_point) = last_point {
+                let transition = Transition::new(last_point, point.clone());
+                if transition.crosses_segments() && transition.has_valid_direction(rate as isize) {
+                    self.data_store.add_transition(transition);
+                }
+            }
+            last_point = Some(point);
+        }
+    }
+
+    fn calculate_intersections(&mut self) -> Result<()> {
+        let transitions = self.data_store.get_transitions();
+        let dims = match transitions.first() {
+            Some(transition) => transition.get_from_point().get_dims(),
+            None => return Err(Error::msg("Could not generate transitions from the data!")),
+        };
+        let planes_end_points =
+            planes_end_points(max_abs_coordinate(&transitions), dims, self.model.rate);
+
+        for transition in transitions {
+            let line_points = line_points(&transition);
+            for segment_id in intersected_segments(&transition, self.model.rate) {
+                let intersection = intersection_distance(
+                    line_points.clone(),
+                    plane_points(&planes_end_points, segment_id),
+                    self.model.n_dims(),
+                )
+                .map_err(|e| Error::msg(format!("intersection error {:?}", e)))?;
+                self.data_store.add_intersection(Intersection::new(
+                    transition.clone(),
+                    intersection,
+                    segment_id,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn assign_nodes(&mut self) {
+        for segment_id in 0..self.model.rate {
+            let nodes: Vec<IndependentNode> =
+                match self.data_store.get_intersections_from_segment(segment_id) {
+                    Some(intersections) => intersections
+                        .iter()
+                        .map(|intersection| {
+                            IndependentNode::new(
+                                segment_id,
+                                self.model
+                                    .closest_cluster(segment_id, intersection.get_coordinates()),
+                                intersection.get_from_id(),
+                            )
+                        })
+                        .collect(),
+                    None => vec![],
+                };
+            for node in nodes {
+                self.data_store.add_independent_node(node);
+            }
+        }
+    }
+
+    fn connect_nodes(&mut self, n_points: usize) {
+        let mut previous_node: Option<NodeRef> = None;
+        for point_id in 0..n_points {
+            let ordered = self
+                .data_store
+                .get_nodes_by_point_id(point_id)
+                .map(|intersection_nodes| order_edges(intersection_nodes, previous_node.clone()));
+            if let Some((edges, last_node)) = ordered {
+                self.data_store.add_edges(edges);
+                previous_node = last_node;
+            }
+        }
+        self.data_store.sort_edges();
+    }
+
+    fn score_edges(&self, n_points: usize) -> Result<Array1<f32>> {
+        let edges = self.data_store.get_edges();
+        // -1 because the last point has no outgoing edge
+        let edges_in_time = edges_in_time(&edges, 0, n_points - 1);
+        let query_length = self.model.query_length;
+        if edges_in_time.len() < query_length {
+            return Err(Error::msg(
+                "There are less edges than the given 'query_length'!",
+            ));
+        }
+
+        let node_degrees: HashMap<NodeRef, usize> = self
+            .model
+            .node_degrees
+            .iter()
+            .map(|(node, degree)| (node.clone().into_ref(), *degree))
+            .collect();
+        let score_length = edges_in_time.len() - (query_length - 1);
+        let (scores, _first_empty) = score_queries(
+            &edges,
+            &edges_in_time,
+            query_length,
+            0..score_length,
+            None,
+            |p_edge| {
+                score_p_degree(
+                    p_edge,
+                    &self.model.edge_weight,
+                    &node_degrees,
+                    UnknownEdges::Ignore,
+                )
+            },
+        )
+        .ok_or_else(|| Error::msg("Could not score the queries!"))?;
+
+        Ok(Array1::from(scores))
+    }
+}
@@ -0,0 +1,291 @@
+use crate::data_manager::data_reader::read_data_;
+use crate::data_manager::DatasetStats;
+use crate::data_store::edge::Edge;
+use crate::data_store::materialize::Materialize;
+use crate::data_store::node::IndependentNode;
+use crate::training::model::export::ExportedGraph;
+use crate::training::model::{GraphFormat, OnlineScorer, S2GppModel};
+use crate::{fit, Parameters, ScoreNormalization};
+use ndarray::{arr1, arr2, Array1, Array2, Array3, Axis};
+use std::collections::HashMap;
+use std::fs::remove_file;
+use std::str::FromStr;
+
+fn small_model() -> S2GppModel {
+    let mut cluster_centers = HashMap::new();
+    cluster_centers.insert(3, arr2(&[[0.0, 0.0], [5.0, 5.0], [1.0, 1.0]]));
+    let mut cluster_sizes = HashMap::new();
+    cluster_sizes.insert(3, vec![1, 1, 1]);
+
+    S2GppModel {
+        pattern_length: 50,
+        latent: 16,
+        rate: 100,
+        query_length: 75,
+        mirrored: false,
+        components: vec![Array2::zeros((3, 34)), Array2::zeros((3, 34))],
+        means: vec![arr1(&[0.0; 34]), arr1(&[0.0; 34])],
+        rotation_matrix: Array3::zeros((3, 3, 2)),
+        cluster_centers,
+        cluster_sizes,
+        edge_weight: HashMap::new(),
+        node_degrees: HashMap::new(),
+        score_quantiles: vec![],
+        dataset_stats: DatasetStats::new(
+            arr1(&[1.0, 2.0]),
+            arr1(&[-3.0, -4.0]),
+            arr1(&[3.0, 4.0]),
+            10000,
+        ),
+    }
+}
+
+#[test]
+fn assigns_closest_cluster_center() {
+    let model = small_model();
+    assert_eq!(model.closest_cluster(3, arr1(&[4.0, 4.5]).view()), 1);
+    assert_eq!(model.closest_cluster(3, arr1(&[0.8, 1.1]).view()), 2);
+    assert_eq!(model.closest_cluster(4, arr1(&[0.8, 1.1]).view()), 0);
+}
+
+#[test]
+fn merges_intersections_into_closest_cluster() {
+    let mut model = small_model();
+    model
+        .merge_intersection(3, arr1(&[4.0, 4.0]).view(), None)
+        .unwrap();
+    assert_eq!(
+        model.cluster_centers[&3],
+        arr2(&[[0.0, 0.0], [4.5, 4.5], [1.0, 1.0]])
+    );
+    assert_eq!(model.cluster_sizes[&3], vec![1, 2, 1]);
+
+    model
+        .merge_intersection(4, arr1(&[2.0, 1.0]).view(), None)
+        .unwrap();
+    assert_eq!(model.cluster_centers[&4], arr2(&[[2.0, 1.0]]));
+    assert_eq!(model.cluster_sizes[&4], vec![1]);
+}
+
+#[test]
+fn merges_into_existing_centers_without_sizes() {
+    let mut model = small_model();
+    model.cluster_sizes.clear();
+    model
+        .merge_intersection(3, arr1(&[4.0, 4.0]).view(), None)
+        .unwrap();
+    assert_eq!(
+        model.cluster_centers[&3],
+        arr2(&[[0.0, 0.0], [4.5, 4.5], [1.0, 1.0]])
+    );
+    assert_eq!(model.cluster_sizes[&3], vec![1, 2, 1]);
+}
+
+#[test]
+fn opens_new_node_for_distant_intersections() {
+    let mut model = small_model();
+    let opened = model
+        .merge_intersection(3, arr1(&[4.0, 4.0]).view(), Some(2.0))
+        .unwrap();
+    assert!(!opened);
+    assert_eq!(model.cluster_sizes[&3], vec![1, 2, 1]);
+
+    let opened = model
+        .merge_intersection(3, arr1(&[20.0, 20.0]).view(), Some(2.0))
+        .unwrap();
+    assert!(opened);
+    assert_eq!(
+        model.cluster_centers[&3],
+        arr2(&[[0.0, 0.0], [4.5, 4.5], [1.0, 1.0], [20.0, 20.0]])
+    );
+    assert_eq!(model.cluster_sizes[&3], vec![1, 2, 1, 1]);
+    assert_eq!(model.closest_cluster(3, arr1(&[19.0, 21.0]).view()), 3);
+}
+
+#[test]
+fn updates_fitted_model_with_new_data() {
+    let dataset = read_data_("data/ts_0.csv");
+    let mut model = fit(Parameters::default(), dataset.clone()).unwrap();
+    let n_intersections: usize = model.cluster_sizes.values().flatten().sum();
+    let total_weight: usize = model.edge_weight.values().sum();
+
+    model.update(dataset, None).unwrap();
+
+    assert!(model.cluster_sizes.values().flatten().sum::<usize>() > n_intersections);
+    assert!(model.edge_weight.values().sum::<usize>() > total_weight);
+    for (segment_id, centers) in model.cluster_centers.iter() {
+        assert_eq!(centers.nrows(), model.cluster_sizes[segment_id].len());
+    }
+}
+
+#[test]
+fn rejects_data_with_different_dimensions() {
+    let model = small_model();
+    assert!(model.score(Array2::zeros((1000, 3))).is_err());
+    assert!(model.score(Array2::zeros((100, 2))).is_err());
+}
+
+#[test]
+fn scores_data_with_fitted_model() {
+    let dataset = read_data_("data/ts_0.csv");
+    let model = fit(Parameters::default(), dataset.clone()).unwrap();
+    assert_eq!(model.n_dims(), 2);
+
+    let anomaly_score = model.score(dataset).unwrap();
+    assert_eq!(anomaly_score.len(), 9875);
+    assert!(anomaly_score.iter().all(|x| (0.0..=1.0).contains(x)));
+}
+
+#[test]
+fn ranks_scores_against_training_scores() {
+    let dataset = read_data_("data/ts_0.csv");
+    let model = fit(Parameters::default(), dataset.clone()).unwrap();
+    assert_eq!(model.score_quantiles.len(), 101);
+    assert_eq!(model.dataset_stats.n, Some(dataset.nrows()));
+
+    let anomaly_score = model
+        .score_normalized(dataset, &ScoreNormalization::Model)
+        .unwrap();
+    assert!(anomaly_score.iter().all(|x| (0.0..=1.0).contains(x)));
+}
+
+#[test]
+fn saved_model_can_be_loaded() {
+    let model_path = "data/_test_model.s2gpp";
+    let model = small_model();
+    model.save(model_path).unwrap();
+
+    let loaded = S2GppModel::load(model_path).unwrap();
+    remove_file(model_path).expect("Could not delete test file!");
+
+    assert_eq!(loaded.pattern_length, model.pattern_length);
+    assert_eq!(loaded.n_dims(), model.n_dims());
+    assert_eq!(loaded.cluster_centers, model.cluster_centers);
+    assert_eq!(loaded.rotation_matrix, model.rotation_matrix);
+    assert_eq!(loaded.dataset_stats.n, Some(10000));
+    assert_eq!(loaded.dataset_stats.min_col, model.dataset_stats.min_col);
+    assert_eq!(loaded.dataset_stats.max_col, model.dataset_stats.max_col);
+    assert_eq!(loaded.dataset_stats.std_col, model.dataset_stats.std_col);
+}
+
+#[test]
+fn loading_other_files_fails() {
+    assert!(S2GppModel::load("data/test.csv").is_err());
+}
+
+#[test]
+fn online_scorer_rejects_observations_with_different_dimensions() {
+    let mut scorer = OnlineScorer::new(small_model());
+    assert!(scorer.push(arr1(&[0.0, 0.0, 0.0]).view()).is_err());
+    assert!(scorer.push(arr1(&[0.0, 0.0]).view()).is_ok());
+}
+
+#[test]
+fn online_scorer_matches_batch_scoring() {
+    let dataset = read_data_("data/ts_0.csv");
+    let model = fit(Parameters::default(), dataset.clone()).unwrap();
+    let batch_score = model.score(dataset.clone()).unwrap();
+
+    let mut scorer = OnlineScorer::new(model);
+    let mut online_score = vec![];
+    for observation in dataset.axis_iter(Axis(0)) {
+        online_score.extend(scorer.push(observation).unwrap());
+    }
+    assert!(!online_score.is_empty());
+    assert!(online_score.len() <= batch_score.len());
+
+    // the online scores are not normalized, so both are normalized on the common range
+    let normalize = |x: Array1<f32>| {
+        let (min, max) = x.iter().fold((f32::MAX, f32::MIN), |(min, max), v| {
+            (min.min(*v), max.max(*v))
+        });
+        (x - min) / (max - min)
+    };
+    let online_score = normalize(Array1::from(online_score));
+    let batch_score = normalize(batch_score.slice_move(ndarray::s![..online_score.len()]));
+    let mean_difference = (&online_score - &batch_score)
+        .mapv(f32::abs)
+        .mean()
+        .unwrap();
+    assert!(mean_difference < 0.01);
+}
+
+#[test]
+fn online_scorer_scores_saved_model_with_more_components() {
+    let model_path = "data/_test_model_components.s2gpp";
+    let dataset = read_data_("data/ts_0.csv");
+    let params = Parameters {
+        n_components: 4,
+        ..Default::default()
+    };
+    fit(params, dataset.clone())
+        .unwrap()
+        .save(model_path)
+        .unwrap();
+    let model = S2GppModel::load(model_path).unwrap();
+    remove_file(model_path).expect("Could not delete test file!");
+    assert_eq!(model.point_dims(), 6);
+    assert!(model.score(dataset.clone()).is_ok());
+
+    let mut scorer = OnlineScorer::new(model);
+    let mut online_score = vec![];
+    for observation in dataset.axis_iter(Axis(0)) {
+        online_score.extend(scorer.push(observation).unwrap());
+    }
+    assert!(!online_score.is_empty());
+}
+
+fn model_with_edge() -> S2GppModel {
+    let mut model = small_model();
+    let edge = Edge::new(
+        IndependentNode::new(3, 0, 0).into_ref(),
+        IndependentNode::new(3, 1, 1).into_ref(),
+    );
+    model.edge_weight.insert(edge.materialize(), 2);
+    model.node_degrees.insert(IndependentNode::new(3, 0, 0), 1);
+    model.node_degrees.insert(IndependentNode::new(3, 1, 1), 1);
+    model
+}
+
+#[test]
+fn exported_graph_contains_nodes_and_edges() {
+    let graph = ExportedGraph::from_model(&model_with_edge());
+
+    assert_eq!(graph.nodes.len(), 3);
+    assert_eq!(graph.nodes[1].id, "3_1");
+    assert_eq!(graph.nodes[1].center, vec![5.0, 5.0]);
+    assert_eq!(graph.nodes[1].degree, 1);
+    assert_eq!(graph.nodes[2].degree, 0);
+    assert_eq!(graph.edges.len(), 1);
+    assert_eq!(graph.edges[0].source, "3_0");
+    assert_eq!(graph.edges[0].target, "3_1");
+    assert_eq!(graph.edges[0].weight, 2);
+}
+
+#[test]
+fn graph_is_written_in_all_formats() {
+    let graph = ExportedGraph::from_model(&model_with_edge());
+    let write = |format: &str| {
+        let mut buffer = vec![];
+        graph
+            .write(&mut buffer, &GraphFormat::from_str(format).unwrap())
+            .unwrap();
+        String::from_utf8(buffer).unwrap()
+    };
+
+    let graphml = write("graphml");
+    assert!(graphml.contains(r#"<node id="3_2">"#));
+    assert!(graphml.contains(r#"<edge source="3_0" target="3_1">"#));
+
+    let dot = write("dot");
+    assert!(dot.starts_with("digraph s2gpp {"));
+    assert!(dot.contains(r#""3_0" -> "3_1" [weight=2];"#));
+
+    let json = write("json");
+    assert_eq!(json.matches(r#""id": "#).count(), 3);
+    let node = r#"{"id": "3_1", "segment": 3, "cluster": 1, "center": [5, 5], "degree": 1}"#;
+    assert!(json.contains(node));
+    assert!(json.contains(r#"{"source": "3_0", "target": "3_1", "weight": 2}"#));
+
+    assert!(GraphFormat::from_str("gml").is_err());
+}
@@ -0,0 +1,97 @@
+use crate::training::node_estimation::neighbours::{
+    closest_center, clustering_response, mean_k_distance, within,
+};
+use crate::training::node_estimation::ClusteringResponse;
+use ndarray::{ArrayView2, Axis};
+
+/// Density-based clustering. The neighbourhood radius is the mean distance of the points to their
+/// `min_samples`-th nearest neighbour. Every intersection must belong to a node, therefore noise
+/// points join the closest cluster.
+#[derive(Debug, Clone)]
+pub(crate) struct Dbscan {
+    min_samples: usize,
+}
+
+impl Dbscan {
+    pub fn cluster(&self, data: ArrayView2<f32>) -> ClusteringResponse<f32> {
+        let eps = mean_k_distance(data, self.min_samples);
+        let mut labels: Vec<Option<usize>> = vec![None; data.nrows()];
+        let mut n_clusters = 0;
+
+        if eps > f32::EPSILON {
+            for p in 0..data.nrows() {
+                if labels[p].is_some() {
+                    continue;
+                }
+                let mut queue = within(data, data.row(p), eps);
+                if queue.len() < self.min_samples {
+                    continue;
+                }
+                while let Some(q) = queue.pop() {
+                    if labels[q].is_some() {
+                        continue;
+                    }
+                    labels[q] = Some(n_clusters);
+                    let neighbours = within(data, data.row(q), eps);
+                    if neighbours.len() >= self.min_samples {
+                        queue.extend(neighbours.into_iter().filter(|r| labels[*r].is_none()));
+                    }
+                }
+                n_clusters += 1;
+            }
+        }
+
+        if n_clusters == 0 {
+            return clustering_response(data, vec![0; data.nrows()]);
+        }
+        let clustered: Vec<usize> = (0..data.nrows()).filter(|i| labels[*i].is_some()).collect();
+        let ClusteringResponse {
+            cluster_centers,
+            labels: core_labels,
+        } = clustering_response(
+            data.select(Axis(0), &clustered).view(),
+            labels.iter().flatten().copied().collect(),
+        );
+        let mut core_labels = core_labels.into_iter();
+        let labels = labels
+            .into_iter()
+            .zip(data.axis_iter(Axis(0)))
+            .map(|(label, point)| match label {
+                Some(_) => core_labels.next().unwrap(),
+                None => closest_center(point, cluster_centers.view()),
+            })
+            .collect();
+        clustering_response(data, labels)
+    }
+}
+
+impl Default for Dbscan {
+    fn default() -> Self {
+        Self { min_samples: 5 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::training::node_estimation::dbscan::Dbscan;
+    use ndarray::{concatenate, Array2, Axis};
+
+    #[test]
+    fn separates_distant_blobs() {
+        let blob: Array2<f32> = Array2::from_shape_fn((9, 2), |(i, j)| {
+            if j == 0 {
+                (i / 3) as f32 * 0.05
+            } else {
+                (i % 3) as f32 * 0.05
+            }
+        });
+        let far_blob: Array2<f32> = &blob + 10.;
+        let data = concatenate(Axis(0), &[blob.view(), far_blob.view()]).unwrap();
+
+        let response = Dbscan::default().cluster(data.view());
+        let mut expected = vec![0; 9];
+        expected.extend(vec![1; 9]);
+        assert_eq!(response.labels, expected);
+        assert_eq!(response.cluster_centers.nrows(), 2);
+    }
+}
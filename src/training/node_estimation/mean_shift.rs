@@ -0,0 +1,158 @@
+use crate::training::node_estimation::neighbours::{
+    closest_center, clustering_response, mean_k_distance, squared_distance, within,
+};
+use crate::training::node_estimation::ClusteringResponse;
+use ndarray::{stack, Array1, ArrayView1, ArrayView2, Axis};
+use std::collections::BTreeSet;
+
+/// Flat kernel mean shift, inspired by scikit-learn's
+/// [MeanShift](https://scikit-learn.org/stable/modules/generated/sklearn.cluster.MeanShift.html)
+/// with bin seeding. The bandwidth is estimated per segment.
+#[derive(Debug, Clone)]
+pub(crate) struct MeanShift {
+    quantile: f32,
+    max_iterations: usize,
+}
+
+impl MeanShift {
+    pub fn cluster(&self, data: ArrayView2<f32>) -> ClusteringResponse<f32> {
+        let k = (data.nrows() as f32 * self.quantile) as usize;
+        let bandwidth = mean_k_distance(data, k);
+        if bandwidth <= f32::EPSILON {
+            return clustering_response(data, vec![0; data.nrows()]);
+        }
+
+        let mut modes = self.climb_all(data, self.bin_seeds(data, bandwidth), bandwidth);
+        if modes.is_empty() {
+            // in many dimensions, a bin center can be farther than the bandwidth from all points
+            let seeds = data
+                .axis_iter(Axis(0))
+                .map(|point| point.to_owned())
+                .collect();
+            modes = self.climb_all(data, seeds, bandwidth);
+        }
+        modes.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+        let mut centers: Vec<Array1<f32>> = vec![];
+        for (mode, _) in modes {
+            if centers
+                .iter()
+                .all(|center| squared_distance(center.view(), mode.view()) >= bandwidth.powi(2))
+            {
+                centers.push(mode);
+            }
+        }
+        let centers: Vec<ArrayView1<f32>> = centers.iter().map(Array1::view).collect();
+        let centers = stack(Axis(0), centers.as_slice()).unwrap();
+
+        let labels = data
+            .axis_iter(Axis(0))
+            .map(|point| closest_center(point, centers.view()))
+            .collect();
+        clustering_response(data, labels)
+    }
+
+    /// One seed per occupied grid cell with the size of the bandwidth.
+    fn bin_seeds(&self, data: ArrayView2<f32>, bandwidth: f32) -> Vec<Array1<f32>> {
+        let bins: BTreeSet<Vec<i64>> = data
+            .axis_iter(Axis(0))
+            .map(|point| {
+                point
+                    .iter()
+                    .map(|x| (x / bandwidth).round() as i64)
+                    .collect()
+            })
+            .collect();
+        bins.into_iter()
+            .map(|bin| bin.into_iter().map(|x| x as f32 * bandwidth).collect())
+            .collect()
+    }
+
+    fn climb_all(
+        &self,
+        data: ArrayView2<f32>,
+        seeds: Vec<Array1<f32>>,
+        bandwidth: f32,
+    ) -> Vec<(Array1<f32>, usize)> {
+        seeds
+            .into_iter()
+            .filter_map(|seed| self.climb(data, seed, bandwidth))
+            .collect()
+    }
+
+    /// Moves the `seed` to the mean of its neighbourhood until it converges. Returns the mode and
+    /// the size of its neighbourhood.
+    fn climb(
+        &self,
+        data: ArrayView2<f32>,
+        seed: Array1<f32>,
+        bandwidth: f32,
+    ) -> Option<(Array1<f32>, usize)> {
+        let mut mean = seed;
+        let mut neighbours = within(data, mean.view(), bandwidth);
+        for _ in 0..self.max_iterations {
+            if neighbours.is_empty() {
+                return None;
+            }
+            let new_mean = data
+                .select(Axis(0), &neighbours)
+                .mean_axis(Axis(0))
+                .unwrap();
+            let shift = squared_distance(mean.view(), new_mean.view()).sqrt();
+            mean = new_mean;
+            neighbours = within(data, mean.view(), bandwidth);
+            if shift < 1e-3 * bandwidth {
+                break;
+            }
+        }
+        Some((mean, neighbours.len()))
+    }
+}
+
+impl Default for MeanShift {
+    fn default() -> Self {
+        Self {
+            quantile: 0.3,
+            max_iterations: 300,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::training::node_estimation::mean_shift::MeanShift;
+    use ndarray::{arr2, Array2};
+    use ndarray_linalg::close_l1;
+
+    #[test]
+    fn separates_two_blobs() {
+        let points = arr2(&[
+            [1.1, 0.9],
+            [0.9, 1.1],
+            [1.1, 1.1],
+            [0.9, 0.9],
+            [-1.1, -0.9],
+            [-0.9, -1.1],
+            [-1.1, -1.1],
+            [-0.9, -0.9],
+        ]);
+        let response = MeanShift::default().cluster(points.view());
+        assert_eq!(response.labels, vec![0, 0, 0, 0, 1, 1, 1, 1]);
+        close_l1(
+            &response.cluster_centers,
+            &arr2(&[[1., 1.], [-1., -1.]]),
+            0.0001,
+        );
+    }
+
+    #[test]
+    fn seeds_from_points_if_no_bin_has_neighbours() {
+        // every point is farther than the bandwidth (1.0) from the center of its bin
+        let mut points = Array2::from_elem((2, 9), 0.49);
+        points[[0, 8]] = 0.0;
+        points[[1, 8]] = 1.0;
+        let response = MeanShift::default().cluster(points.view());
+        assert_eq!(response.labels, vec![0, 0]);
+        assert_eq!(response.cluster_centers.nrows(), 1);
+    }
+}
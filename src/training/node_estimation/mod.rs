@@ -1,5 +1,9 @@
+mod dbscan;
+mod mean_shift;
 mod messages;
 mod multi_kde;
+mod neighbours;
+mod radius_kde;
 
 use crate::training::Training;
 use actix::{
@@ -15,16 +19,23 @@ use std::ops::Deref;
 use std::str::FromStr;
 
 use crate::data_store::intersection::IntersectionRef;
+use crate::error::S2GppError;
+use crate::messages::TrainingFailed;
 use crate::data_store::node::{IndependentNode, Node};
 use crate::data_store::node_questions::node_in_question::NodeInQuestion;
 use crate::data_store::node_questions::NodeQuestions;
-use crate::training::anomaly_contribution::ClusterCenterMessage;
+use crate::training::node_estimation::dbscan::Dbscan;
+use crate::training::node_estimation::mean_shift::MeanShift;
 pub(crate) use crate::training::node_estimation::messages::{
     AskForForeignNodes, ForeignNodesAnswer, NodeEstimationDone,
 };
 use crate::training::node_estimation::multi_kde::actors::messages::MultiKDEMessage;
 use crate::training::node_estimation::multi_kde::actors::MultiKDEActor;
+pub use crate::training::node_estimation::multi_kde::Bandwidth;
+use crate::training::node_estimation::multi_kde::MultiKDEBase;
+use crate::training::node_estimation::radius_kde::RadiusKDE;
 use crate::utils::direct_protocol::DirectProtocol;
+use crate::utils::Phase;
 
 #[derive(Default, Clone)]
 pub(crate) struct NodeEstimation {
@@ -35,13 +46,21 @@ pub(crate) struct NodeEstimation {
     asking_direct_protocol: DirectProtocol<AskForForeignNodes>,
     answering_direct_protocol: DirectProtocol<ForeignNodesAnswer>,
     answers: HashMap<usize, Vec<(usize, usize, usize, IndependentNode)>>,
+    /// cluster centers per segment; the row index is the node's cluster label
+    pub(crate) cluster_centers: HashMap<usize, Array2<f32>>,
+    /// number of intersections per cluster center and segment
+    pub(crate) cluster_sizes: HashMap<usize, Vec<usize>>,
 }
 
 pub(crate) trait NodeEstimator {
+    fn multi_kde_base(&self) -> MultiKDEBase;
     fn estimate_nodes(&mut self, clustering_recipient: Recipient<ClusteringResponse<f32>>);
     fn ask_for_foreign_nodes(&mut self, ctx: &mut Context<Training>);
     fn ask_next(&mut self);
-    fn search_for_asked_nodes(&mut self, node_questions: HashMap<usize, Vec<NodeInQuestion>>);
+    fn search_for_asked_nodes(
+        &mut self,
+        node_questions: HashMap<usize, Vec<NodeInQuestion>>,
+    ) -> Result<(), S2GppError>;
     fn start_anwering(&mut self, ctx: &mut Context<Training>);
     fn answer_next(&mut self, ctx: &mut Context<Training>);
     fn take_in_answers(&mut self, answers: Vec<(usize, usize, usize, IndependentNode)>);
@@ -49,6 +68,14 @@ pub(crate) trait NodeEstimator {
 }
 
 impl NodeEstimator for Training {
+    fn multi_kde_base(&self) -> MultiKDEBase {
+        MultiKDEBase::new(
+            self.parameters.kde_resolution,
+            self.parameters.kde_peak_order,
+        )
+        .with_bandwidth(self.parameters.kde_bandwidth.clone())
+    }
+
     fn estimate_nodes(&mut self, clustering_recipient: Recipient<ClusteringResponse<f32>>) {
         let segment_id = self.node_estimation.current_segment_id;
 
@@ -62,10 +89,23 @@ impl NodeEstimator for Training {
                 match data.nrows().cmp(&1) {
                     Ordering::Greater => match &self.parameters.clustering {
                         Clustering::MultiKDE => {
-                            let cluster_addr =
-                                MultiKDEActor::new(clustering_recipient, self.parameters.n_threads)
-                                    .start();
-                            cluster_addr.do_send(MultiKDEMessage { data });
+                            let cluster_addr = MultiKDEActor::new(
+                                clustering_recipient,
+                                self.parameters.n_threads,
+                                self.multi_kde_base(),
+                            )
+                            .start();
+                            cluster_addr.do_send(MultiKDEMessage { data, segment_id });
+                        }
+                        Clustering::RadiusKDE => {
+                            let radius_kde = RadiusKDE::new(self.multi_kde_base(), segment_id);
+                            clustering_recipient.do_send(radius_kde.cluster(data.view()))
+                        }
+                        Clustering::MeanShift => {
+                            clustering_recipient.do_send(MeanShift::default().cluster(data.view()))
+                        }
+                        Clustering::Dbscan => {
+                            clustering_recipient.do_send(Dbscan::default().cluster(data.view()))
                         }
                     },
                     Ordering::Equal => clustering_recipient
@@ -73,7 +113,14 @@ impl NodeEstimator for Training {
                             cluster_centers: data,
                             labels: vec![0],
                         }),
-                    Ordering::Less => panic!("No Intersection found."),
+                    Ordering::Less => self
+                        .own_addr
+                        .as_ref()
+                        .unwrap()
+                        .do_send(TrainingFailed {
+                            error: S2GppError::NoIntersection { segment_id },
+                            cluster_node: None,
+                        }),
                 }
             }
             None => {
@@ -120,7 +167,10 @@ impl NodeEstimator for Training {
         }
     }
 
-    fn search_for_asked_nodes(&mut self, mut node_questions: HashMap<usize, Vec<NodeInQuestion>>) {
+    fn search_for_asked_nodes(
+        &mut self,
+        mut node_questions: HashMap<usize, Vec<NodeInQuestion>>,
+    ) -> Result<(), S2GppError> {
         for (asking_node, _remote_addr) in self.cluster_nodes.iter() {
             let answers = match node_questions.remove(asking_node) {
                 Some(questions) => questions
@@ -139,22 +189,20 @@ impl NodeEstimator for Training {
                                         )
                                     })
                                 })
-                                .unwrap_or_else(|| {
-                                    panic!(
-                                        "There is no answer here: no segment_id: {} {}",
-                                        &niq.get_point_id(),
-                                        &niq.get_segment()
-                                    )
+                                .ok_or_else(|| {
+                                    S2GppError::Aborted(format!(
+                                        "Point {} has no node in the asked segment {}!",
+                                        niq.get_point_id(),
+                                        niq.get_segment()
+                                    ))
                                 }),
-                            None => {
-                                panic!(
-                                    "There is no answer here!: no point_id: {}",
-                                    niq.get_point_id()
-                                )
-                            }
+                            None => Err(S2GppError::Aborted(format!(
+                                "There are no nodes for the asked point {}!",
+                                niq.get_point_id()
+                            ))),
                         },
                     )
-                    .collect(),
+                    .collect::<Result<Vec<_>, S2GppError>>()?,
                 None => vec![],
             };
             match self.node_estimation.answers.get_mut(asking_node) {
@@ -164,6 +212,7 @@ impl NodeEstimator for Training {
                 }
             }
         }
+        Ok(())
     }
 
     fn start_anwering(&mut self, ctx: &mut Context<Training>) {
@@ -237,28 +286,32 @@ impl Handler<ClusteringResponse<f32>> for Training {
                 let node = Node::new(intersection.clone(), *label);
                 nodes.push(node.to_independent().into_ref());
             }
+            let mut label_counts: Vec<usize> = vec![0; msg.cluster_centers.nrows()];
+            for label in msg.labels {
+                label_counts[label] += 1;
+            }
+            self.node_estimation.cluster_centers.insert(
+                self.node_estimation.current_segment_id,
+                msg.cluster_centers.clone(),
+            );
+            self.node_estimation.cluster_sizes.insert(
+                self.node_estimation.current_segment_id,
+                label_counts.clone(),
+            );
             if self.parameters.explainability {
-                let mut label_counts: Vec<usize> = (0..(msg.labels.iter().max().unwrap() + 1))
-                    .map(|_| 0)
-                    .collect();
-                for label in msg.labels {
-                    label_counts[label] += 1;
-                }
-
-                self.anomaly_contribution
-                    .as_ref()
-                    .expect("Should've been started by now")
-                    .do_send(ClusterCenterMessage {
-                        cluster_centers: msg.cluster_centers,
-                        nodes: nodes.clone(),
-                        label_counts,
-                    })
+                self.anomaly_contribution.record_contributions(
+                    nodes.clone(),
+                    msg.cluster_centers,
+                    label_counts,
+                    &self.parameters.contribution_metric,
+                );
             }
             for node in nodes {
                 self.data_store.add_node_ref(node)
             }
         }
         self.node_estimation.current_segment_id += 1;
+        self.parameters.progress.advance(Phase::NodeEstimation, 1);
 
         if self.node_estimation.current_segment_id < self.parameters.rate {
             self.estimate_nodes(ctx.address().recipient());
@@ -278,7 +331,10 @@ impl Handler<AskForForeignNodes> for Training {
 
         let mut asked_nodes = msg.asked_nodes;
         if let Some(questions) = asked_nodes.remove(&self.cluster_nodes.get_own_idx()) {
-            self.search_for_asked_nodes(questions);
+            if let Err(error) = self.search_for_asked_nodes(questions) {
+                self.fail(error, ctx);
+                return;
+            }
         }
 
         if !self.node_estimation.asking_direct_protocol.is_running() {
@@ -310,22 +366,29 @@ impl Handler<ForeignNodesAnswer> for Training {
     }
 }
 
+/// How the intersections of a segment are clustered to nodes. `MultiKDE` is the most accurate;
+/// the other methods trade accuracy for speed.
 #[derive(Debug, Clone)]
 pub enum Clustering {
     MultiKDE,
+    RadiusKDE,
+    MeanShift,
+    Dbscan,
 }
 
 impl FromStr for Clustering {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.eq("kde") {
-            Ok(Clustering::MultiKDE)
-        } else {
-            Err(format!(
-                "{} is not a valid clustering method! Allowed values are: 'meanshift' and 'kde'",
+        match s {
+            "kde" => Ok(Clustering::MultiKDE),
+            "radius-kde" => Ok(Clustering::RadiusKDE),
+            "meanshift" => Ok(Clustering::MeanShift),
+            "dbscan" => Ok(Clustering::Dbscan),
+            _ => Err(format!(
+                "{} is not a valid clustering method! Allowed values are: 'kde', 'radius-kde', 'meanshift' and 'dbscan'",
                 s
-            ))
+            )),
         }
     }
 }
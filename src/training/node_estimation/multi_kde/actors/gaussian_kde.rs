@@ -3,16 +3,18 @@ use crate::training::node_estimation::multi_kde::actors::helper::EstimatorHelper
 use crate::training::node_estimation::multi_kde::actors::messages::{
     EstimatorResponse, EstimatorTask, GaussianKDEMessage, GaussianKDEResponse,
 };
+use crate::training::node_estimation::multi_kde::Bandwidth;
 use crate::utils::HelperProtocol;
 use actix::{Actor, ActorContext, Addr, AsyncContext, Context, Handler, Recipient, SyncArbiter};
-use ndarray::{ArcArray2, Array, Array1, Array2, ArrayView2, Axis, Dim};
-use ndarray_linalg::Inverse;
-use ndarray_stats::{CorrelationExt, QuantileExt};
+use ndarray::{ArcArray2, Array, Array1, Array2, Axis, Dim};
+use ndarray_stats::QuantileExt;
 use std::ops::{AddAssign, Mul};
 
 pub(in crate::training::node_estimation::multi_kde::actors) struct GaussianKDEActor {
     n_threads: usize,
     resolution: usize,
+    bandwidth: Bandwidth,
+    kernel_bandwidth: f32,
     data: Option<ArcArray2<f32>>,
     helper: Option<Addr<EstimatorHelper>>,
     helper_protocol: HelperProtocol,
@@ -24,11 +26,13 @@ impl GaussianKDEActor {
     pub fn new(
         n_threads: usize,
         resolution: usize,
+        bandwidth: Bandwidth,
         receiver: Recipient<GaussianKDEResponse>,
     ) -> Self {
         Self {
             n_threads,
             resolution,
+            bandwidth,
             receiver: Some(receiver),
             ..Default::default()
         }
@@ -47,11 +51,11 @@ impl GaussianKDEActor {
         let padding = (grid_max - grid_min).mul(0.1);
         let grid = Array::linspace(grid_min - padding, grid_max + padding, self.resolution)
             .insert_axis(Axis(1));
+        self.kernel_bandwidth = self.bandwidth.standard_deviation(data.column(0));
         self.data = Some(data);
 
         let weights = self.calculate_weights();
-        let scotts_factor = self.scotts_factor(weights.view());
-        let precision = self.compute_covariance(scotts_factor);
+        let precision = Array2::from_elem((1, 1), self.kernel_bandwidth.powi(-2));
         self.evaluate(grid, weights, precision, ctx);
     }
 
@@ -95,23 +99,6 @@ impl GaussianKDEActor {
         }
     }
 
-    fn compute_covariance(&self, factor: f32) -> Array2<f32> {
-        let covariance = self.data.as_ref().unwrap().t().cov(1.).unwrap();
-        let covariance_inv = covariance.inv().unwrap(); // todo: catch exception
-        covariance_inv / factor.powi(2)
-    }
-
-    fn scotts_factor(&self, weights: ArrayView2<f32>) -> f32 {
-        let d = self.data.as_ref().unwrap().shape()[1];
-        let exponent = -1.0 / ((d + 4) as f32);
-        self.neff(weights).powf(exponent)
-    }
-
-    fn neff(&self, weights: ArrayView2<f32>) -> f32 {
-        let weights_sum: f32 = weights.iter().map(|w| w.powi(2)).sum();
-        1.0 / weights_sum
-    }
-
     fn calculate_weights(&self) -> Array2<f32> {
         let n = self.data.as_ref().unwrap().shape()[0];
         Array2::ones(Dim([n, 1])) / (n as f32)
@@ -123,6 +110,8 @@ impl Default for GaussianKDEActor {
         Self {
             n_threads: 1,
             resolution: 250,
+            bandwidth: Bandwidth::Scott,
+            kernel_bandwidth: 0.0,
             data: None,
             helper: None,
             helper_protocol: HelperProtocol::default(),
@@ -167,6 +156,7 @@ impl Handler<EstimatorResponse> for GaussianKDEActor {
                 .unwrap()
                 .do_send(GaussianKDEResponse {
                     kernel_estimate: estimate,
+                    bandwidth: self.kernel_bandwidth,
                 });
         }
     }
@@ -6,6 +6,7 @@ use std::ops::Range;
 #[rtype(Result = "()")]
 pub(in crate::training::node_estimation) struct MultiKDEMessage {
     pub data: Array2<f32>,
+    pub segment_id: usize,
 }
 
 #[derive(Message)]
@@ -18,6 +19,7 @@ pub(in crate::training::node_estimation) struct GaussianKDEMessage {
 #[rtype(Result = "()")]
 pub(in crate::training::node_estimation) struct GaussianKDEResponse {
     pub kernel_estimate: Array1<f32>,
+    pub bandwidth: f32,
 }
 
 #[derive(Message)]
@@ -8,6 +8,7 @@ use crate::training::node_estimation::multi_kde::MultiKDEBase;
 use crate::utils::pop_clear::PopClear;
 use crate::utils::stack::Stack;
 use actix::{Actor, ActorContext, Addr, AsyncContext, Context, Handler, Recipient};
+use log::*;
 use ndarray::{s, ArcArray2, Array1, Array2, Axis};
 use ndarray_stats::QuantileExt;
 
@@ -22,6 +23,8 @@ pub(in crate::training::node_estimation) struct MultiKDEActor {
     n_threads: usize,
     multi_kde_base: MultiKDEBase,
     data: Option<Array2<f32>>,
+    segment_id: usize,
+    bandwidths: Vec<f32>,
     next_dim: usize,
     current_column: Option<ArcArray2<f32>>,
     cluster_centers: Vec<Array1<f32>>,
@@ -29,12 +32,18 @@ pub(in crate::training::node_estimation) struct MultiKDEActor {
 }
 
 impl MultiKDEActor {
-    pub fn new(receiver: Recipient<ClusteringResponse<f32>>, n_threads: usize) -> Self {
+    pub fn new(
+        receiver: Recipient<ClusteringResponse<f32>>,
+        n_threads: usize,
+        multi_kde_base: MultiKDEBase,
+    ) -> Self {
         Self {
             receiver,
             n_threads,
-            multi_kde_base: MultiKDEBase::default(),
+            multi_kde_base,
             data: None,
+            segment_id: 0,
+            bandwidths: vec![],
             next_dim: 0,
             current_column: None,
             cluster_centers: vec![],
@@ -55,6 +64,11 @@ impl MultiKDEActor {
             self.current_column = Some(column);
             self.next_dim += 1;
         } else {
+            let bandwidths = self.bandwidths.pop_clear();
+            debug!(
+                "Segment {} uses the KDE bandwidths {:?}",
+                self.segment_id, bandwidths
+            );
             let cluster_centers_vec = self.cluster_centers.pop_clear();
             let cluster_centers = cluster_centers_vec.stack(Axis(1)).unwrap();
             let (labels, cluster_centers) = self
@@ -101,8 +115,16 @@ impl Handler<MultiKDEMessage> for MultiKDEActor {
 
     fn handle(&mut self, msg: MultiKDEMessage, ctx: &mut Self::Context) -> Self::Result {
         self.data = Some(msg.data);
-        self.gaussian_kde =
-            Some(GaussianKDEActor::new(self.n_threads, 250, ctx.address().recipient()).start());
+        self.segment_id = msg.segment_id;
+        self.gaussian_kde = Some(
+            GaussianKDEActor::new(
+                self.n_threads,
+                self.multi_kde_base.resolution,
+                self.multi_kde_base.bandwidth.clone(),
+                ctx.address().recipient(),
+            )
+            .start(),
+        );
         self.cluster_next_dim(ctx);
     }
 }
@@ -111,6 +133,7 @@ impl Handler<GaussianKDEResponse> for MultiKDEActor {
     type Result = ();
 
     fn handle(&mut self, msg: GaussianKDEResponse, ctx: &mut Self::Context) -> Self::Result {
+        self.bandwidths.push(msg.bandwidth);
         self.find_cluster_centers(msg.kernel_estimate, ctx);
     }
 }
@@ -1,6 +1,7 @@
 use crate::training::node_estimation::ClusteringResponse;
 use crate::training::node_estimation::multi_kde::actors::messages::MultiKDEMessage;
 use crate::training::node_estimation::multi_kde::actors::MultiKDEActor;
+use crate::training::node_estimation::multi_kde::MultiKDEBase;
 use actix::{Actor, Context, Handler};
 use ndarray::{arr2, Array1, Array2, Axis};
 use ndarray_stats::CorrelationExt;
@@ -46,8 +47,11 @@ async fn correct_result() {
         coords: None,
     })
     .start();
-    let mkde = MultiKDEActor::new(receiver.recipient(), 1).start();
-    mkde.do_send(MultiKDEMessage { data });
+    let mkde = MultiKDEActor::new(receiver.recipient(), 1, MultiKDEBase::default()).start();
+    mkde.do_send(MultiKDEMessage {
+        data,
+        segment_id: 0,
+    });
     sleep(Duration::from_millis(2000)).await;
     assert_eq!(labels.lock().unwrap().deref().clone(), expected);
 }
@@ -62,8 +66,11 @@ async fn correct_result_multithreading() {
         coords: None,
     })
     .start();
-    let mkde = MultiKDEActor::new(receiver.recipient(), 4).start();
-    mkde.do_send(MultiKDEMessage { data });
+    let mkde = MultiKDEActor::new(receiver.recipient(), 4, MultiKDEBase::default()).start();
+    mkde.do_send(MultiKDEMessage {
+        data,
+        segment_id: 0,
+    });
     sleep(Duration::from_millis(1000)).await;
     assert_eq!(labels.lock().unwrap().deref().clone(), expected);
 }
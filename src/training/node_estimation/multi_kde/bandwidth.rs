@@ -0,0 +1,68 @@
+use ndarray::ArrayView1;
+use std::str::FromStr;
+
+/// How the standard deviation of the Gaussian kernels is chosen.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Bandwidth {
+    Scott,
+    Silverman,
+    Fixed(f32),
+}
+
+impl Bandwidth {
+    /// The kernel standard deviation for the one-dimensional `values`. Scott's and Silverman's
+    /// rules scale the standard deviation of the values like scipy's `gaussian_kde`.
+    pub fn standard_deviation(&self, values: ArrayView1<f32>) -> f32 {
+        let n = values.len() as f32;
+        match self {
+            Bandwidth::Scott => values.std(1.) * n.powf(-0.2),
+            Bandwidth::Silverman => values.std(1.) * (n * 0.75).powf(-0.2),
+            Bandwidth::Fixed(bandwidth) => *bandwidth,
+        }
+    }
+}
+
+impl FromStr for Bandwidth {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "scott" => Ok(Bandwidth::Scott),
+            "silverman" => Ok(Bandwidth::Silverman),
+            _ => match s.parse::<f32>() {
+                Ok(bandwidth) if bandwidth > 0.0 && bandwidth.is_finite() => {
+                    Ok(Bandwidth::Fixed(bandwidth))
+                }
+                _ => Err(format!(
+                    "{} is not a valid bandwidth! Allowed values are: 'scott', 'silverman' or a positive number",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::training::node_estimation::multi_kde::Bandwidth;
+    use ndarray::arr1;
+    use std::str::FromStr;
+
+    #[test]
+    fn parses_rules_and_fixed_values() {
+        assert_eq!(Bandwidth::from_str("scott"), Ok(Bandwidth::Scott));
+        assert_eq!(Bandwidth::from_str("silverman"), Ok(Bandwidth::Silverman));
+        assert_eq!(Bandwidth::from_str("0.5"), Ok(Bandwidth::Fixed(0.5)));
+        assert!(Bandwidth::from_str("-1").is_err());
+        assert!(Bandwidth::from_str("wide").is_err());
+    }
+
+    #[test]
+    fn silverman_is_wider_than_scott() {
+        let values = arr1(&[1., 2., 3., 4., 5., 6.]);
+        let scott = Bandwidth::Scott.standard_deviation(values.view());
+        let silverman = Bandwidth::Silverman.standard_deviation(values.view());
+        assert!(silverman > scott);
+        assert_eq!(Bandwidth::Fixed(0.3).standard_deviation(values.view()), 0.3);
+    }
+}
@@ -10,26 +10,34 @@ use crate::utils::boolean::BooleanCollectives;
 use crate::utils::float_approx::FloatApprox;
 use crate::utils::itertools::FromToAble;
 use crate::utils::stack::Stack;
+pub use bandwidth::Bandwidth;
 
 pub(crate) mod actors;
+mod bandwidth;
 mod gaussian_kde;
 mod gaussian_kernel_estimate;
 
 #[derive(Debug, Clone)]
 pub(crate) struct MultiKDEBase {
-    resolution: usize,
+    pub(in crate::training::node_estimation) resolution: usize,
     peak_order: usize,
+    pub(in crate::training::node_estimation) bandwidth: Bandwidth,
 }
 
 impl MultiKDEBase {
-    #[allow(dead_code)]
     pub fn new(resolution: usize, peak_order: usize) -> Self {
         Self {
             resolution,
             peak_order,
+            bandwidth: Bandwidth::Scott,
         }
     }
 
+    pub fn with_bandwidth(mut self, bandwidth: Bandwidth) -> Self {
+        self.bandwidth = bandwidth;
+        self
+    }
+
     #[allow(dead_code)]
     pub fn cluster(&self, data: ArrayView2<f32>) -> Result<Array1<usize>> {
         let n_dims = data.shape()[1];
@@ -70,7 +78,10 @@ impl MultiKDEBase {
         result
     }
 
-    fn find_peak_index(&self, kernel_estimate: ArrayView1<f32>) -> Vec<usize> {
+    pub(in crate::training::node_estimation) fn find_peak_index(
+        &self,
+        kernel_estimate: ArrayView1<f32>,
+    ) -> Vec<usize> {
         let mut results: Array1<bool> = arr1(vec![true; kernel_estimate.len()].as_slice());
         let datalen = results.len();
         for shift in 1..self.peak_order + 1 {
@@ -164,6 +175,7 @@ impl Default for MultiKDEBase {
         Self {
             resolution: 250,
             peak_order: 1,
+            bandwidth: Bandwidth::Scott,
         }
     }
 }
@@ -0,0 +1,110 @@
+use crate::training::node_estimation::ClusteringResponse;
+use ndarray::{Array2, ArrayView1, ArrayView2, Axis};
+use std::collections::HashMap;
+
+/// Distance estimations only consider this many points of a segment.
+const MAX_SAMPLES: usize = 500;
+
+pub(super) fn squared_distance(a: ArrayView1<f32>, b: ArrayView1<f32>) -> f32 {
+    a.iter().zip(b.iter()).map(|(x, y)| (x - y).powi(2)).sum()
+}
+
+/// The indices of all points that are at most `radius` away from `point`.
+pub(super) fn within(data: ArrayView2<f32>, point: ArrayView1<f32>, radius: f32) -> Vec<usize> {
+    let squared_radius = radius.powi(2);
+    data.axis_iter(Axis(0))
+        .enumerate()
+        .filter(|(_, other)| squared_distance(point, *other) <= squared_radius)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// The mean distance of the points to their `k`-th nearest neighbour.
+pub(super) fn mean_k_distance(data: ArrayView2<f32>, k: usize) -> f32 {
+    let n = data.nrows();
+    if n < 2 {
+        return 0.0;
+    }
+    let k = k.clamp(1, n - 1);
+    let step = (n / MAX_SAMPLES).max(1);
+    let (sum, count) = (0..n).step_by(step).fold((0.0, 0), |(sum, count), i| {
+        let mut distances: Vec<f32> = data
+            .axis_iter(Axis(0))
+            .map(|other| squared_distance(data.row(i), other))
+            .collect();
+        let (_, kth, _) = distances.select_nth_unstable_by(k, |a, b| a.partial_cmp(b).unwrap());
+        (sum + kth.sqrt(), count + 1)
+    });
+    sum / count as f32
+}
+
+/// The index of the center that is closest to `point`.
+pub(super) fn closest_center(point: ArrayView1<f32>, centers: ArrayView2<f32>) -> usize {
+    centers
+        .axis_iter(Axis(0))
+        .map(|center| squared_distance(point, center))
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+        .map(|(c, _)| c)
+        .expect("There must be at least one center")
+}
+
+/// Renumbers the `labels` consecutively in order of their first occurrence and uses the mean of
+/// each cluster as its center.
+pub(super) fn clustering_response(
+    data: ArrayView2<f32>,
+    labels: Vec<usize>,
+) -> ClusteringResponse<f32> {
+    let mut renumbered: HashMap<usize, usize> = HashMap::new();
+    let labels: Vec<usize> = labels
+        .into_iter()
+        .map(|label| {
+            let next = renumbered.len();
+            *renumbered.entry(label).or_insert(next)
+        })
+        .collect();
+
+    let mut cluster_centers = Array2::zeros((renumbered.len(), data.ncols()));
+    let mut counts = vec![0.0; renumbered.len()];
+    for (point, label) in data.axis_iter(Axis(0)).zip(labels.iter()) {
+        let mut center = cluster_centers.row_mut(*label);
+        center += &point;
+        counts[*label] += 1.0;
+    }
+    for (mut center, count) in cluster_centers.axis_iter_mut(Axis(0)).zip(counts) {
+        center /= count;
+    }
+
+    ClusteringResponse {
+        cluster_centers,
+        labels,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::training::node_estimation::neighbours::{
+        clustering_response, mean_k_distance, within,
+    };
+    use ndarray::arr2;
+
+    #[test]
+    fn finds_points_within_radius() {
+        let data = arr2(&[[0., 0.], [1., 0.], [3., 0.]]);
+        assert_eq!(within(data.view(), data.row(0), 1.5), vec![0, 1]);
+    }
+
+    #[test]
+    fn mean_distance_to_nearest_neighbour() {
+        let data = arr2(&[[0., 0.], [1., 0.], [3., 0.]]);
+        assert_eq!(mean_k_distance(data.view(), 1), (1. + 1. + 2.) / 3.);
+    }
+
+    #[test]
+    fn renumbers_labels_and_averages_centers() {
+        let data = arr2(&[[0., 0.], [4., 4.], [2., 0.], [6., 4.]]);
+        let response = clustering_response(data.view(), vec![3, 1, 3, 1]);
+        assert_eq!(response.labels, vec![0, 1, 0, 1]);
+        assert_eq!(response.cluster_centers, arr2(&[[1., 0.], [5., 4.]]));
+    }
+}
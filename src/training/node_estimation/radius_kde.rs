@@ -0,0 +1,99 @@
+use crate::training::node_estimation::multi_kde::MultiKDEBase;
+use crate::training::node_estimation::neighbours::clustering_response;
+use crate::training::node_estimation::ClusteringResponse;
+use crate::utils::norm;
+use log::*;
+use ndarray::{Array, Array1, ArrayView2, Axis};
+use ndarray_stats::QuantileExt;
+
+/// Clusters the intersections of a segment by a 1-D Gaussian KDE on their distance to the origin,
+/// as in the original Series2Graph. Much faster than `MultiKDE`, but it cannot separate
+/// intersections with the same radius. It shares the settings of `MultiKDE`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RadiusKDE {
+    multi_kde_base: MultiKDEBase,
+    segment_id: usize,
+}
+
+impl RadiusKDE {
+    pub fn new(multi_kde_base: MultiKDEBase, segment_id: usize) -> Self {
+        Self {
+            multi_kde_base,
+            segment_id,
+        }
+    }
+
+    pub fn cluster(&self, data: ArrayView2<f32>) -> ClusteringResponse<f32> {
+        let radii = norm(data, Axis(1));
+        let peaks = self.find_peaks(&radii);
+        let labels = radii
+            .iter()
+            .map(|radius| {
+                peaks
+                    .iter()
+                    .map(|peak| (peak - radius).abs())
+                    .enumerate()
+                    .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+                    .map_or(0, |(p, _)| p)
+            })
+            .collect();
+        clustering_response(data, labels)
+    }
+
+    /// The radii with the highest density.
+    fn find_peaks(&self, radii: &Array1<f32>) -> Vec<f32> {
+        let (min, max) = (*radii.min().unwrap(), *radii.max().unwrap());
+        let bandwidth = self
+            .multi_kde_base
+            .bandwidth
+            .standard_deviation(radii.view());
+        debug!(
+            "Segment {} uses the KDE bandwidth {}",
+            self.segment_id, bandwidth
+        );
+        if max - min <= f32::EPSILON || bandwidth <= f32::EPSILON {
+            return vec![];
+        }
+
+        let grid = Array::linspace(min, max, self.multi_kde_base.resolution);
+        let kernel_estimate = grid.mapv(|x| {
+            radii
+                .iter()
+                .map(|radius| (-0.5 * ((radius - x) / bandwidth).powi(2)).exp())
+                .sum::<f32>()
+        });
+        self.multi_kde_base
+            .find_peak_index(kernel_estimate.view())
+            .into_iter()
+            .map(|i| grid[i])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::training::node_estimation::radius_kde::RadiusKDE;
+    use ndarray::arr2;
+
+    #[test]
+    fn separates_rings() {
+        let points = arr2(&[
+            [1., 0., 0.],
+            [0., 1.1, 0.],
+            [-0.9, 0., 0.],
+            [0., -1., 0.],
+            [5., 0., 0.],
+            [0., 5.1, 0.],
+            [-4.9, 0., 0.],
+        ]);
+        let response = RadiusKDE::default().cluster(points.view());
+        assert_eq!(response.labels, vec![0, 0, 0, 0, 1, 1, 1]);
+    }
+
+    #[test]
+    fn single_radius_is_one_cluster() {
+        let points = arr2(&[[1., 0.], [0., 1.], [-1., 0.]]);
+        let response = RadiusKDE::default().cluster(points.view());
+        assert_eq!(response.labels, vec![0, 0, 0]);
+    }
+}
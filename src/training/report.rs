@@ -0,0 +1,136 @@
+use crate::utils::json;
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::time::Instant;
+
+#[derive(Debug, Clone)]
+pub(crate) struct PhaseReport {
+    name: &'static str,
+    seconds: f64,
+    /// peak resident set size during the phase, only available on Linux
+    peak_memory_bytes: Option<u64>,
+    counts: BTreeMap<&'static str, usize>,
+}
+
+impl PhaseReport {
+    fn to_json(&self) -> String {
+        json::object([
+            ("name", json::string(self.name)),
+            ("seconds", json::number(self.seconds)),
+            ("peak_memory_bytes", json::optional(self.peak_memory_bytes)),
+            (
+                "counts",
+                json::object(
+                    self.counts
+                        .iter()
+                        .map(|(name, count)| (*name, count.to_string())),
+                ),
+            ),
+        ])
+    }
+}
+
+/// Wall time, peak memory and sizes of the data structures of each phase of a training on this
+/// cluster node. A phase ends with the handler of its done message.
+#[derive(Default, Clone)]
+pub(crate) struct RunReport {
+    started: Option<Instant>,
+    phase_started: Option<Instant>,
+    pub phases: Vec<PhaseReport>,
+}
+
+impl RunReport {
+    pub fn start(&mut self) {
+        let now = Instant::now();
+        self.started = Some(now);
+        self.phase_started = Some(now);
+        reset_peak_memory();
+    }
+
+    /// Records the phase that ended now. Does nothing if the report was not started.
+    pub fn finish_phase(&mut self, name: &'static str, counts: &[(&'static str, usize)]) {
+        let phase_started = match self.phase_started {
+            Some(phase_started) => phase_started,
+            None => return,
+        };
+        let now = Instant::now();
+        self.phases.push(PhaseReport {
+            name,
+            seconds: now.duration_since(phase_started).as_secs_f64(),
+            peak_memory_bytes: peak_memory(),
+            counts: counts.iter().copied().collect(),
+        });
+        self.phase_started = Some(now);
+        reset_peak_memory();
+    }
+
+    pub fn write(&self, path: &str, cluster_node: usize, n_cluster_nodes: usize) -> Result<()> {
+        let seconds = self
+            .started
+            .map_or(0.0, |started| started.elapsed().as_secs_f64());
+        let peak_memory_bytes = self
+            .phases
+            .iter()
+            .filter_map(|phase| phase.peak_memory_bytes)
+            .max();
+        let phases = self.phases.iter().map(|phase| phase.to_json());
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{{")?;
+        writeln!(writer, r#"  "cluster_node": {},"#, cluster_node)?;
+        writeln!(writer, r#"  "n_cluster_nodes": {},"#, n_cluster_nodes)?;
+        writeln!(writer, r#"  "seconds": {},"#, json::number(seconds))?;
+        writeln!(
+            writer,
+            r#"  "peak_memory_bytes": {},"#,
+            json::optional(peak_memory_bytes)
+        )?;
+        writeln!(writer, r#"  "phases": {}"#, json::array_lines(phases, "  "))?;
+        writeln!(writer, "}}")?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// The peak resident set size of the process since the last reset, read from `/proc`.
+fn peak_memory() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))
+        .and_then(|value| {
+            value
+                .trim()
+                .trim_end_matches("kB")
+                .trim()
+                .parse::<u64>()
+                .ok()
+        })
+        .map(|kilobytes| kilobytes * 1024)
+}
+
+/// Resets the peak resident set size, so that each phase reports its own peak.
+fn reset_peak_memory() {
+    let _ = std::fs::write("/proc/self/clear_refs", "5");
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::training::report::RunReport;
+
+    #[test]
+    fn records_phases_only_after_start() {
+        let mut report = RunReport::default();
+        report.finish_phase("rotation", &[("points", 3)]);
+        assert!(report.phases.is_empty());
+
+        report.start();
+        report.finish_phase("rotation", &[("points", 3)]);
+        report.finish_phase("segmentation", &[("transitions", 2)]);
+        let names: Vec<&str> = report.phases.iter().map(|phase| phase.name).collect();
+        assert_eq!(names, vec!["rotation", "segmentation"]);
+        assert_eq!(report.phases[0].counts["points"], 3);
+    }
+}
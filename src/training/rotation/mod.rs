@@ -1,18 +1,16 @@
-use std::ops::{Mul, Sub};
+use std::ops::Sub;
 
 use actix::{Addr, AsyncContext, Handler};
 
-use ndarray::{
-    arr1, concatenate, s, stack, ArcArray, Array1, Array2, Array3, ArrayBase, ArrayView2, Axis,
-    Dim, Ix3,
-};
+use ndarray::{s, stack, ArcArray, Array1, Array2, Array3, ArrayBase, ArrayView2, Axis, Dim, Ix3};
 
 use crate::parameters::Role;
 pub use crate::training::rotation::messages::{RotationDoneMessage, RotationMatrixMessage};
 pub use crate::training::rotation::pca::*;
 use crate::training::rotation::pca::{PCAnalyzer, PCA};
 use crate::training::Training;
-use crate::utils::{cross2d, norm, repeat};
+use crate::utils::Phase;
+use ndarray_linalg::Norm;
 
 mod messages;
 mod pca;
@@ -21,12 +19,93 @@ mod pca;
 pub struct Rotation {
     phase_space: Option<ArcArray<f32, Ix3>>,
     data_ref: Option<ArcArray<f32, Ix3>>,
-    reduced: Option<Array3<f32>>,
+    pub reduced: Option<Array3<f32>>,
     reduced_ref: Option<Array3<f32>>,
     n_reduced: usize,
     broadcasted: bool,
     rotation_matrix_buffer: Option<RotationMatrixMessage>,
     pub pca: PCA,
+    /// principal components of each input dimension
+    pub components: Vec<Array2<f32>>,
+    /// means each input dimension is centered with before its projection
+    pub means: Vec<Array1<f32>>,
+    pub rotation_matrix: Option<Array3<f32>>,
+}
+
+/// Centers one dimension of the phase space and projects it onto its principal components.
+pub(crate) fn project(
+    x: ArrayView2<f32>,
+    global_means: &Array1<f32>,
+    components: &Array2<f32>,
+) -> Array2<f32> {
+    let shape = x.shape();
+    let x = x.sub(&global_means.broadcast([shape[0], shape[1]]).unwrap());
+    x.dot(&components.t())
+}
+
+/// Rotation matrices (components x components x input dimensions) that turn the direction of the
+/// reference `curve` of each input dimension onto the last axis. Only the plane spanned by both
+/// vectors is rotated: `R = I + K + K^2 / (1 + cos)` with `K = b a^T - a b^T`, which is Rodrigues'
+/// formula for 3 components.
+pub(crate) fn rotation_onto_last_axis(curve: ArrayView2<f32>) -> Array3<f32> {
+    let n_components = curve.nrows();
+    let mut b: Array1<f32> = Array1::zeros(n_components);
+    b[n_components - 1] = 1.0;
+    let b = b.insert_axis(Axis(1));
+    let identity: Array2<f32> = Array2::eye(n_components);
+
+    let rotations: Vec<Array2<f32>> = curve
+        .axis_iter(Axis(1))
+        .map(|a| {
+            let a = (&a / a.norm()).insert_axis(Axis(1));
+            let c = b.t().dot(&a)[[0, 0]];
+            let k = b.dot(&a.t()) - a.dot(&b.t());
+            &identity + &k + k.dot(&k) / (1.0 + c)
+        })
+        .collect();
+    stack(
+        Axis(2),
+        rotations
+            .iter()
+            .map(|x| x.view())
+            .collect::<Vec<ArrayView2<f32>>>()
+            .as_slice(),
+    )
+    .unwrap()
+}
+
+/// Rotates the reduced phase space of each dimension and flattens all rotated components but the
+/// last, which points along the reference curve, of all dimensions into one point per time step.
+/// The coordinates are ordered by component first and by input dimension second.
+pub(crate) fn rotate_reduced(
+    rotation_matrix: &Array3<f32>,
+    reduced: &Array3<f32>,
+) -> Vec<Array1<f32>> {
+    let rotations: Vec<Array2<f32>> = rotation_matrix
+        .axis_iter(Axis(2))
+        .zip(reduced.axis_iter(Axis(2)))
+        .map(|(a, b)| b.dot(&a.t()))
+        .collect();
+
+    let rotated_all = stack(
+        Axis(2),
+        rotations
+            .iter()
+            .map(|x| x.view())
+            .collect::<Vec<ArrayView2<f32>>>()
+            .as_slice(),
+    )
+    .unwrap();
+    let n_rotated = rotated_all.shape()[1] - 1;
+    let rotated = rotated_all.slice(s![.., 0..n_rotated, ..]).to_owned();
+    let shape = Dim([rotated.shape()[0], rotated.shape()[2] * n_rotated]);
+
+    rotated
+        .into_shape(shape)
+        .unwrap()
+        .axis_iter(Axis(0))
+        .map(|point| point.to_owned())
+        .collect()
 }
 
 pub trait Rotator {
@@ -40,15 +119,28 @@ pub trait Rotator {
 
 impl Rotator for Training {
     fn rotate(&mut self, phase_space: ArcArray<f32, Ix3>, data_ref: ArcArray<f32, Ix3>) {
-        let reduced = ArrayBase::zeros(Dim([phase_space.shape()[0], 3, phase_space.shape()[2]]));
-        let reduced_ref = ArrayBase::zeros(Dim([data_ref.shape()[0], 3, data_ref.shape()[2]]));
+        let n_components = self.parameters.n_components;
+        let reduced = ArrayBase::zeros(Dim([
+            phase_space.shape()[0],
+            n_components,
+            phase_space.shape()[2],
+        ]));
+        let reduced_ref = ArrayBase::zeros(Dim([
+            data_ref.shape()[0],
+            n_components,
+            data_ref.shape()[2],
+        ]));
 
         self.rotation.phase_space = Some(phase_space);
         self.rotation.data_ref = Some(data_ref);
         self.rotation.reduced = Some(reduced);
         self.rotation.reduced_ref = Some(reduced_ref);
 
-        self.rotation.pca = PCA::new(self.cluster_nodes.get_own_idx(), 3);
+        self.rotation.pca = PCA::new(self.cluster_nodes.get_own_idx(), n_components);
+        self.parameters.progress.start(
+            Phase::Pca,
+            self.rotation.phase_space.as_ref().unwrap().shape()[2],
+        );
         self.run_pca();
     }
 
@@ -66,126 +158,55 @@ impl Rotator for Training {
     }
 
     fn reduce(&mut self) {
-        let components = self
-            .rotation
-            .pca
-            .components
-            .as_ref()
-            .unwrap()
-            .clone()
-            .reversed_axes();
+        let components = self.rotation.pca.components.as_ref().unwrap().clone();
+        let global_means = self.rotation.pca.global_means.as_ref().unwrap().clone();
         let i = self.rotation.n_reduced - 1;
+
+        let reduced = project(
+            self.rotation
+                .phase_space
+                .as_ref()
+                .unwrap()
+                .slice(s![.., .., i]),
+            &global_means,
+            &components,
+        );
         self.rotation
             .reduced
             .as_mut()
             .unwrap()
             .index_axis_mut(Axis(2), i)
-            .assign({
-                let x = &self
-                    .rotation
-                    .phase_space
-                    .as_ref()
-                    .unwrap()
-                    .slice(s![.., .., i]);
-                let shape = x.shape();
-                let x = x.sub(
-                    &self
-                        .rotation
-                        .pca
-                        .global_means
-                        .as_ref()
-                        .unwrap()
-                        .broadcast([shape[0], shape[1]])
-                        .unwrap(),
-                );
-                &x.dot(&components)
-            });
+            .assign(&reduced);
 
+        let reduced_ref = project(
+            self.rotation
+                .data_ref
+                .as_ref()
+                .unwrap()
+                .slice(s![.., .., i]),
+            &global_means,
+            &components,
+        );
         self.rotation
             .reduced_ref
             .as_mut()
             .unwrap()
             .index_axis_mut(Axis(2), i)
-            .assign({
-                let x = &self
-                    .rotation
-                    .data_ref
-                    .as_ref()
-                    .unwrap()
-                    .slice(s![.., .., i]);
-                let shape = x.shape();
-                let x = x.sub(
-                    &self
-                        .rotation
-                        .pca
-                        .global_means
-                        .as_ref()
-                        .unwrap()
-                        .broadcast([shape[0], shape[1]])
-                        .unwrap(),
-                );
-                &x.dot(&components)
-            });
+            .assign(&reduced_ref);
+
+        self.rotation.components.push(components);
+        self.rotation.means.push(global_means);
+        self.parameters.progress.advance(Phase::Pca, 1);
     }
 
     fn get_rotation_matrix(&mut self) -> Array3<f32> {
-        let curve_vec1 = self
-            .rotation
-            .reduced_ref
-            .as_ref()
-            .unwrap()
-            .slice(s![0, .., ..])
-            .to_owned();
-        let curve_vec2 = arr1(&[0., 0., 1.]);
-
-        let a = curve_vec1.clone()
-            / norm(curve_vec1.view(), Axis(0))
-                .into_shape((1, curve_vec1.shape()[1]))
-                .unwrap();
-        let b = curve_vec2.into_shape((3, 1)).unwrap();
-
-        let v = cross2d(a.view(), b.view(), Axis(0), Axis(0));
-        let c = b.t().dot(&a);
-        let s_ = norm(v.t(), Axis(0)).into_shape((1, v.shape()[0])).unwrap();
-
-        let identity: Array2<f32> = ArrayBase::eye(3);
-        let i = repeat(identity.view(), v.shape()[0])
-            .into_shape((3, 3, v.shape()[0]))
-            .unwrap();
-
-        let zeros: Array1<f32> = ArrayBase::zeros(v.shape()[0]);
-        let v_ = v.t();
-        let v_n = v_.mul(-1.0);
-
-        let k = concatenate(
-            Axis(0),
-            &[
-                zeros.view(),
-                v_n.row(2),
-                v_.row(1),
-                v_.row(2),
-                zeros.view(),
-                v_n.row(0),
-                v_n.row(1),
-                v_.row(0),
-                zeros.view(),
-            ],
+        rotation_onto_last_axis(
+            self.rotation
+                .reduced_ref
+                .as_ref()
+                .unwrap()
+                .slice(s![0, .., ..]),
         )
-        .unwrap()
-        .into_shape((3, 3, v.shape()[0]))
-        .unwrap();
-
-        let k_: Vec<Array2<f32>> = k.axis_iter(Axis(2)).map(|x| x.dot(&x)).collect();
-        i + k
-            + stack(
-                Axis(2),
-                k_.iter()
-                    .map(|x| x.view())
-                    .collect::<Vec<ArrayView2<f32>>>()
-                    .as_slice(),
-            )
-            .unwrap()
-                * ((1.0 - c) / s_.clone().mul(s_))
     }
 
     fn broadcast_rotation_matrix(&mut self, addr: Addr<Self>) {
@@ -203,30 +224,8 @@ impl Rotator for Training {
     }
 
     fn apply_rotation_matrix(&mut self, rotation_matrix: Array3<f32>) {
-        let rotations: Vec<Array2<f32>> = rotation_matrix
-            .axis_iter(Axis(2))
-            .zip(self.rotation.reduced.as_ref().unwrap().axis_iter(Axis(2)))
-            .map(|(a, b)| b.dot(&a.t()))
-            .collect();
-
-        let rotated_3 = stack(
-            Axis(2),
-            rotations
-                .iter()
-                .map(|x| x.view())
-                .collect::<Vec<ArrayView2<f32>>>()
-                .as_slice(),
-        )
-        .unwrap();
-        let rotated = rotated_3.slice(s![.., 0..2, ..]).to_owned();
-        let shape = Dim([rotated.shape()[0], rotated.shape()[2] * 2]);
-
-        let points: Vec<Array1<f32>> = rotated
-            .into_shape(shape)
-            .unwrap()
-            .axis_iter(Axis(0))
-            .map(|point| point.to_owned())
-            .collect();
+        let points = rotate_reduced(&rotation_matrix, self.rotation.reduced.as_ref().unwrap());
+        self.rotation.rotation_matrix = Some(rotation_matrix);
 
         self.num_rotated = Some(points.len());
         let points_per_node = self
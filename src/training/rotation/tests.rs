@@ -1,14 +1,14 @@
 use std::sync::{Arc, Mutex};
 
 use actix::System;
-use ndarray::{arr3, Array3};
-use ndarray_linalg::close_l1;
+use ndarray::{arr1, arr2, arr3, Array2, Array3, Axis};
+use ndarray_linalg::{close_l1, Norm};
 
 use crate::parameters::Parameters;
 use crate::training::Training;
 use crate::SyncInterface;
 
-use crate::training::rotation::Rotator;
+use crate::training::rotation::{rotate_reduced, rotation_onto_last_axis, Rotator};
 
 #[test]
 fn test_rotation_matrix() {
@@ -54,6 +54,27 @@ fn test_rotation_matrix() {
     close_l1(truth.as_ref().unwrap(), &expects, 0.0005)
 }
 
+#[test]
+fn rotates_higher_dimensional_curve_onto_last_axis() {
+    let curve = arr2(&[[3.0, -1.0], [0.5, 2.0], [-2.0, 0.1], [1.0, 4.0]]);
+    let rotation_matrix = rotation_onto_last_axis(curve.view());
+    assert_eq!(rotation_matrix.shape(), &[4, 4, 2]);
+
+    for (rotation, direction) in rotation_matrix
+        .axis_iter(Axis(2))
+        .zip(curve.axis_iter(Axis(1)))
+    {
+        let direction = &direction / direction.norm();
+        close_l1(&rotation.dot(&direction), &arr1(&[0., 0., 0., 1.]), 0.0001);
+        close_l1(&rotation.dot(&rotation.t()), &Array2::eye(4), 0.0001);
+    }
+
+    let reduced = Array3::ones((5, 4, 2));
+    let points = rotate_reduced(&rotation_matrix, &reduced);
+    assert_eq!(points.len(), 5);
+    assert_eq!(points[0].len(), 6);
+}
+
 /*struct TestParams {
     ip: SocketAddr,
     seeds: Vec<SocketAddr>,
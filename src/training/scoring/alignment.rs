@@ -0,0 +1,103 @@
+use ndarray::{concatenate, Array1, ArrayView1, Axis};
+use std::str::FromStr;
+
+/// How the score, which has one value per query, is aligned to the length of the time series.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScoreAlignment {
+    /// One score per query, starting at the first row.
+    None,
+    /// Every score is placed at the center of its query; the borders are `NaN`.
+    Nan,
+    /// Like `Nan`, but the borders repeat the first and last score.
+    Edge,
+    /// Every row gets the mean score of all queries that contain it, as TimeEval's
+    /// `ReverseWindowing` does.
+    MovingAverage,
+}
+
+impl FromStr for ScoreAlignment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(ScoreAlignment::None),
+            "nan" => Ok(ScoreAlignment::Nan),
+            "edge" => Ok(ScoreAlignment::Edge),
+            "moving-average" => Ok(ScoreAlignment::MovingAverage),
+            _ => Err(format!(
+                "{} is not a valid score alignment! Allowed values are: 'none', 'nan', 'edge' and 'moving-average'",
+                s
+            )),
+        }
+    }
+}
+
+/// Stretches the `score` to `n` rows. Each score covers `n - score.len() + 1` rows.
+pub(crate) fn align_score(
+    score: ArrayView1<f32>,
+    n: usize,
+    alignment: &ScoreAlignment,
+) -> Array1<f32> {
+    if score.is_empty() || score.len() >= n {
+        return score.to_owned();
+    }
+    let window = n - score.len() + 1;
+    let front = (window - 1) / 2;
+    let back = n - score.len() - front;
+
+    match alignment {
+        ScoreAlignment::None => score.to_owned(),
+        ScoreAlignment::Nan => pad(score, front, back, f32::NAN, f32::NAN),
+        ScoreAlignment::Edge => pad(score, front, back, score[0], score[score.len() - 1]),
+        ScoreAlignment::MovingAverage => {
+            let mut cumulative = vec![0.0f64];
+            for s in score.iter() {
+                cumulative.push(cumulative.last().unwrap() + *s as f64);
+            }
+            (0..n)
+                .map(|t| {
+                    let from = (t + 1).saturating_sub(window);
+                    let to = (t + 1).min(score.len());
+                    ((cumulative[to] - cumulative[from]) / (to - from) as f64) as f32
+                })
+                .collect()
+        }
+    }
+}
+
+fn pad(score: ArrayView1<f32>, front: usize, back: usize, first: f32, last: f32) -> Array1<f32> {
+    let front = Array1::from_elem(front, first);
+    let back = Array1::from_elem(back, last);
+    concatenate(Axis(0), &[front.view(), score.view(), back.view()]).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::training::scoring::alignment::{align_score, ScoreAlignment};
+    use ndarray::arr1;
+
+    #[test]
+    fn pads_around_the_query_centers() {
+        let score = arr1(&[1., 2., 3.]);
+        let aligned = align_score(score.view(), 6, &ScoreAlignment::Edge);
+        assert_eq!(aligned, arr1(&[1., 1., 2., 3., 3., 3.]));
+
+        let aligned = align_score(score.view(), 6, &ScoreAlignment::Nan);
+        assert_eq!(aligned.len(), 6);
+        assert!(aligned[0].is_nan() && aligned[5].is_nan());
+        assert_eq!(aligned[1], 1.);
+    }
+
+    #[test]
+    fn averages_all_queries_of_a_row() {
+        let score = arr1(&[1., 2., 3.]);
+        let aligned = align_score(score.view(), 5, &ScoreAlignment::MovingAverage);
+        assert_eq!(aligned, arr1(&[1., 1.5, 2., 2.5, 3.]));
+    }
+
+    #[test]
+    fn none_keeps_the_score() {
+        let score = arr1(&[1., 2., 3.]);
+        assert_eq!(align_score(score.view(), 5, &ScoreAlignment::None), score);
+    }
+}
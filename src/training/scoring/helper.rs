@@ -1,8 +1,10 @@
 use crate::data_store::edge::{EdgeRef, MaterializedEdge};
 use crate::data_store::materialize::Materialize;
 use crate::data_store::node::NodeRef;
-use crate::messages::PoisonPill;
+use crate::error::S2GppError;
+use crate::messages::{PoisonPill, TrainingFailed};
 use crate::training::scoring::messages::{ScoringHelperInstruction, ScoringHelperResponse};
+use crate::utils::{Phase, Progress};
 use actix::prelude::*;
 use std::collections::HashMap;
 use std::ops::Range;
@@ -14,25 +16,83 @@ pub(crate) struct ScoringHelper {
     pub node_degrees: HashMap<NodeRef, usize>,
     pub query_length: usize,
     pub receiver: Recipient<ScoringHelperResponse>,
+    pub failure: Recipient<TrainingFailed>,
+    pub progress: Progress,
 }
 
-impl ScoringHelper {
-    fn score_p_degree(&mut self, edge_range: Range<usize>) -> (f32, usize) {
-        let p_edge = self.edges[edge_range].iter();
-        let len_score = p_edge.len();
-        let alpha = 0.00000001 + (len_score as f32);
-        let score: f32 = p_edge
-            .map(|edge| {
-                (self.edge_weight.get(&edge.materialize()).unwrap()
-                    * (self
-                        .node_degrees
-                        .get(&edge.get_from_node())
-                        .expect("Edge with unknown Node found!")
-                        - 1)) as f32
-            })
-            .sum();
-        (score / alpha, len_score)
+/// How `score_p_degree` treats edges and nodes that are unknown to the graph.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum UnknownEdges {
+    /// The query cannot be scored, because the graph was learned from the scored data and must
+    /// know all of its edges.
+    Fail,
+    /// They contribute nothing, as it happens when scoring data the graph was not learned from.
+    Ignore,
+}
+
+/// Scores a query by the weights and degrees of its edges. Returns `None` if an edge or node is
+/// unknown to the graph and `unknown` is `UnknownEdges::Fail`.
+pub(crate) fn score_p_degree(
+    p_edge: &[EdgeRef],
+    edge_weight: &HashMap<MaterializedEdge, usize>,
+    node_degrees: &HashMap<NodeRef, usize>,
+    unknown: UnknownEdges,
+) -> Option<(f32, usize)> {
+    let len_score = p_edge.len();
+    let alpha = 0.00000001 + (len_score as f32);
+    let mut score = 0.0;
+    for edge in p_edge {
+        let weight = edge_weight.get(&edge.materialize()).copied();
+        let degree = node_degrees.get(&edge.get_from_node()).copied();
+        let (weight, degree) = match (weight, degree, unknown) {
+            (Some(weight), Some(degree), _) => (weight, degree),
+            (_, _, UnknownEdges::Ignore) => (weight.unwrap_or(0), degree.unwrap_or(0)),
+            (_, _, UnknownEdges::Fail) => return None,
+        };
+        score += (weight * degree.saturating_sub(1)) as f32;
+    }
+    Some((score / alpha, len_score))
+}
+
+/// Scores the queries starting at the time steps in `range` with `score_query`. Returns the scores
+/// and whether the first queries had no edges and, hence, could not be scored, or `None` if a
+/// query could not be scored at all.
+pub(crate) fn score_queries<F>(
+    edges: &[EdgeRef],
+    edges_in_time: &[usize],
+    query_length: usize,
+    range: Range<usize>,
+    progress: Option<&Progress>,
+    score_query: F,
+) -> Option<(Vec<f32>, bool)>
+where
+    F: Fn(&[EdgeRef]) -> Option<(f32, usize)>,
+{
+    let mut single_scores: Vec<f32> = vec![];
+    let mut first_empty = false;
+
+    for i in range {
+        let from_edge_idx = edges_in_time[i];
+        let to_edge_idx = edges_in_time[i + query_length - 1] + 1;
+
+        let (score, len_score) = score_query(&edges[from_edge_idx..to_edge_idx.min(edges.len())])?;
+
+        single_scores.push(if len_score == 0 {
+            match single_scores.last() {
+                Some(last) => *last,
+                None => {
+                    first_empty = true;
+                    0.0
+                }
+            }
+        } else {
+            -score
+        });
+        if let Some(progress) = progress {
+            progress.advance(Phase::Scoring, 1);
+        }
     }
+    Some((single_scores, first_empty))
 }
 
 impl Actor for ScoringHelper {
@@ -43,28 +103,34 @@ impl Handler<ScoringHelperInstruction> for ScoringHelper {
     type Result = ();
 
     fn handle(&mut self, msg: ScoringHelperInstruction, _ctx: &mut Self::Context) -> Self::Result {
-        let mut single_scores: Vec<f32> = vec![];
-        let mut first_empty = false;
-
-        for i in msg.start..msg.start + msg.length {
-            let from_edge_idx = self.edges_in_time[i];
-            let to_edge_idx = self.edges_in_time[i + self.query_length - 1] + 1;
-
-            let (score, len_score) =
-                self.score_p_degree(from_edge_idx..to_edge_idx.min(self.edges.len()));
-
-            single_scores.push(if len_score == 0 {
-                match single_scores.last() {
-                    Some(last) => *last,
-                    None => {
-                        first_empty = true;
-                        0.0
-                    }
-                }
-            } else {
-                -score
-            });
-        }
+        let scores = score_queries(
+            &self.edges,
+            &self.edges_in_time,
+            self.query_length,
+            msg.start..msg.start + msg.length,
+            Some(&self.progress),
+            |p_edge| {
+                score_p_degree(
+                    p_edge,
+                    &self.edge_weight,
+                    &self.node_degrees,
+                    UnknownEdges::Fail,
+                )
+            },
+        );
+        let (single_scores, first_empty) = match scores {
+            Some(scores) => scores,
+            None => {
+                self.failure.do_send(TrainingFailed {
+                    error: S2GppError::Aborted(
+                        "A query contains an edge or node that is missing in the graph!"
+                            .to_string(),
+                    ),
+                    cluster_node: None,
+                });
+                return;
+            }
+        };
         self.receiver
             .do_send(ScoringHelperResponse {
                 start: msg.start,
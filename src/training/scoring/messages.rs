@@ -2,7 +2,7 @@ use crate::data_store::edge::MaterializedEdge;
 use crate::data_store::node::IndependentNode;
 use actix::prelude::*;
 use actix_telepathy::prelude::*;
-use ndarray::Array1;
+use ndarray::{Array1, Array2};
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 use std::collections::HashMap;
@@ -55,4 +55,5 @@ pub struct SubScores {
     pub cluster_node_id: usize,
     pub scores: Array1<f32>,
     pub first_empty: bool,
+    pub contributions: Option<Array2<f32>>,
 }
@@ -1,48 +1,57 @@
-mod helper;
+pub mod alignment;
+pub mod helper;
 pub mod messages;
+pub mod normalization;
 pub mod overlap;
 #[cfg(test)]
 mod tests;
+pub mod threshold;
 pub mod weights;
 
+use crate::data_manager::data_reader::read_carried_columns;
 use crate::data_store::edge::MaterializedEdge;
 use crate::data_store::node::NodeRef;
+use crate::error::S2GppError;
 use crate::messages::PoisonPill;
-use crate::training::anomaly_contribution::{
-    QueryClusterContribution, QueryClusterContributionResponse, QueryClustercontributionDone,
-};
+use crate::parameters::{Parameters, Role};
+use crate::training::anomaly_contribution::{contribution_path, AnomalyContributor};
+use crate::training::scoring::alignment::align_score;
 use crate::training::scoring::helper::ScoringHelper;
 use crate::training::scoring::messages::{
     EdgeWeights, NodeDegrees, OverlapRotation, ScoringDone, ScoringHelperInstruction,
     ScoringHelperResponse, SubScores,
 };
+use crate::training::scoring::normalization::{normalize, score_quantiles};
+use crate::training::scoring::threshold::write_anomaly_ranges;
 use crate::training::scoring::weights::ScoringWeights;
 use crate::training::Training;
-use crate::utils::float_approx::FloatApprox;
 use crate::utils::itertools::LengthAble;
 use crate::utils::rotation_protocol::RotationProtocol;
-use crate::utils::{ConsoleLogger, HelperProtocol};
+use crate::utils::{HelperProtocol, Phase};
 use actix::{Addr, AsyncContext, Context, Handler, SyncArbiter};
 use anyhow::Result;
 use csv::WriterBuilder;
-use ndarray::{concatenate, stack, Array1, ArrayView1, Axis};
-use ndarray_stats::QuantileExt;
+use ndarray::{concatenate, Array1, Array2, ArrayView1, ArrayView2, Axis};
 use num_traits::Float;
-use num_traits::ToPrimitive;
 use std::collections::HashMap;
 use std::fs::File;
-use std::ops::{Div, Index, IndexMut};
+use std::ops::{Index, IndexMut};
+
+/// The scores of a cluster node, whether its first scores are missing, and its contributions
+type SubScore = (Array1<f32>, bool, Option<Array2<f32>>);
 
 #[derive(Default, Clone)]
 pub(crate) struct Scoring {
     pub score: Option<Array1<f32>>,
+    /// percentiles of the raw training scores
+    pub score_quantiles: Vec<f32>,
     single_scores: Vec<f32>,
-    /// cluster_node_id -> (subscores, first_empty?)
-    subscores: HashMap<usize, (Array1<f32>, bool)>,
+    /// cluster_node_id -> subscores
+    subscores: HashMap<usize, SubScore>,
     first_empty: bool,
     pub node_degrees: HashMap<NodeRef, usize>, // must be sent
-    edge_weight: HashMap<MaterializedEdge, usize>, // must be sent
-    edges_in_time: Vec<usize>,
+    pub edge_weight: HashMap<MaterializedEdge, usize>, // must be sent
+    pub edges_in_time: Vec<usize>,
     node_degrees_rotation_protocol: RotationProtocol<NodeDegrees>,
     edge_weight_rotation_protocol: RotationProtocol<EdgeWeights>,
     overlap_rotation_protocol: RotationProtocol<OverlapRotation>,
@@ -56,11 +65,11 @@ pub(crate) struct Scoring {
 pub(crate) trait Scorer {
     fn init_scoring(&mut self, ctx: &mut Context<Training>);
     fn score(&mut self, ctx: &mut Context<Training>);
-    fn build_anomaly_contribution_score(&self, ctx: &mut Context<Training>);
     fn parallel_score(&mut self, score_length: usize);
     fn finalize_parallel_score(&mut self, ctx: &mut Context<Training>);
-    fn normalize_score(&mut self, score: &mut Array1<f32>);
+    fn normalize_score(&mut self, score: &mut Array1<f32>) -> Result<(), S2GppError>;
     fn finalize_scoring(&mut self, ctx: &mut Context<Training>);
+    fn merge_subscores(&mut self) -> Result<(), S2GppError>;
     fn output_score(&mut self, output_path: String) -> Result<()>;
 }
 
@@ -72,6 +81,9 @@ impl Scorer for Training {
         if self.cluster_nodes.len() > 0 {
             self.start_node_degrees_rotation(ctx);
             self.start_edge_weight_rotation(ctx);
+            if self.parameters.explainability {
+                self.start_node_contribution_rotation(ctx);
+            }
         } else {
             // non-distributed
             self.score(ctx);
@@ -80,7 +92,12 @@ impl Scorer for Training {
 
     fn score(&mut self, ctx: &mut Context<Training>) {
         if self.scoring.edges_in_time.len() < (self.parameters.query_length - 1) {
-            panic!("There are less edges than the given 'query_length'!");
+            let error = S2GppError::TooFewEdges {
+                n_edges: self.scoring.edges_in_time.len(),
+                query_length: self.parameters.query_length,
+            };
+            self.fail(error, ctx);
+            return;
         }
 
         let score_length = self.scoring.edges_in_time.len() - (self.parameters.query_length - 1);
@@ -92,6 +109,9 @@ impl Scorer for Training {
         let node_degrees = self.scoring.node_degrees.clone();
         let query_length = self.parameters.query_length;
         let receiver = ctx.address().recipient();
+        let failure = ctx.address().recipient();
+        let progress = self.parameters.progress.clone();
+        progress.start(Phase::Scoring, score_length);
 
         self.scoring.helpers = Some(SyncArbiter::start(self.parameters.n_threads, move || {
             ScoringHelper {
@@ -101,36 +121,14 @@ impl Scorer for Training {
                 node_degrees: node_degrees.clone(),
                 query_length,
                 receiver: receiver.clone(),
+                failure: failure.clone(),
+                progress: progress.clone(),
             }
         }));
 
         self.parallel_score(score_length);
     }
 
-    fn build_anomaly_contribution_score(&self, ctx: &mut Context<Training>) {
-        ConsoleLogger::new(13, 12, "(+1) Anomaly Contribution Scoring".to_string()).print();
-
-        let edges = self.data_store.get_edges();
-        let anomaly_contribution = self
-            .anomaly_contribution
-            .as_ref()
-            .expect("Should've been set by now");
-        let end = self.scoring.edges_in_time.len() - (self.parameters.query_length - 1);
-        for i in 0..end {
-            let from_edge_idx = self.scoring.edges_in_time[i];
-            let to_edge_idx = self.scoring.edges_in_time[i + self.parameters.query_length - 1] + 1;
-            let nodes: Vec<NodeRef> = edges[from_edge_idx..to_edge_idx.min(edges.len())]
-                .iter()
-                .map(|e| e.get_from_node())
-                .collect();
-            anomaly_contribution.do_send(QueryClusterContribution { nodes });
-        }
-
-        anomaly_contribution.do_send(QueryClustercontributionDone {
-            receiver: ctx.address().recipient(),
-        });
-    }
-
     fn parallel_score(&mut self, score_length: usize) {
         let n_per_thread = score_length / self.parameters.n_threads;
         let n_rest = score_length % self.parameters.n_threads;
@@ -159,6 +157,11 @@ impl Scorer for Training {
         self.scoring.helpers.as_ref().unwrap().do_send(PoisonPill);
         let mut scores: Array1<f32> = self.scoring.single_scores.clone().into_iter().collect();
         self.scoring.single_scores.clear();
+        let contributions = if self.parameters.explainability {
+            Some(self.query_contributions())
+        } else {
+            None
+        };
 
         if self.cluster_nodes.len() > 0 {
             let own_idx = self.cluster_nodes.get_own_idx();
@@ -168,9 +171,14 @@ impl Scorer for Training {
             self.scoring
                 .score_rotation_protocol
                 .resolve_buffer(ctx.address().recipient());
-            self.scoring
-                .subscores
-                .insert(own_idx, (scores.clone(), self.scoring.first_empty));
+            self.scoring.subscores.insert(
+                own_idx,
+                (
+                    scores.clone(),
+                    self.scoring.first_empty,
+                    contributions.clone(),
+                ),
+            );
             self.cluster_nodes
                 .get_next_as("Training")
                 .unwrap()
@@ -178,66 +186,111 @@ impl Scorer for Training {
                     cluster_node_id: own_idx,
                     scores,
                     first_empty: self.scoring.first_empty,
+                    contributions,
                 });
             self.scoring.score_rotation_protocol.sent();
         } else {
-            self.normalize_score(&mut scores);
+            if let Err(error) = self.normalize_score(&mut scores) {
+                self.fail(error, ctx);
+                return;
+            }
             self.scoring.score = Some(scores);
+            if let Some(contributions) = contributions {
+                self.finalize_contribution(contributions);
+            }
             self.finalize_scoring(ctx);
         }
     }
 
-    fn normalize_score(&mut self, scores: &mut Array1<f32>) {
-        let all_score_max = *scores.max().unwrap();
-        let all_score_min = *scores.min().unwrap();
-        *scores = scores
-            .into_iter()
-            .map(|x| (*x - all_score_min) / (all_score_max - all_score_min))
-            .collect();
+    fn normalize_score(&mut self, scores: &mut Array1<f32>) -> Result<(), S2GppError> {
+        self.scoring.score_quantiles = score_quantiles(scores.view());
+        normalize(
+            scores,
+            &self.parameters.score_normalization,
+            &self.scoring.score_quantiles,
+        )
     }
 
     fn finalize_scoring(&mut self, ctx: &mut Context<Training>) {
         if self.scoring.score.is_none() {
-            let mut scores: Vec<Array1<f32>> = vec![];
-            for cluster_node_id in 0..self.parameters.n_cluster_nodes {
-                let (mut sub_score, first_empty) = self
-                    .scoring
-                    .subscores
-                    .remove(&cluster_node_id)
-                    .expect("A subscore is missing!");
-                if first_empty {
-                    let last_score = scores
-                        .last()
-                        .expect("First cannot be empty if it's the first overall score point!");
-
-                    fill_up_first_missing_points(&mut sub_score, last_score[last_score.len() - 1]);
-                }
-                scores.push(sub_score);
+            if let Err(error) = self.merge_subscores() {
+                self.fail(error, ctx);
+                return;
             }
-            let mut cat_scores = concatenate(
-                Axis(0),
-                scores
-                    .iter()
-                    .map(|s| s.view())
-                    .collect::<Vec<ArrayView1<f32>>>()
-                    .as_slice(),
-            )
-            .expect("Could not concatenate subscores!");
-            self.normalize_score(&mut cat_scores);
-            self.scoring.score = Some(cat_scores);
+        }
+
+        let n = self.dataset_stats.as_ref().and_then(|stats| stats.n);
+        if let (Some(score), Some(n)) = (self.scoring.score.as_mut(), n) {
+            *score = align_score(score.view(), n, &self.parameters.score_alignment);
         }
 
         if let Some(output_path) = self.parameters.score_output_path.clone() {
-            self.output_score(output_path).unwrap();
+            if let Err(e) = self.output_score(output_path.clone()) {
+                self.fail(S2GppError::writing(&output_path, e), ctx);
+                return;
+            }
         }
 
-        if self.parameters.explainability {
-            self.build_anomaly_contribution_score(ctx);
+        if self.anomaly_contribution.contribution.is_some() {
+            if let Some(output_path) = contribution_path(&self.parameters) {
+                if let Err(e) = self.output_contribution(output_path.clone()) {
+                    self.fail(S2GppError::writing(&output_path.to_string_lossy(), e), ctx);
+                    return;
+                }
+            }
         }
 
-        if !self.parameters.explainability {
-            ctx.address().do_send(ScoringDone);
+        ctx.address().do_send(ScoringDone);
+    }
+
+    /// Concatenates the sub scores (and contributions) of all cluster nodes in their order.
+    fn merge_subscores(&mut self) -> Result<(), S2GppError> {
+        let mut scores: Vec<Array1<f32>> = vec![];
+        let mut contributions: Vec<Array2<f32>> = vec![];
+        for cluster_node_id in 0..self.parameters.n_cluster_nodes {
+            let (mut sub_score, first_empty, sub_contributions) = self
+                .scoring
+                .subscores
+                .remove(&cluster_node_id)
+                .ok_or_else(|| {
+                    S2GppError::Aborted(format!(
+                        "The sub score of cluster node {} is missing!",
+                        cluster_node_id
+                    ))
+                })?;
+            if first_empty {
+                let last_score = scores
+                    .last()
+                    .and_then(|score| score.last().copied())
+                    .ok_or_else(|| {
+                        S2GppError::Aborted(
+                            "The first sub score cannot start with missing points!".to_string(),
+                        )
+                    })?;
+                fill_up_first_missing_points(&mut sub_score, last_score);
+            }
+            scores.push(sub_score);
+            contributions.extend(sub_contributions);
+        }
+        let mut cat_scores = concatenate(
+            Axis(0),
+            scores
+                .iter()
+                .map(|s| s.view())
+                .collect::<Vec<ArrayView1<f32>>>()
+                .as_slice(),
+        )
+        .map_err(|e| S2GppError::Aborted(format!("Could not concatenate the sub scores: {}", e)))?;
+        self.normalize_score(&mut cat_scores)?;
+        self.scoring.score = Some(cat_scores);
+        if self.parameters.explainability {
+            let views: Vec<ArrayView2<f32>> = contributions.iter().map(|c| c.view()).collect();
+            let contributions = concatenate(Axis(0), &views).map_err(|e| {
+                S2GppError::Aborted(format!("Could not concatenate the contributions: {}", e))
+            })?;
+            self.finalize_contribution(contributions);
         }
+        Ok(())
     }
 
     fn output_score(&mut self, output_path: String) -> Result<()> {
@@ -246,12 +299,8 @@ impl Scorer for Training {
             .score
             .as_ref()
             .expect("Please, calculate score before saving to file!");
-        let file = File::create(output_path)?;
-        let mut writer = WriterBuilder::new().has_headers(false).from_writer(file);
-        for s in score.iter() {
-            writer.serialize(s)?;
-        }
-        Ok(())
+        write_score(score, &output_path, &self.parameters)?;
+        write_anomaly_ranges(score, &output_path, &self.parameters)
     }
 }
 
@@ -293,6 +342,61 @@ impl Handler<ScoringHelperResponse> for Training {
     }
 }
 
+/// Writes one score per line. If a timestamp or label column is configured and the data was read
+/// from a file, these columns are written next to the score of the same row. Without a score
+/// alignment, this is the score of the query starting at that row.
+pub(crate) fn write_score(
+    score: &Array1<f32>,
+    output_path: &str,
+    parameters: &Parameters,
+) -> Result<()> {
+    let file = File::create(output_path)?;
+    let carried_columns = parameters.carried_columns();
+    let data_path = match &parameters.role {
+        Role::Main {
+            data_path: Some(data_path),
+        } if !carried_columns.is_empty() => data_path,
+        _ => {
+            let mut writer = WriterBuilder::new().has_headers(false).from_writer(file);
+            for s in score.iter() {
+                writer.serialize(s)?;
+            }
+            return Ok(());
+        }
+    };
+
+    let mut carried_values = read_carried_columns(data_path, parameters)?.into_iter();
+    let timestamps = parameters
+        .timestamp_column
+        .as_ref()
+        .and_then(|name| Some((name, carried_values.next()?)));
+    let labels = parameters
+        .label_column
+        .as_ref()
+        .and_then(|name| Some((name, carried_values.next()?)));
+
+    let mut writer = WriterBuilder::new()
+        .delimiter(parameters.delimiter)
+        .from_writer(file);
+    let header = timestamps
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .chain(Some("score"))
+        .chain(labels.iter().map(|(name, _)| name.as_str()));
+    writer.write_record(header)?;
+
+    let value = |values: &Vec<String>, i: usize| values.get(i).cloned().unwrap_or_default();
+    for (i, s) in score.iter().enumerate() {
+        let record = timestamps
+            .iter()
+            .map(|(_, values)| value(values, i))
+            .chain(Some(s.to_string()))
+            .chain(labels.iter().map(|(_, values)| value(values, i)));
+        writer.write_record(record)?;
+    }
+    Ok(())
+}
+
 fn fill_up_first_missing_points<T: IndexMut<usize, Output = f32> + LengthAble>(
     scores: &mut T,
     initial_score: f32,
@@ -318,9 +422,14 @@ impl Handler<SubScores> for Training {
             return;
         }
 
-        self.scoring
-            .subscores
-            .insert(msg.cluster_node_id, (msg.scores.clone(), msg.first_empty));
+        self.scoring.subscores.insert(
+            msg.cluster_node_id,
+            (
+                msg.scores.clone(),
+                msg.first_empty,
+                msg.contributions.clone(),
+            ),
+        );
 
         if self.scoring.score_rotation_protocol.is_running() {
             self.cluster_nodes
@@ -333,55 +442,3 @@ impl Handler<SubScores> for Training {
         }
     }
 }
-
-impl Handler<QueryClusterContributionResponse> for Training {
-    type Result = ();
-
-    fn handle(
-        &mut self,
-        msg: QueryClusterContributionResponse,
-        ctx: &mut Self::Context,
-    ) -> Self::Result {
-        let file = File::create(&self.parameters.anomaly_contribution_output_path).unwrap();
-        let mut writer = WriterBuilder::new().has_headers(false).from_writer(file);
-        let mut last = None;
-        let mut contributions: Vec<ArrayView1<f32>> =
-            msg.contributions.iter().map(|x| x.view()).collect();
-        let mut last_contribution = None;
-        for c in contributions.iter_mut() {
-            if c.sum() == 0.0 {
-                *c = last_contribution.expect("No contribution");
-            } else {
-                last_contribution = Some(*c);
-            }
-        }
-        let mut contributions_matrix = stack(Axis(0), contributions.as_slice()).unwrap();
-
-        for mut dim in contributions_matrix.axis_iter_mut(Axis(1)) {
-            let mut sorted: Vec<FloatApprox<f32>> = dim
-                .iter()
-                .map(|x| FloatApprox(*x))
-                .collect::<Vec<FloatApprox<f32>>>();
-            sorted.sort();
-            let median = sorted
-                .get(sorted.len().to_f32().unwrap().div(2.0).to_usize().unwrap())
-                .unwrap()
-                .0;
-            dim.mapv_inplace(|x| x - median);
-        }
-
-        for s in contributions_matrix.axis_iter(Axis(0)) {
-            let ser = if s.sum() == 0. {
-                match last {
-                    Some(last) => last,
-                    None => s,
-                }
-            } else {
-                s
-            };
-            last = Some(ser);
-            writer.serialize(ser.to_vec()).unwrap();
-        }
-        ctx.address().do_send(ScoringDone);
-    }
-}
@@ -0,0 +1,160 @@
+use crate::error::S2GppError;
+use log::*;
+use ndarray::{Array1, ArrayView1};
+use ndarray_stats::QuantileExt;
+use std::str::FromStr;
+
+const N_QUANTILES: usize = 101;
+
+/// How the raw query scores are scaled.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScoreNormalization {
+    /// The raw scores, comparable across runs on the same graph.
+    Raw,
+    /// Scales the scores to [0, 1].
+    MinMax,
+    /// Subtracts the mean and divides by the standard deviation.
+    ZScore,
+    /// Subtracts the median and divides by the interquartile range.
+    Robust,
+    /// The share of training queries of a saved model with a lower score.
+    Model,
+}
+
+impl FromStr for ScoreNormalization {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "raw" | "none" => Ok(ScoreNormalization::Raw),
+            "min-max" => Ok(ScoreNormalization::MinMax),
+            "z-score" => Ok(ScoreNormalization::ZScore),
+            "robust" => Ok(ScoreNormalization::Robust),
+            "model" => Ok(ScoreNormalization::Model),
+            _ => Err(format!(
+                "{} is not a valid score normalization! Allowed values are: 'raw', 'min-max', 'z-score', 'robust' and 'model'",
+                s
+            )),
+        }
+    }
+}
+
+/// Normalizes the raw `scores` in place. `Model` needs the `score_quantiles` of the training
+/// scores. If the scale of the scores is zero, e.g. because all scores are equal, they are only
+/// shifted.
+pub(crate) fn normalize(
+    scores: &mut Array1<f32>,
+    normalization: &ScoreNormalization,
+    score_quantiles: &[f32],
+) -> Result<(), S2GppError> {
+    if scores.is_empty() {
+        return Ok(());
+    }
+    let (center, scale) = match normalization {
+        ScoreNormalization::Raw => return Ok(()),
+        ScoreNormalization::MinMax => {
+            let min = *scores.min().unwrap();
+            (min, *scores.max().unwrap() - min)
+        }
+        ScoreNormalization::ZScore => (scores.mean().unwrap(), scores.std(0.)),
+        ScoreNormalization::Robust => {
+            let quantiles = quantiles(scores.view(), &[0.25, 0.5, 0.75]);
+            (quantiles[1], quantiles[2] - quantiles[0])
+        }
+        ScoreNormalization::Model => {
+            if score_quantiles.is_empty() {
+                return Err(S2GppError::InvalidParameters(
+                    "The 'model' score normalization needs a model that was trained with score quantiles.".to_string(),
+                ));
+            }
+            scores.mapv_inplace(|s| percentile_rank(s, score_quantiles));
+            return Ok(());
+        }
+    };
+
+    if scale <= f32::EPSILON {
+        warn!("The scores have no spread, so they are only shifted");
+        scores.mapv_inplace(|s| s - center);
+    } else {
+        scores.mapv_inplace(|s| (s - center) / scale);
+    }
+    Ok(())
+}
+
+/// The percentiles 0, 1, ..., 100 of the raw `scores`.
+pub(crate) fn score_quantiles(scores: ArrayView1<f32>) -> Vec<f32> {
+    if scores.is_empty() {
+        return vec![];
+    }
+    let qs: Vec<f32> = (0..N_QUANTILES)
+        .map(|q| q as f32 / (N_QUANTILES - 1) as f32)
+        .collect();
+    quantiles(scores, &qs)
+}
+
+/// Linearly interpolated quantiles of `values`.
+pub(super) fn quantiles(values: ArrayView1<f32>, qs: &[f32]) -> Vec<f32> {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let last = (sorted.len() - 1) as f32;
+    qs.iter()
+        .map(|q| {
+            let position = q * last;
+            let (lower, upper) = (position.floor() as usize, position.ceil() as usize);
+            sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f32)
+        })
+        .collect()
+}
+
+/// The interpolated position of `score` in the evenly spaced `quantiles`, between 0 and 1.
+fn percentile_rank(score: f32, quantiles: &[f32]) -> f32 {
+    let last = quantiles.len() - 1;
+    if score <= quantiles[0] {
+        return 0.0;
+    }
+    if score >= quantiles[last] {
+        return 1.0;
+    }
+    let upper = quantiles.iter().position(|q| *q > score).unwrap();
+    let (low, high) = (quantiles[upper - 1], quantiles[upper]);
+    ((upper - 1) as f32 + (score - low) / (high - low)) / last as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::training::scoring::normalization::{normalize, score_quantiles, ScoreNormalization};
+    use ndarray::{arr1, Array1};
+
+    #[test]
+    fn constant_scores_are_only_shifted() {
+        for normalization in [
+            ScoreNormalization::MinMax,
+            ScoreNormalization::ZScore,
+            ScoreNormalization::Robust,
+        ]
+        .iter()
+        {
+            let mut scores = arr1(&[2., 2., 2.]);
+            normalize(&mut scores, normalization, &[]).unwrap();
+            assert_eq!(scores, arr1(&[0., 0., 0.]));
+        }
+    }
+
+    #[test]
+    fn robust_uses_median_and_iqr() {
+        let mut scores = arr1(&[1., 2., 3., 4., 5.]);
+        normalize(&mut scores, &ScoreNormalization::Robust, &[]).unwrap();
+        assert_eq!(scores, arr1(&[-1., -0.5, 0., 0.5, 1.]));
+    }
+
+    #[test]
+    fn model_ranks_against_training_scores() {
+        let quantiles = score_quantiles(Array1::range(0., 101., 1.).view());
+        let mut scores = arr1(&[-1., 25., 50.5, 200.]);
+        normalize(&mut scores, &ScoreNormalization::Model, &quantiles).unwrap();
+        let expected = arr1(&[0., 0.25, 0.505, 1.]);
+        assert!((&scores - &expected).iter().all(|d| d.abs() < 1e-5));
+
+        assert!(normalize(&mut scores, &ScoreNormalization::Model, &[]).is_err());
+    }
+}
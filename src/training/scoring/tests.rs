@@ -1,12 +1,15 @@
-use crate::data_store::edge::Edge;
+use crate::data_store::edge::{Edge, EdgeRef};
 use crate::data_store::node::IndependentNode;
-use crate::parameters::Parameters;
-use crate::training::scoring::weights::ScoringWeights;
-use crate::training::scoring::Scorer;
+use crate::parameters::{Parameters, Role};
+use crate::training::scoring::helper::{score_p_degree, UnknownEdges};
+use crate::training::scoring::weights::{add_edges_to_weights, ScoringWeights};
+use crate::training::scoring::{write_score, Scorer};
 use crate::training::Training;
 use crate::SyncInterface;
 use ndarray::arr1;
+use std::collections::HashMap;
 use std::fs::remove_file;
+use std::ops::Deref;
 use std::path::Path;
 
 #[test]
@@ -21,6 +24,32 @@ fn scores_are_written_to_file() {
     remove_file(path).expect("Could not delete test file!");
 }
 
+#[test]
+fn scores_are_written_with_timestamp_and_label() {
+    let data_path = "data/_test_scores_input.csv";
+    std::fs::write(
+        data_path,
+        "timestamp,value,is_anomaly\na,0.5,0\nb,0.7,1\nc,0.9,0\n",
+    )
+    .unwrap();
+    let parameters = Parameters {
+        role: Role::Main {
+            data_path: Some(data_path.to_string()),
+        },
+        timestamp_column: Some("timestamp".to_string()),
+        label_column: Some("is_anomaly".to_string()),
+        ..Default::default()
+    };
+
+    let scores_path = "data/_test_scores_carried.csv";
+    write_score(&arr1(&[0.25, 1.0]), scores_path, &parameters).unwrap();
+    let written = std::fs::read_to_string(scores_path).unwrap();
+    remove_file(data_path).expect("Could not delete test file!");
+    remove_file(scores_path).expect("Could not delete test file!");
+
+    assert_eq!(written, "timestamp,score,is_anomaly\na,0.25,0\nb,1,1\n");
+}
+
 #[test]
 fn node_degrees_correctly_calculated() {
     let mut training = Training::init(Parameters::default());
@@ -58,3 +87,84 @@ fn node_degrees_correctly_calculated() {
 
     assert_eq!(real_degrees, expected_node_degrees);
 }
+
+fn test_edges() -> Vec<Edge> {
+    vec![
+        Edge::new(
+            IndependentNode::new(0, 0, 0).into_ref(),
+            IndependentNode::new(0, 1, 1).into_ref(),
+        ),
+        Edge::new(
+            IndependentNode::new(0, 1, 1).into_ref(),
+            IndependentNode::new(1, 1, 2).into_ref(),
+        ),
+        Edge::new(
+            IndependentNode::new(0, 0, 2).into_ref(),
+            IndependentNode::new(1, 1, 3).into_ref(),
+        ),
+        Edge::new(
+            IndependentNode::new(0, 0, 3).into_ref(),
+            IndependentNode::new(0, 1, 4).into_ref(),
+        ),
+    ]
+}
+
+#[test]
+fn weights_are_updated_incrementally() {
+    let mut training = Training::init(Parameters::default());
+    training.data_store.add_edges(test_edges());
+    let edge_weight = training.calculate_edge_weight();
+    let node_degrees: HashMap<IndependentNode, usize> = training
+        .calculate_node_degrees()
+        .into_iter()
+        .map(|(node, degree)| (node.deref().clone(), degree))
+        .collect();
+
+    let edges: Vec<EdgeRef> = test_edges()
+        .into_iter()
+        .map(|edge| edge.into_ref())
+        .collect();
+    let mut incremental_edge_weight = HashMap::new();
+    let mut incremental_node_degrees = HashMap::new();
+    for part in edges.chunks(3) {
+        add_edges_to_weights(
+            part,
+            &mut incremental_edge_weight,
+            &mut incremental_node_degrees,
+        );
+    }
+
+    assert_eq!(incremental_edge_weight, edge_weight);
+    assert_eq!(incremental_node_degrees, node_degrees);
+}
+
+#[test]
+fn unknown_edges_are_only_ignored_on_request() {
+    let mut training = Training::init(Parameters::default());
+    training.data_store.add_edges(test_edges());
+    let edge_weight = training.calculate_edge_weight();
+    let node_degrees = training.calculate_node_degrees();
+
+    let mut edges: Vec<EdgeRef> = test_edges()
+        .into_iter()
+        .map(|edge| edge.into_ref())
+        .collect();
+    let score =
+        |edges: &[EdgeRef], unknown| score_p_degree(edges, &edge_weight, &node_degrees, unknown);
+    assert!(score(&edges, UnknownEdges::Fail).is_some());
+
+    edges.push(
+        Edge::new(
+            IndependentNode::new(2, 0, 5).into_ref(),
+            IndependentNode::new(3, 0, 6).into_ref(),
+        )
+        .into_ref(),
+    );
+    assert!(score(&edges, UnknownEdges::Fail).is_none());
+
+    // the unknown edge adds nothing to the score, but still counts towards the query length
+    let (known_score, _) = score(&edges[..4], UnknownEdges::Fail).unwrap();
+    let (ignored_score, len_score) = score(&edges, UnknownEdges::Ignore).unwrap();
+    assert_eq!(len_score, 5);
+    assert!((ignored_score * 5.0 - known_score * 4.0).abs() < 1e-4);
+}
@@ -0,0 +1,286 @@
+use crate::parameters::Parameters;
+use crate::training::scoring::normalization::quantiles;
+use crate::utils::json;
+use anyhow::Result;
+use csv::WriterBuilder;
+use log::*;
+use ndarray::{Array1, ArrayView1};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// The quantile above which the tail of the scores is modelled by `Thresholding::Pot`.
+const POT_INITIAL_QUANTILE: f32 = 0.98;
+
+/// How the threshold that separates anomalous from normal scores is chosen.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Thresholding {
+    /// The given score.
+    Fixed(f32),
+    /// Marks the given percentage of scores with the highest values.
+    TopK(f32),
+    /// The mean plus the given multiple of the standard deviation of the scores.
+    Std(f32),
+    /// Peaks over threshold: the score that is exceeded with the given risk according to a
+    /// generalized Pareto distribution fitted to the highest scores.
+    Pot(f32),
+}
+
+impl Thresholding {
+    /// The threshold for `score`. `NaN` scores, e.g. from the score alignment, are ignored.
+    pub fn threshold(&self, score: ArrayView1<f32>) -> f32 {
+        let values: Array1<f32> = score.iter().copied().filter(|s| !s.is_nan()).collect();
+        if values.is_empty() {
+            return f32::INFINITY;
+        }
+        match self {
+            Thresholding::Fixed(threshold) => *threshold,
+            Thresholding::TopK(percent) => quantiles(values.view(), &[1.0 - percent / 100.0])[0],
+            Thresholding::Std(k) => values.mean().unwrap() + k * values.std(0.),
+            Thresholding::Pot(risk) => pot_threshold(values.view(), *risk),
+        }
+    }
+}
+
+impl FromStr for Thresholding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || {
+            format!(
+                "{} is not a valid thresholding! Allowed values are: 'fixed:<score>', 'top-k:<percent>', 'std:<k>' and 'pot:<risk>'",
+                s
+            )
+        };
+        let (method, value) = s.split_once(':').ok_or_else(error)?;
+        let value: f32 = value.parse().map_err(|_| error())?;
+        if !value.is_finite() {
+            return Err(error());
+        }
+        match method {
+            "fixed" => Ok(Thresholding::Fixed(value)),
+            "top-k" if value > 0.0 && value <= 100.0 => Ok(Thresholding::TopK(value)),
+            "std" => Ok(Thresholding::Std(value)),
+            "pot" if value > 0.0 && value < 1.0 => Ok(Thresholding::Pot(value)),
+            _ => Err(error()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum RangesFormat {
+    Csv,
+    Json,
+}
+
+impl RangesFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            RangesFormat::Csv => "csv",
+            RangesFormat::Json => "json",
+        }
+    }
+}
+
+impl FromStr for RangesFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(RangesFormat::Csv),
+            "json" => Ok(RangesFormat::Json),
+            _ => Err(format!(
+                "{} is not a valid ranges format! Allowed values are: 'csv' and 'json'",
+                s
+            )),
+        }
+    }
+}
+
+/// Consecutive scores above the threshold. `end` is exclusive.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct AnomalyRange {
+    pub start: usize,
+    pub end: usize,
+    /// index of the highest score in the range
+    pub peak: usize,
+    pub peak_score: f32,
+}
+
+/// All runs of consecutive scores above `threshold`.
+pub fn anomaly_ranges(score: ArrayView1<f32>, threshold: f32) -> Vec<AnomalyRange> {
+    let mut ranges: Vec<AnomalyRange> = vec![];
+    let mut open = false;
+    for (i, s) in score.iter().enumerate() {
+        if *s > threshold {
+            match ranges.last_mut() {
+                Some(range) if open => {
+                    range.end = i + 1;
+                    if *s > range.peak_score {
+                        range.peak = i;
+                        range.peak_score = *s;
+                    }
+                }
+                _ => ranges.push(AnomalyRange {
+                    start: i,
+                    end: i + 1,
+                    peak: i,
+                    peak_score: *s,
+                }),
+            }
+            open = true;
+        } else {
+            open = false;
+        }
+    }
+    ranges
+}
+
+/// If a thresholding is configured, writes the anomaly ranges of `score` next to the score at
+/// `output_path`, e.g. `scores.ranges.csv` for `scores.csv`.
+pub(crate) fn write_anomaly_ranges(
+    score: &Array1<f32>,
+    output_path: &str,
+    parameters: &Parameters,
+) -> Result<()> {
+    let thresholding = match &parameters.thresholding {
+        Some(thresholding) => thresholding,
+        None => return Ok(()),
+    };
+    let threshold = thresholding.threshold(score.view());
+    let ranges = anomaly_ranges(score.view(), threshold);
+    info!(
+        "The threshold {} marks {} anomaly ranges",
+        threshold,
+        ranges.len()
+    );
+
+    let file = File::create(ranges_path(output_path, &parameters.ranges_format))?;
+    match parameters.ranges_format {
+        RangesFormat::Csv => {
+            let mut writer = WriterBuilder::new().from_writer(file);
+            for range in ranges.iter() {
+                writer.serialize(range)?;
+            }
+            writer.flush()?;
+        }
+        RangesFormat::Json => {
+            let mut writer = BufWriter::new(file);
+            let ranges = ranges.iter().map(|range| {
+                json::object([
+                    ("start", range.start.to_string()),
+                    ("end", range.end.to_string()),
+                    ("peak", range.peak.to_string()),
+                    ("peak_score", json::number(range.peak_score)),
+                ])
+            });
+            writeln!(writer, "{{")?;
+            writeln!(writer, r#"  "threshold": {},"#, json::number(threshold))?;
+            writeln!(writer, r#"  "ranges": {}"#, json::array_lines(ranges, "  "))?;
+            writeln!(writer, "}}")?;
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+fn ranges_path(output_path: &str, format: &RangesFormat) -> PathBuf {
+    Path::new(output_path).with_extension(format!("ranges.{}", format.extension()))
+}
+
+/// Fits a generalized Pareto distribution to the excesses over an initial high quantile with the
+/// method of moments and returns the score that is exceeded with probability `risk`
+/// (Siffer et al., "Anomaly Detection in Streams with Extreme Value Theory").
+fn pot_threshold(values: ArrayView1<f32>, risk: f32) -> f32 {
+    let initial = quantiles(values, &[POT_INITIAL_QUANTILE])[0];
+    let excesses: Array1<f32> = values
+        .iter()
+        .filter(|v| **v > initial)
+        .map(|v| v - initial)
+        .collect();
+    if excesses.len() < 2 {
+        return initial;
+    }
+    let (mean, variance) = (excesses.mean().unwrap(), excesses.var(0.));
+    if variance <= f32::EPSILON {
+        return initial;
+    }
+
+    let shape = 0.5 * (1.0 - mean.powi(2) / variance);
+    let scale = 0.5 * mean * (1.0 + mean.powi(2) / variance);
+    let ratio = risk * values.len() as f32 / excesses.len() as f32;
+    if shape.abs() <= f32::EPSILON {
+        initial - scale * ratio.ln()
+    } else {
+        initial + scale / shape * (ratio.powf(-shape) - 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::training::scoring::threshold::{anomaly_ranges, ranges_path, AnomalyRange};
+    use crate::training::scoring::threshold::{RangesFormat, Thresholding};
+    use ndarray::{arr1, Array1};
+    use std::path::PathBuf;
+    use std::str::FromStr;
+
+    #[test]
+    fn extracts_consecutive_ranges() {
+        let score = arr1(&[0.1, 0.9, 0.7, 0.2, f32::NAN, 0.8]);
+        let ranges = anomaly_ranges(score.view(), 0.5);
+        assert_eq!(
+            ranges,
+            vec![
+                AnomalyRange {
+                    start: 1,
+                    end: 3,
+                    peak: 1,
+                    peak_score: 0.9
+                },
+                AnomalyRange {
+                    start: 5,
+                    end: 6,
+                    peak: 5,
+                    peak_score: 0.8
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn chooses_thresholds() {
+        let score = Array1::range(0., 100., 1.);
+        assert_eq!(Thresholding::Fixed(0.5).threshold(score.view()), 0.5);
+        let top_k = Thresholding::TopK(5.).threshold(score.view());
+        assert_eq!(anomaly_ranges(score.view(), top_k)[0].start, 95);
+        let std = Thresholding::Std(1.).threshold(score.view());
+        assert!(std > 49.5 && std < 100.);
+        let pot = Thresholding::Pot(0.001).threshold(score.view());
+        assert!(pot > 97.);
+    }
+
+    #[test]
+    fn parses_thresholdings() {
+        assert_eq!(
+            Thresholding::from_str("top-k:1.5"),
+            Ok(Thresholding::TopK(1.5))
+        );
+        assert_eq!(
+            Thresholding::from_str("pot:1e-4"),
+            Ok(Thresholding::Pot(1e-4))
+        );
+        assert!(Thresholding::from_str("pot:2").is_err());
+        assert!(Thresholding::from_str("fixed").is_err());
+        assert!(Thresholding::from_str("median:1").is_err());
+    }
+
+    #[test]
+    fn ranges_are_written_next_to_the_score() {
+        assert_eq!(
+            ranges_path("out/scores.csv", &RangesFormat::Json),
+            PathBuf::from("out/scores.ranges.json")
+        );
+    }
+}
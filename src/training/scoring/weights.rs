@@ -1,4 +1,4 @@
-use crate::data_store::edge::{Edge, MaterializedEdge};
+use crate::data_store::edge::{Edge, EdgeRef, MaterializedEdge};
 use crate::data_store::materialize::Materialize;
 use crate::data_store::node::{IndependentNode, NodeRef};
 use actix::prelude::*;
@@ -9,6 +9,68 @@ use crate::training::scoring::messages::{EdgeWeights, NodeDegrees, ScoreInitDone
 use crate::training::scoring::overlap::ScoringOverlap;
 use crate::Training;
 
+/// For each point, the index of the first edge (sorted by `to_id`) that leads beyond it.
+pub(crate) fn edges_in_time(
+    edges: &[EdgeRef],
+    start_point: usize,
+    result_length: usize,
+) -> Vec<usize> {
+    let pseudo_edge = Edge::new(
+        IndependentNode::new(0, 0, 0).into_ref(),
+        IndependentNode::new(0, 0, 0).into_ref(),
+    )
+    .into_ref();
+    let mut edges_in_time = vec![];
+    let mut last_point_id = None;
+    let mut last_len: usize = 0;
+    for (i, edge) in edges.iter().chain(&[pseudo_edge]).enumerate() {
+        match last_point_id {
+            None => {
+                last_point_id = Some(edge.get_to_id());
+            }
+            Some(last_point_id_ref) => {
+                if edge.get_to_id().ne(&last_point_id_ref) {
+                    while edges_in_time.len().lt(&last_point_id_ref.sub(&start_point)) {
+                        edges_in_time.push(last_len);
+                    }
+                    last_point_id = Some(edge.get_to_id());
+                    last_len = i;
+                    edges_in_time.push(i);
+                }
+            }
+        }
+    }
+
+    while edges_in_time.len().lt(&result_length) {
+        edges_in_time.push(last_len);
+    }
+
+    edges_in_time
+}
+
+/// Adds `edges` to the weights and node degrees of an existing graph. Like in
+/// `calculate_node_degrees`, only edges that are new to the graph increase the node degrees.
+pub(crate) fn add_edges_to_weights(
+    edges: &[EdgeRef],
+    edge_weight: &mut HashMap<MaterializedEdge, usize>,
+    node_degrees: &mut HashMap<IndependentNode, usize>,
+) {
+    for edge in edges {
+        let materialized = edge.materialize();
+        match edge_weight.get_mut(&materialized) {
+            Some(weight) => {
+                *weight += 1;
+            }
+            None => {
+                for node in [materialized.get_from_node(), materialized.get_to_node()] {
+                    *node_degrees.entry(node).or_insert(0) += 1;
+                }
+                edge_weight.insert(materialized, 1);
+            }
+        }
+    }
+}
+
 pub(crate) trait ScoringWeights {
     fn count_edges_in_time(&mut self) -> Vec<usize>;
     fn calculate_edge_weight(&mut self) -> HashMap<MaterializedEdge, usize>;
@@ -21,38 +83,6 @@ pub(crate) trait ScoringWeights {
 impl ScoringWeights for Training {
     fn count_edges_in_time(&mut self) -> Vec<usize> {
         let start_point = self.transposition.range_start_point.unwrap_or(0);
-        let pseudo_edge = Edge::new(
-            IndependentNode::new(0, 0, 0).into_ref(),
-            IndependentNode::new(0, 0, 0).into_ref(),
-        )
-        .into_ref();
-        let mut edges_in_time = vec![];
-        let mut last_point_id = None;
-        let mut last_len: usize = 0;
-        for (i, edge) in self
-            .data_store
-            .get_edges()
-            .iter()
-            .chain(&[pseudo_edge])
-            .enumerate()
-        {
-            match last_point_id {
-                None => {
-                    last_point_id = Some(edge.get_to_id());
-                }
-                Some(last_point_id_ref) => {
-                    if edge.get_to_id().ne(&last_point_id_ref) {
-                        while edges_in_time.len().lt(&last_point_id_ref.sub(&start_point)) {
-                            edges_in_time.push(last_len);
-                        }
-                        last_point_id = Some(edge.get_to_id());
-                        last_len = i;
-                        edges_in_time.push(i);
-                    }
-                }
-            }
-        }
-
         let result_length = self.num_rotated.expect("should have been already set")
             - if self
                 .cluster_nodes
@@ -63,11 +93,8 @@ impl ScoringWeights for Training {
             } else {
                 0
             };
-        while edges_in_time.len().lt(&result_length) {
-            edges_in_time.push(last_len);
-        }
 
-        edges_in_time
+        edges_in_time(&self.data_store.get_edges(), start_point, result_length)
     }
 
     fn calculate_edge_weight(&mut self) -> HashMap<MaterializedEdge, usize> {
@@ -154,7 +181,8 @@ impl ScoringWeights for Training {
 
     fn init_done(&mut self) -> bool {
         !(self.scoring.node_degrees_rotation_protocol.is_running()
-            || self.scoring.edge_weight_rotation_protocol.is_running())
+            || self.scoring.edge_weight_rotation_protocol.is_running()
+            || self.anomaly_contribution.is_rotating())
     }
 }
 
@@ -35,6 +35,8 @@ pub(crate) struct Segmentation {
     pub transition_count_protocol: RotationProtocol<TransitionCountMessage>,
     pub global_transition_count: usize,
     pub invalid_transition_directions: usize,
+    /// whether the points have been mirrored by the self-correction
+    pub mirrored: bool,
 }
 
 pub(crate) trait Segmenter {
@@ -338,6 +340,7 @@ impl Segmenter for Training {
             );
             self.clear_segmentation();
             self.data_store.mirror_points(self.parameters.rate);
+            self.segmentation.mirrored = true;
             let node_transitions = self.build_segments();
             self.distribute_or_wait_for_segments(node_transitions, ctx);
         } else {
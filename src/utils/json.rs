@@ -0,0 +1,100 @@
+use itertools::Itertools;
+use num_traits::Float;
+use std::fmt::Display;
+
+/// A JSON string literal of `s`.
+pub(crate) fn string(s: &str) -> String {
+    let mut literal = String::with_capacity(s.len() + 2);
+    literal.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            c if c.is_control() => literal.push_str(&format!("\\u{:04x}", c as u32)),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// A JSON number literal of `x`. JSON has no literals for NaN and infinity, so they become `null`.
+pub(crate) fn number<F: Float + Display>(x: F) -> String {
+    if x.is_finite() {
+        x.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+/// `value` as it is displayed, or `null` if there is none.
+pub(crate) fn optional<T: Display>(value: Option<T>) -> String {
+    value.map_or_else(|| "null".to_string(), |value| value.to_string())
+}
+
+/// A JSON array of the already formatted `values`.
+pub(crate) fn array<I: IntoIterator<Item = String>>(values: I) -> String {
+    format!("[{}]", values.into_iter().join(", "))
+}
+
+/// Like `array`, but with one value per line, indented one level deeper than `indent`.
+pub(crate) fn array_lines<I: IntoIterator<Item = String>>(values: I, indent: &str) -> String {
+    let values = values
+        .into_iter()
+        .map(|value| format!("{}  {}", indent, value))
+        .join(",\n");
+    if values.is_empty() {
+        "[]".to_string()
+    } else {
+        format!("[\n{}\n{}]", values, indent)
+    }
+}
+
+/// A JSON object of the already formatted values of `fields`.
+pub(crate) fn object<'a, I: IntoIterator<Item = (&'a str, String)>>(fields: I) -> String {
+    format!(
+        "{{{}}}",
+        fields
+            .into_iter()
+            .map(|(key, value)| format!("{}: {}", string(key), value))
+            .join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::json::{array, array_lines, number, object, optional, string};
+
+    #[test]
+    fn escapes_strings() {
+        assert_eq!(string("3_0"), r#""3_0""#);
+        assert_eq!(string("a\"b\\c\nd\u{1}"), r#""a\"b\\c\nd\u0001""#);
+    }
+
+    #[test]
+    fn writes_non_finite_numbers_as_null() {
+        assert_eq!(number(0.1_f32), "0.1");
+        assert_eq!(number(2.0_f32), "2");
+        assert_eq!(number(f32::NAN), "null");
+        assert_eq!(number(f64::INFINITY), "null");
+        assert_eq!(optional(Some(3)), "3");
+        assert_eq!(optional::<u64>(None), "null");
+    }
+
+    #[test]
+    fn writes_arrays_and_objects() {
+        assert_eq!(array(vec![number(1.0_f32), number(2.5)]), "[1, 2.5]");
+        assert_eq!(
+            object(vec![("weight", 2.to_string()), ("id", string("3_1"))]),
+            r#"{"weight": 2, "id": "3_1"}"#
+        );
+        assert_eq!(array_lines(vec![], "  "), "[]");
+        assert_eq!(
+            array_lines(vec![number(1.0_f32), number(2.5)], "  "),
+            "[\n    1,\n    2.5\n  ]"
+        );
+    }
+}
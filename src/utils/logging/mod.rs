@@ -1 +1,2 @@
 pub mod console_logger;
+pub mod progress;
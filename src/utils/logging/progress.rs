@@ -0,0 +1,213 @@
+use crate::utils::ConsoleLogger;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::fmt::{Debug, Display, Formatter};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+/// A phase of S2G++ whose progress is counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// rows of the input file
+    ReadingData,
+    /// input dimensions reduced by the PCA
+    Pca,
+    /// intersections of the transitions with the segments
+    Intersections,
+    /// segments clustered into nodes
+    NodeEstimation,
+    /// queries scored
+    Scoring,
+}
+
+impl Display for Phase {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Phase::ReadingData => "reading-data",
+            Phase::Pca => "pca",
+            Phase::Intersections => "intersections",
+            Phase::NodeEstimation => "node-estimation",
+            Phase::Scoring => "scoring",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressEvent {
+    /// the pipeline started its `step`-th of `n_steps` steps
+    Step {
+        step: usize,
+        n_steps: usize,
+        title: String,
+    },
+    /// `done` of `total` units of `phase` are processed on this cluster node
+    Advanced {
+        phase: Phase,
+        done: usize,
+        total: usize,
+    },
+}
+
+type ProgressCallback = Arc<dyn Fn(&ProgressEvent) + Send + Sync>;
+
+#[derive(Default)]
+struct ProgressState {
+    phase: Option<Phase>,
+    done: usize,
+    total: usize,
+    reported: usize,
+    bar: Option<ProgressBar>,
+}
+
+/// Reports how far a run has got, as progress bars on the console and/or as events to a callback.
+/// Clones share their state, so all actors of a process advance the same phase. Without bars or a
+/// callback, it only logs the steps.
+#[derive(Clone, Default)]
+pub struct Progress {
+    bars: bool,
+    callback: Option<ProgressCallback>,
+    state: Arc<Mutex<ProgressState>>,
+}
+
+impl Progress {
+    /// Draws a progress bar per phase to stderr, if it is a terminal.
+    pub fn bars() -> Self {
+        Self {
+            bars: true,
+            ..Default::default()
+        }
+    }
+
+    /// Calls `callback` for every step and at most about 100 times per phase.
+    pub fn with_callback<F: Fn(&ProgressEvent) + Send + Sync + 'static>(callback: F) -> Self {
+        Self {
+            callback: Some(Arc::new(callback)),
+            ..Default::default()
+        }
+    }
+
+    /// Like `with_callback`, but sends the events to the returned receiver.
+    pub fn channel() -> (Self, Receiver<ProgressEvent>) {
+        let (sender, receiver): (Sender<ProgressEvent>, _) = channel();
+        let sender = Mutex::new(sender);
+        let progress = Self::with_callback(move |event| {
+            let _ = sender.lock().unwrap().send(event.clone());
+        });
+        (progress, receiver)
+    }
+
+    fn emit(&self, event: ProgressEvent) {
+        if let Some(callback) = self.callback.as_ref() {
+            callback(&event);
+        }
+    }
+
+    /// Logs the start of a step and closes the bar of the previous phase.
+    pub(crate) fn step(&self, step: usize, n_steps: usize, title: &str) {
+        if let Some(bar) = self.state.lock().unwrap().bar.take() {
+            bar.finish_and_clear();
+        }
+        ConsoleLogger::new(step, n_steps, title.to_string()).print();
+        self.emit(ProgressEvent::Step {
+            step,
+            n_steps,
+            title: title.to_string(),
+        });
+    }
+
+    pub(crate) fn start(&self, phase: Phase, total: usize) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(bar) = state.bar.take() {
+            bar.finish_and_clear();
+        }
+        if self.bars {
+            let bar = ProgressBar::new(total as u64);
+            bar.set_style(
+                ProgressStyle::with_template("{msg:>16} [{bar:40}] {pos}/{len} ({eta})")
+                    .unwrap()
+                    .progress_chars("=> "),
+            );
+            bar.set_message(phase.to_string());
+            state.bar = Some(bar);
+        }
+        state.phase = Some(phase);
+        state.done = 0;
+        state.total = total;
+        state.reported = 0;
+    }
+
+    /// Counts `n` more processed units of `phase`. Units of other phases than the started one are
+    /// ignored.
+    pub(crate) fn advance(&self, phase: Phase, n: usize) {
+        let mut state = self.state.lock().unwrap();
+        if state.phase != Some(phase) {
+            return;
+        }
+        state.done = (state.done + n).min(state.total);
+        if let Some(bar) = state.bar.as_ref() {
+            bar.set_position(state.done as u64);
+        }
+        let finished = state.done == state.total;
+        if finished {
+            if let Some(bar) = state.bar.take() {
+                bar.finish_and_clear();
+            }
+        }
+
+        let min_step = (state.total / 100).max(1);
+        if finished || state.done - state.reported >= min_step {
+            state.reported = state.done;
+            let event = ProgressEvent::Advanced {
+                phase,
+                done: state.done,
+                total: state.total,
+            };
+            drop(state);
+            self.emit(event);
+        }
+    }
+}
+
+impl Debug for Progress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Progress")
+            .field("bars", &self.bars)
+            .field("callback", &self.callback.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::logging::progress::{Phase, Progress, ProgressEvent};
+
+    #[test]
+    fn reports_at_most_every_percent() {
+        let (progress, receiver) = Progress::channel();
+        progress.step(1, 2, "Scoring");
+        progress.start(Phase::Scoring, 1000);
+        for _ in 0..1000 {
+            progress.clone().advance(Phase::Scoring, 1);
+        }
+        progress.advance(Phase::Pca, 1);
+
+        let events: Vec<ProgressEvent> = receiver.try_iter().collect();
+        assert_eq!(events.len(), 101);
+        assert_eq!(
+            events[0],
+            ProgressEvent::Step {
+                step: 1,
+                n_steps: 2,
+                title: "Scoring".to_string()
+            }
+        );
+        assert_eq!(
+            events[100],
+            ProgressEvent::Advanced {
+                phase: Phase::Scoring,
+                done: 1000,
+                total: 1000
+            }
+        );
+    }
+}
@@ -6,9 +6,10 @@ use actix_telepathy::{AnyAddr, RemoteAddr, Node};
 use ndarray::{ArcArray, Ix3};
 use num_integer::Integer;
 
-pub use geometry::line_plane_intersection;
+pub use geometry::{line_plane_intersection, IntersectionError};
 pub use helper_protocol::HelperProtocol;
 pub use logging::console_logger::ConsoleLogger;
+pub use logging::progress::{Phase, Progress, ProgressEvent};
 pub use ndarray_extensions::*;
 
 use crate::parameters::Parameters;
@@ -18,8 +19,10 @@ pub(crate) mod float_approx;
 mod geometry;
 mod helper_protocol;
 pub(crate) mod itertools;
+pub(crate) mod json;
 pub(crate) mod logging;
 pub(crate) mod ndarray_extensions;
+pub(crate) mod period;
 pub(crate) mod pop_clear;
 pub(crate) mod rotation_protocol;
 
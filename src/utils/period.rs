@@ -0,0 +1,98 @@
+use ndarray::{s, Array1, ArrayView1, ArrayView2, Axis};
+
+/// The autocorrelation is only computed on the beginning of long time series.
+const MAX_SAMPLE_LENGTH: usize = 100_000;
+const MAX_LAG: usize = 1_000;
+/// Peaks with a lower autocorrelation are considered noise.
+const MIN_CORRELATION: f32 = 0.1;
+
+/// Estimates the dominant period of every channel and returns the longest one, so that a pattern
+/// covers the slowest repeating shape. Channels without a clear period are ignored.
+pub(crate) fn dominant_period(data: ArrayView2<f32>) -> Option<usize> {
+    data.axis_iter(Axis(1))
+        .filter_map(channel_period)
+        .map(|(period, _)| period)
+        .max()
+}
+
+/// The lag of the highest autocorrelation peak after the first zero crossing, together with its
+/// autocorrelation. Missing values are treated as the mean of the channel.
+pub(crate) fn channel_period(channel: ArrayView1<f32>) -> Option<(usize, f32)> {
+    let sample = channel.slice(s![..channel.len().min(MAX_SAMPLE_LENGTH)]);
+    let max_lag = MAX_LAG.min(sample.len() / 3);
+    let acf = autocorrelation(sample, max_lag)?;
+
+    let first_negative = acf.iter().position(|c| *c < 0.0)?;
+    (first_negative + 1..max_lag)
+        .filter(|lag| acf[*lag] >= acf[lag - 1] && acf[*lag] >= acf[lag + 1])
+        .map(|lag| (lag, acf[lag]))
+        .filter(|(_, correlation)| *correlation >= MIN_CORRELATION)
+        .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+}
+
+/// The normalized autocorrelation for the lags `0..=max_lag`; `None` for constant channels.
+fn autocorrelation(channel: ArrayView1<f32>, max_lag: usize) -> Option<Array1<f32>> {
+    let known = channel.iter().filter(|x| !x.is_nan());
+    let (sum, count) = known.fold((0.0, 0), |(sum, count), x| (sum + x, count + 1));
+    if count == 0 || max_lag < 2 {
+        return None;
+    }
+    let mean = sum / count as f32;
+    let centered = channel.mapv(|x| if x.is_nan() { 0.0 } else { x - mean });
+
+    let variance = centered.dot(&centered);
+    if variance <= f32::EPSILON {
+        return None;
+    }
+    let n = centered.len();
+    Some(
+        (0..=max_lag)
+            .map(|lag| {
+                centered
+                    .slice(s![..n - lag])
+                    .dot(&centered.slice(s![lag..]))
+                    / variance
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::period::{channel_period, dominant_period};
+    use ndarray::{Array1, Array2, Axis};
+    use std::f32::consts::PI;
+
+    fn sine(period: usize, length: usize) -> Array1<f32> {
+        Array1::range(0., length as f32, 1.).mapv(|x| (2. * PI * x / period as f32).sin())
+    }
+
+    #[test]
+    fn finds_period_of_sine() {
+        let (period, correlation) = channel_period(sine(40, 2000).view()).unwrap();
+        assert_eq!(period, 40);
+        assert!(correlation > 0.9);
+    }
+
+    #[test]
+    fn ignores_constant_channels() {
+        assert_eq!(channel_period(Array1::ones(1000).view()), None);
+    }
+
+    #[test]
+    fn takes_longest_period_of_all_channels() {
+        let mut data = Array2::zeros((3000, 3));
+        data.column_mut(0).assign(&sine(25, 3000));
+        data.column_mut(1).assign(&sine(60, 3000));
+        data.column_mut(2).fill(1.0);
+        assert_eq!(dominant_period(data.view()), Some(60));
+    }
+
+    #[test]
+    fn tolerates_missing_values() {
+        let mut data = sine(30, 1500).insert_axis(Axis(1));
+        data[[100, 0]] = f32::NAN;
+        data[[701, 0]] = f32::NAN;
+        assert_eq!(dominant_period(data.view()), Some(30));
+    }
+}